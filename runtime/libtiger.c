@@ -1,51 +1,100 @@
 #include <stdlib.h>
 #include <stdio.h>
 #include <string.h>
-#include <math.h>
 
-void __prints__(char* s) {
-    printf("%s", s);
+#include "libtiger.h"
+
+static void error(const char* message) {
+    fflush(stdout);
+    fprintf(stderr, "%s\n", message);
+    exit(1);
+}
+
+static string* allocate_string(long size) {
+    string* s = malloc(sizeof(string) + sizeof(char) * size);
+    s->size = size;
+    return s;
+}
+
+void __prints__(string* s) {
+    fwrite(s->data, sizeof(char), s->size, stdout);
 }
 
-void __printi__(int i) {
-    printf("%i", i);
+void __printi__(long i) {
+    printf("%ld", i);
 }
 
 void __flush__() {
     fflush(stdout);
 }
 
-char* __getchar__() {
-    char* c = malloc(sizeof(char) * 2);
-    scanf("%c", c);
-    fflush(stdin);
-    c[1] = '\0';
-    return c;
-}
-
-int __ord__(char* s) {
-    switch (s[0]) {
-    case '1': return 1;
-    case '2': return 2;
-    case '3': return 3;
-    case '4': return 4;
-    case '5': return 5;
-    case '6': return 6;
-    case '7': return 7;
-    case '8': return 8;
-    case '9': return 9;
-    default: return -1;
+string* __getchar__() {
+    int c = getchar();
+    if (c == EOF) {
+        return allocate_string(0);
+    }
+    string* s = allocate_string(1);
+    s->data[0] = (char) c;
+    return s;
+}
+
+long __ord__(string* s) {
+    if (s->size == 0) {
+        return -1;
+    }
+    return (unsigned char) s->data[0];
+}
+
+string* __chr__(long i) {
+    if (i < 0 || i > 255) {
+        error("chr: character out of range");
     }
+    string* s = allocate_string(1);
+    s->data[0] = (char) i;
+    return s;
 }
 
-int __size__(char* s) {
-    return strlen(s);
+long __size__(string* s) {
+    return s->size;
 }
 
-int __not__(int i) {
+string* __substring__(string* s, long first, long n) {
+    if (first < 0 || n < 0 || first > s->size - n) {
+        error("substring: index out of range");
+    }
+    string* t = allocate_string(n);
+    memcpy(t->data, s->data + first, n);
+    return t;
+}
+
+string* __concat__(string* a, string* b) {
+    string* s = allocate_string(a->size + b->size);
+    memcpy(s->data, a->data, a->size);
+    memcpy(s->data + a->size, b->data, b->size);
+    return s;
+}
+
+long __not__(long i) {
     return i == 0;
 }
 
-void __exit__(int i) {
+void __exit__(long i) {
     exit(i);
 }
+
+long* init_array(long size, long init) {
+    long* array = malloc(sizeof(long) * (size > 0 ? size : 1));
+    for (long i = 0; i < size; i++) {
+        array[i] = init;
+    }
+    return array;
+}
+
+long string_compare(string* a, string* b) {
+    long size = a->size < b->size ? a->size : b->size;
+    int order = memcmp(a->data, b->data, size);
+    if (order != 0) {
+        return order;
+    }
+    return (a->size > b->size) - (a->size < b->size);
+}
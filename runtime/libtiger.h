@@ -3,20 +3,36 @@
 
 #include <stdio.h>
 
-void __prints__(char* s);
+/* Tiger strings are a word-size length followed by their bytes. */
+typedef struct {
+    long size;
+    char data[];
+} string;
 
-void __printi__(int i);
+void __prints__(string* s);
+
+void __printi__(long i);
 
 void __flush__();
 
-char* __getchar__();
+string* __getchar__();
+
+long __ord__(string* s);
+
+string* __chr__(long i);
+
+long __size__(string* s);
+
+string* __substring__(string* s, long first, long n);
+
+string* __concat__(string* a, string* b);
 
-int __ord__(char* s);
+long __not__(long i);
 
-int __size__(char* s);
+void __exit__(long i);
 
-int __not__(int i);
+long* init_array(long size, long init);
 
-void __exit__(int i);
+long string_compare(string* a, string* b);
 
 #endif
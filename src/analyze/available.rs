@@ -0,0 +1,121 @@
+use fnv::FnvHashSet;
+
+use analyze::data::{Analysis, Forward, Solution, solve};
+use analyze::flow::Flow;
+use analyze::reaching::defined;
+use ir::*;
+use operand::{Label, Temp};
+
+/// Returns true if [exp] can be recomputed without side effects or memory access.
+fn is_pure(exp: &Exp) -> bool {
+    match exp {
+    | Exp::Const(_)
+    | Exp::Name(_)
+    | Exp::Temp(_)             => true,
+    | Exp::Binop(lhs, _, rhs)  => is_pure(lhs) && is_pure(rhs),
+    | Exp::Mem(_)
+    | Exp::Call(_, _)
+    | Exp::ESeq(_, _)          => false,
+    }
+}
+
+fn mentions(exp: &Exp, temp: Temp) -> bool {
+    match exp {
+    | Exp::Temp(other)        => *other == temp,
+    | Exp::Binop(lhs, _, rhs) => mentions(lhs, temp) || mentions(rhs, temp),
+    | Exp::Mem(exp)           => mentions(exp, temp),
+    | Exp::Call(name, args)   => mentions(name, temp) || args.iter().any(|arg| mentions(arg, temp)),
+    | Exp::ESeq(_, exp)       => mentions(exp, temp),
+    | Exp::Const(_)
+    | Exp::Name(_)            => false,
+    }
+}
+
+/// Collects the pure binary operations computed within [exp].
+fn computed(exp: &Exp, exps: &mut Vec<Exp>) {
+    match exp {
+    | Exp::Binop(lhs, _, rhs) => {
+        computed(lhs, exps);
+        computed(rhs, exps);
+        if is_pure(exp) { exps.push(exp.clone()) }
+    },
+    | Exp::Mem(exp)           => computed(exp, exps),
+    | Exp::Call(name, args)   => {
+        computed(name, exps);
+        for arg in args { computed(arg, exps) }
+    },
+    | Exp::ESeq(_, exp)       => computed(exp, exps),
+    | Exp::Const(_)
+    | Exp::Name(_)
+    | Exp::Temp(_)            => (),
+    }
+}
+
+/// Collects the pure binary operations evaluated by [stm].
+fn evaluated(stm: &Stm) -> Vec<Exp> {
+    let mut exps = Vec::new();
+    match stm {
+    | Stm::Move(src, dst) => {
+        computed(src, &mut exps);
+        if let Exp::Mem(addr) = dst { computed(addr, &mut exps) }
+    },
+    | Stm::Exp(exp)
+    | Stm::Jump(exp, _)           => computed(exp, &mut exps),
+    | Stm::CJump(lhs, _, rhs, _, _) => {
+        computed(lhs, &mut exps);
+        computed(rhs, &mut exps);
+    },
+    | Stm::Seq(_)
+    | Stm::Label(_)
+    | Stm::Comment(_)             => (),
+    }
+    exps
+}
+
+struct Available<'a> {
+    flow: &'a Flow,
+    universe: FnvHashSet<Exp>,
+}
+
+impl <'a> Available<'a> {
+    fn new(flow: &'a Flow) -> Self {
+        let universe = flow.graph().nodes()
+            .flat_map(|block| flow.block(block).iter().flat_map(evaluated))
+            .collect();
+        Available { flow, universe }
+    }
+}
+
+impl <'a> Analysis for Available<'a> {
+    type Node = Label;
+    type Fact = FnvHashSet<Exp>;
+    type Direction = Forward;
+
+    fn boundary(&self) -> Self::Fact {
+        FnvHashSet::default()
+    }
+
+    fn top(&self) -> Self::Fact {
+        self.universe.clone()
+    }
+
+    fn meet(&self, a: &Self::Fact, b: &Self::Fact) -> Self::Fact {
+        a.intersection(b).cloned().collect()
+    }
+
+    fn transfer(&self, block: Label, fact: &Self::Fact) -> Self::Fact {
+        let mut available = fact.clone();
+        for stm in self.flow.block(block) {
+            available.extend(evaluated(stm));
+            if let Some(temp) = defined(stm) {
+                available.retain(|exp| !mentions(exp, temp));
+            }
+        }
+        available
+    }
+}
+
+/// Computes the pure expressions available at the start and end of each block in [flow].
+pub fn available_expressions(flow: &Flow) -> Solution<Label, FnvHashSet<Exp>> {
+    solve(&Available::new(flow), flow.graph(), &[flow.start()])
+}
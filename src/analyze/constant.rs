@@ -0,0 +1,98 @@
+use fnv::FnvHashMap;
+
+use analyze::data::{Analysis, Forward, Solution, solve};
+use analyze::flow::Flow;
+use analyze::reaching::defined;
+use ir::*;
+use operand::{Label, Temp};
+
+/// Value of a temp along all paths reaching a program point.
+///
+/// Temps missing from a fact have not been assigned along any path yet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Constant {
+    Const(i64),
+    Varying,
+}
+
+/// Evaluates [exp] under the constants known in [fact].
+pub fn evaluate(exp: &Exp, fact: &FnvHashMap<Temp, Constant>) -> Constant {
+    match exp {
+    | Exp::Const(n)  => Constant::Const(*n),
+    | Exp::Temp(temp) => fact.get(temp).cloned().unwrap_or(Constant::Varying),
+    | Exp::Binop(lhs, op, rhs) => {
+        match (evaluate(lhs, fact), evaluate(rhs, fact)) {
+        | (Constant::Const(lhs), Constant::Const(rhs)) => {
+            let result = match op {
+            | Binop::Add => Some(lhs.wrapping_add(rhs)),
+            | Binop::Sub => Some(lhs.wrapping_sub(rhs)),
+            | Binop::Mul => Some(lhs.wrapping_mul(rhs)),
+            | Binop::Div => lhs.checked_div(rhs),
+            | Binop::Mod => lhs.checked_rem(rhs),
+            | Binop::And => Some(lhs & rhs),
+            | Binop::Or  => Some(lhs | rhs),
+            | Binop::XOr => Some(lhs ^ rhs),
+            };
+            result.map(Constant::Const).unwrap_or(Constant::Varying)
+        },
+        | _ => Constant::Varying,
+        }
+    },
+    | Exp::Name(_)
+    | Exp::Mem(_)
+    | Exp::Call(_, _)
+    | Exp::ESeq(_, _) => Constant::Varying,
+    }
+}
+
+struct Propagation<'a> {
+    flow: &'a Flow,
+}
+
+impl <'a> Analysis for Propagation<'a> {
+    type Node = Label;
+    type Fact = FnvHashMap<Temp, Constant>;
+    type Direction = Forward;
+
+    fn boundary(&self) -> Self::Fact {
+        FnvHashMap::default()
+    }
+
+    fn top(&self) -> Self::Fact {
+        FnvHashMap::default()
+    }
+
+    fn meet(&self, a: &Self::Fact, b: &Self::Fact) -> Self::Fact {
+        let mut met = a.clone();
+        for (temp, constant) in b {
+            let merged = match met.get(temp) {
+            | None                        => *constant,
+            | Some(other) if other == constant => *constant,
+            | Some(_)                     => Constant::Varying,
+            };
+            met.insert(*temp, merged);
+        }
+        met
+    }
+
+    fn transfer(&self, block: Label, fact: &Self::Fact) -> Self::Fact {
+        let mut constants = fact.clone();
+        for stm in self.flow.block(block) {
+            assign(stm, &mut constants);
+        }
+        constants
+    }
+}
+
+/// Updates [constants] with the value that [stm] assigns, if any.
+pub fn assign(stm: &Stm, constants: &mut FnvHashMap<Temp, Constant>) {
+    if let (Some(temp), Stm::Move(src, _)) = (defined(stm), stm) {
+        let value = evaluate(src, constants);
+        constants.insert(temp, value);
+    }
+}
+
+/// Computes the constant temps at the start and end of each block in [flow].
+pub fn constant_propagation(flow: &Flow) -> Solution<Label, FnvHashMap<Temp, Constant>> {
+    solve(&Propagation { flow }, flow.graph(), &[flow.start()])
+}
@@ -1,25 +1,129 @@
-use std::hash::Hash;
+use std::collections::VecDeque;
 
-use fnv::FnvHashSet;
-use petgraph::{Directed, Direction::*};
-use petgraph::graphmap::{DiGraphMap, NodeTrait, NeighborsDirected};
+use fnv::{FnvHashMap, FnvHashSet};
+use petgraph::Direction::*;
+use petgraph::graphmap::{DiGraphMap, NodeTrait};
 
 pub trait Direction {
-    fn neighbors<'graph, N: NodeTrait>(graph: &'graph DiGraphMap<N, Directed>, node: N) -> NeighborsDirected<'graph, N, Directed>;
+    /// Nodes that facts at [node] flow into.
+    fn neighbors<N: NodeTrait, E>(graph: &DiGraphMap<N, E>, node: N) -> Vec<N>;
+
+    /// Nodes whose facts flow into [node].
+    fn dependencies<N: NodeTrait, E>(graph: &DiGraphMap<N, E>, node: N) -> Vec<N>;
 }
 
 pub struct Forward {}
 
 pub struct Backward {}
 
+/// Nodes with an edge into [node]. Self loops are only stored as outgoing edges.
+fn predecessors<N: NodeTrait, E>(graph: &DiGraphMap<N, E>, node: N) -> Vec<N> {
+    let mut predecessors = graph.neighbors_directed(node, Incoming).collect::<Vec<_>>();
+    if graph.contains_edge(node, node) { predecessors.push(node) }
+    predecessors
+}
+
 impl Direction for Forward {
-    fn neighbors<'graph, N: NodeTrait>(graph: &'graph DiGraphMap<N, Directed>, node: N) -> NeighborsDirected<'graph, N, Directed> {
-        graph.neighbors_directed(node, Outgoing)
+    fn neighbors<N: NodeTrait, E>(graph: &DiGraphMap<N, E>, node: N) -> Vec<N> {
+        graph.neighbors_directed(node, Outgoing).collect()
+    }
+
+    fn dependencies<N: NodeTrait, E>(graph: &DiGraphMap<N, E>, node: N) -> Vec<N> {
+        predecessors(graph, node)
     }
 }
 
 impl Direction for Backward {
-    fn neighbors<'graph, N: NodeTrait>(graph: &'graph DiGraphMap<N, Directed>, node: N) -> NeighborsDirected<'graph, N, Directed> {
-        graph.neighbors_directed(node, Incoming)
+    fn neighbors<N: NodeTrait, E>(graph: &DiGraphMap<N, E>, node: N) -> Vec<N> {
+        predecessors(graph, node)
+    }
+
+    fn dependencies<N: NodeTrait, E>(graph: &DiGraphMap<N, E>, node: N) -> Vec<N> {
+        graph.neighbors_directed(node, Outgoing).collect()
+    }
+}
+
+/// A monotone dataflow analysis over a lattice of facts.
+pub trait Analysis {
+    type Node: NodeTrait;
+    type Fact: Clone + PartialEq;
+    type Direction: Direction;
+
+    /// Fact flowing into boundary nodes: the entry for forward analyses,
+    /// or the exits for backward analyses.
+    fn boundary(&self) -> Self::Fact;
+
+    /// Initial approximation at every node; the top of the lattice.
+    fn top(&self) -> Self::Fact;
+
+    fn meet(&self, a: &Self::Fact, b: &Self::Fact) -> Self::Fact;
+
+    fn transfer(&self, node: Self::Node, fact: &Self::Fact) -> Self::Fact;
+}
+
+/// Fixed point of an analysis, with facts oriented along its direction:
+/// for backward analyses, [input] holds facts at the end of each node.
+#[derive(Debug)]
+pub struct Solution<N: NodeTrait, F> {
+    input: FnvHashMap<N, F>,
+    output: FnvHashMap<N, F>,
+}
+
+impl <N: NodeTrait, F> Solution<N, F> {
+    pub fn input(&self, node: N) -> &F {
+        &self.input[&node]
+    }
+
+    pub fn output(&self, node: N) -> &F {
+        &self.output[&node]
+    }
+}
+
+/// Solves [analysis] over [graph] using a worklist algorithm.
+///
+/// Nodes in [boundary], and nodes without dependencies, receive the boundary fact.
+pub fn solve<A: Analysis, E>(analysis: &A, graph: &DiGraphMap<A::Node, E>, boundary: &[A::Node]) -> Solution<A::Node, A::Fact> {
+
+    let mut input = FnvHashMap::default();
+    let mut output = graph.nodes()
+        .map(|node| (node, analysis.top()))
+        .collect::<FnvHashMap<_, _>>();
+
+    let mut worklist = graph.nodes().collect::<VecDeque<_>>();
+    let mut queued = graph.nodes().collect::<FnvHashSet<_>>();
+
+    while let Some(node) = worklist.pop_front() {
+
+        queued.remove(&node);
+
+        let dependencies = A::Direction::dependencies(graph, node);
+        let initial = if dependencies.is_empty() || boundary.contains(&node) {
+            Some(analysis.boundary())
+        } else {
+            None
+        };
+
+        let fact = dependencies.iter()
+            .fold(initial, |fact, dependency| {
+                match fact {
+                | None       => Some(output[dependency].clone()),
+                | Some(fact) => Some(analysis.meet(&fact, &output[dependency])),
+                }
+            })
+            .expect("Impossible: node has either dependencies or boundary fact");
+
+        let result = analysis.transfer(node, &fact);
+        input.insert(node, fact);
+
+        if result != output[&node] {
+            output.insert(node, result);
+            for neighbor in A::Direction::neighbors(graph, node) {
+                if queued.insert(neighbor) {
+                    worklist.push_back(neighbor);
+                }
+            }
+        }
     }
+
+    Solution { input, output }
 }
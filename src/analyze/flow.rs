@@ -18,6 +18,8 @@ pub struct Flow {
 
 impl Flow {
 
+    /// Splits [ir] into basic blocks at every label, jump and conditional jump.
+    /// Blocks that fall through to a label end with an explicit jump to it.
     pub fn new(start: Label, ir: Vec<ir::Stm>) -> Flow {
         let mut graph = DiGraphMap::default();
         let mut blocks = FnvHashMap::default();
@@ -27,10 +29,26 @@ impl Flow {
 
         for stm in ir {
 
+            // Statements between a jump and the next label can never run
+            if header.is_none() {
+                match stm {
+                | ir::Stm::Label(_) => (),
+                | _                 => continue,
+                }
+            }
+
             match stm {
             | ir::Stm::Label(label) => {
-                let symbol = label;
-                header = Some(symbol);
+
+                // Close the current block with an explicit jump to a label it falls through to
+                if let Some(current) = header.filter(|current| *current != label || !block.is_empty()) {
+                    graph.add_edge(current, label, Void {});
+                    block.push(ir::Stm::Jump(ir::Exp::Name(label), vec![label]));
+                    blocks.insert(current, block);
+                    block = Vec::new();
+                }
+
+                header = Some(label);
                 block.push(stm);
             },
             | ir::Stm::Jump(ir::Exp::Name(label), _) => {
@@ -54,7 +72,7 @@ impl Flow {
                 block.push(stm);
                 blocks.insert(current, block);
                 block = Vec::new();
-                header = Some(f_label);
+                header = None;
             },
             _ => block.push(stm),
             }
@@ -63,6 +81,11 @@ impl Flow {
         let end = header.expect("Internal error: missing end label");
         blocks.insert(end, block);
 
+        // Include blocks without any edges
+        for label in blocks.keys() {
+            graph.add_node(*label);
+        }
+
         let mut height = FnvHashMap::default();
         let mut seen = FnvHashSet::default();
         let mut flow = Flow {
@@ -92,6 +115,10 @@ impl Flow {
         let mut reordered = Vec::new();
         self.trace(self.start(), &mut height, &mut seen);
 
+        // The end block falls through to the epilogue, so it must come last
+        let end = self.end;
+        let mut end_block = self.remove(end).unwrap_or_default();
+
         while !self.blocks.is_empty() {
 
             let mut node_symbol = self.blocks.keys()
@@ -116,6 +143,7 @@ impl Flow {
             }
         }
 
+        reordered.append(&mut end_block);
         reordered
     }
 
@@ -127,6 +155,17 @@ impl Flow {
         self.end
     }
 
+    pub fn graph(&self) -> &DiGraphMap<Label, Void> {
+        &self.graph
+    }
+
+    /// Returns the statements of the block headed by [label], if any.
+    pub fn block(&self, label: Label) -> &[ir::Stm] {
+        self.blocks.get(&label)
+            .map(|block| block.as_slice())
+            .unwrap_or(&[])
+    }
+
     pub fn trace(&self, node: Label, map: &mut FnvHashMap<Label, usize>, seen: &mut FnvHashSet<Label>) -> usize {
         seen.insert(node);
 
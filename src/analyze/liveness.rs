@@ -0,0 +1,254 @@
+use std::fmt::Write;
+
+use fnv::{FnvHashMap, FnvHashSet};
+use petgraph::prelude::*;
+
+use analyze::data::{Analysis, Backward, Solution, solve};
+use asm::*;
+use operand::*;
+use util::Void;
+
+/// Live variable analysis over a single abstract assembly function.
+///
+/// Nodes of the control flow graph are instruction indices into the body.
+#[derive(Debug)]
+pub struct Liveness {
+    graph: DiGraphMap<usize, Void>,
+    defs: Vec<Vec<Temp>>,
+    uses: Vec<Vec<Temp>>,
+    solution: Solution<usize, FnvHashSet<Temp>>,
+}
+
+struct Live<'a> {
+    defs: &'a [Vec<Temp>],
+    uses: &'a [Vec<Temp>],
+}
+
+impl <'a> Analysis for Live<'a> {
+    type Node = usize;
+    type Fact = FnvHashSet<Temp>;
+    type Direction = Backward;
+
+    fn boundary(&self) -> Self::Fact {
+        FnvHashSet::default()
+    }
+
+    fn top(&self) -> Self::Fact {
+        FnvHashSet::default()
+    }
+
+    fn meet(&self, a: &Self::Fact, b: &Self::Fact) -> Self::Fact {
+        a.union(b).cloned().collect()
+    }
+
+    fn transfer(&self, node: usize, live_out: &Self::Fact) -> Self::Fact {
+        live_out.iter()
+            .filter(|temp| !self.defs[node].contains(temp))
+            .chain(self.uses[node].iter())
+            .cloned()
+            .collect()
+    }
+}
+
+impl Liveness {
+
+    pub fn new(body: &[Asm<Temp>]) -> Self {
+
+        let (defs, uses): (Vec<_>, Vec<_>) = body.iter()
+            .map(def_use)
+            .unzip();
+
+        let graph = control_flow(body);
+        let solution = solve(&Live { defs: &defs, uses: &uses }, &graph, &[]);
+
+        Liveness { graph, defs, uses, solution }
+    }
+
+    pub fn len(&self) -> usize {
+        self.defs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.defs.is_empty()
+    }
+
+    pub fn graph(&self) -> &DiGraphMap<usize, Void> {
+        &self.graph
+    }
+
+    pub fn defs(&self, index: usize) -> &[Temp] {
+        &self.defs[index]
+    }
+
+    pub fn uses(&self, index: usize) -> &[Temp] {
+        &self.uses[index]
+    }
+
+    pub fn live_in(&self, index: usize) -> &FnvHashSet<Temp> {
+        self.solution.output(index)
+    }
+
+    pub fn live_out(&self, index: usize) -> &FnvHashSet<Temp> {
+        self.solution.input(index)
+    }
+
+    /// Pretty-prints [body] with the live-in and live-out sets of each instruction.
+    pub fn annotate(&self, body: &[Asm<Temp>]) -> String {
+        let mut annotated = String::new();
+        for (i, stm) in body.iter().enumerate() {
+            writeln!(annotated, "    {:<48} # in: {} out: {}",
+                format!("{}", stm),
+                format_set(self.live_in(i)),
+                format_set(self.live_out(i)),
+            ).expect("Internal error: IO");
+        }
+        annotated
+    }
+}
+
+fn format_set(set: &FnvHashSet<Temp>) -> String {
+    let mut temps = set.iter()
+        .map(|temp| format!("{}", temp))
+        .collect::<Vec<_>>();
+    temps.sort();
+    format!("{{{}}}", temps.join(", "))
+}
+
+/// Builds the control flow graph between instruction indices of [body].
+///
+/// Jumps to labels outside the function contribute no edges.
+pub fn control_flow(body: &[Asm<Temp>]) -> DiGraphMap<usize, Void> {
+
+    let labels = body.iter()
+        .enumerate()
+        .filter_map(|(i, stm)| if let Asm::Label(label) = stm { Some((*label, i)) } else { None })
+        .collect::<FnvHashMap<_, _>>();
+
+    let mut graph = DiGraphMap::default();
+
+    for (i, stm) in body.iter().enumerate() {
+
+        graph.add_node(i);
+
+        let falls_through = match stm {
+        | Asm::Jmp(_) | Asm::Ret => false,
+        | _                      => true,
+        };
+
+        if falls_through && i + 1 < body.len() {
+            graph.add_edge(i, i + 1, Void {});
+        }
+
+        match stm {
+        | Asm::Jmp(label)
+        | Asm::Jcc(_, label) => if let Some(target) = labels.get(label) {
+            graph.add_edge(i, *target, Void {});
+        },
+        | _ => (),
+        }
+    }
+
+    graph
+}
+
+/// Returns true if [temp] is tracked by liveness analysis.
+///
+/// The stack and base pointers are managed manually and never tracked.
+pub fn is_tracked(temp: &Temp) -> bool {
+    match temp {
+    | Temp::Reg(Reg::RSP)
+    | Temp::Reg(Reg::RBP) => false,
+    | _                   => true,
+    }
+}
+
+/// Computes the temps defined and used by [stm], including implicit operands.
+pub fn def_use(stm: &Asm<Temp>) -> (Vec<Temp>, Vec<Temp>) {
+
+    let mut defs = Vec::new();
+    let mut uses = Vec::new();
+
+    {
+        let use_value = |value: Value<Temp>, uses: &mut Vec<Temp>| {
+            match value {
+            | Value::Reg(temp) => uses.push(temp),
+            | Value::Mem(mem)  => uses.push(mem.base()),
+            | Value::Imm(_)    => (),
+            }
+        };
+
+        match stm {
+        | Asm::Mov(bin) => {
+            use_value(bin.source(), &mut uses);
+            match bin.dest() {
+            | Value::Reg(temp) => defs.push(temp),
+            | dest             => use_value(dest, &mut uses),
+            }
+        },
+        | Asm::Bin(_, bin) => {
+            use_value(bin.source(), &mut uses);
+            use_value(bin.dest(), &mut uses);
+            if let Value::Reg(temp) = bin.dest() { defs.push(temp) }
+        },
+        | Asm::Mul(un) => {
+            use_value(un.source(), &mut uses);
+            uses.push(Temp::Reg(Reg::RAX));
+            defs.push(Temp::Reg(Reg::RAX));
+            defs.push(Temp::Reg(Reg::RDX));
+        },
+        | Asm::Div(_, un) => {
+            use_value(un.source(), &mut uses);
+            uses.push(Temp::Reg(Reg::RAX));
+            uses.push(Temp::Reg(Reg::RDX));
+            defs.push(Temp::Reg(Reg::RAX));
+            defs.push(Temp::Reg(Reg::RDX));
+        },
+        | Asm::Un(_, un) => {
+            use_value(un.source(), &mut uses);
+            if let Unary::R(temp) = un { defs.push(*temp) }
+        },
+        | Asm::Pop(un) => {
+            match un {
+            | Unary::R(temp) => defs.push(*temp),
+            | Unary::M(mem)  => uses.push(mem.base()),
+            }
+        },
+        | Asm::Push(un) => use_value(un.source(), &mut uses),
+        | Asm::Lea(mem, temp) => {
+            uses.push(mem.base());
+            defs.push(*temp);
+        },
+        | Asm::Cmp(bin) => {
+            use_value(bin.source(), &mut uses);
+            use_value(bin.dest(), &mut uses);
+        },
+        | Asm::Call(_, args) => {
+            for i in 0..usize::min(*args, 6) {
+                uses.push(Temp::Reg(Reg::get_argument(i)));
+            }
+            for reg in &Reg::CALLER_SAVED {
+                defs.push(Temp::Reg(*reg));
+            }
+        },
+        | Asm::Cqo => {
+            uses.push(Temp::Reg(Reg::RAX));
+            defs.push(Temp::Reg(Reg::RDX));
+        },
+        | Asm::Ret => {
+            uses.push(Temp::Reg(Reg::get_return()));
+            for reg in &Reg::CALLEE_SAVED {
+                uses.push(Temp::Reg(*reg));
+            }
+        },
+        | Asm::Jmp(_)
+        | Asm::Jcc(_, _)
+        | Asm::Label(_)
+        | Asm::Comment(_)
+        | Asm::Direct(_) => (),
+        }
+    }
+
+    defs.retain(is_tracked);
+    uses.retain(is_tracked);
+    (defs, uses)
+}
@@ -1,2 +1,6 @@
 pub mod data;
 pub mod flow;
+pub mod liveness;
+pub mod reaching;
+pub mod available;
+pub mod constant;
@@ -0,0 +1,78 @@
+use fnv::{FnvHashMap, FnvHashSet};
+
+use analyze::data::{Analysis, Forward, Solution, solve};
+use analyze::flow::Flow;
+use ir::*;
+use operand::{Label, Temp};
+
+/// A definition site: the statement at [index] within the block headed by [block].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Definition {
+    pub block: Label,
+    pub index: usize,
+}
+
+/// Returns the temp assigned by [stm], if any.
+pub fn defined(stm: &Stm) -> Option<Temp> {
+    match stm {
+    | Stm::Move(_, Exp::Temp(temp)) => Some(*temp),
+    | _                             => None,
+    }
+}
+
+struct Reaching<'a> {
+    flow: &'a Flow,
+    definitions: FnvHashMap<Temp, FnvHashSet<Definition>>,
+}
+
+impl <'a> Reaching<'a> {
+    fn new(flow: &'a Flow) -> Self {
+        let mut definitions: FnvHashMap<Temp, FnvHashSet<Definition>> = FnvHashMap::default();
+        for block in flow.graph().nodes() {
+            for (index, stm) in flow.block(block).iter().enumerate() {
+                if let Some(temp) = defined(stm) {
+                    definitions.entry(temp)
+                        .or_default()
+                        .insert(Definition { block, index });
+                }
+            }
+        }
+        Reaching { flow, definitions }
+    }
+}
+
+impl <'a> Analysis for Reaching<'a> {
+    type Node = Label;
+    type Fact = FnvHashSet<Definition>;
+    type Direction = Forward;
+
+    fn boundary(&self) -> Self::Fact {
+        FnvHashSet::default()
+    }
+
+    fn top(&self) -> Self::Fact {
+        FnvHashSet::default()
+    }
+
+    fn meet(&self, a: &Self::Fact, b: &Self::Fact) -> Self::Fact {
+        a.union(b).cloned().collect()
+    }
+
+    fn transfer(&self, block: Label, fact: &Self::Fact) -> Self::Fact {
+        let mut reaching = fact.clone();
+        for (index, stm) in self.flow.block(block).iter().enumerate() {
+            if let Some(temp) = defined(stm) {
+                for killed in &self.definitions[&temp] {
+                    reaching.remove(killed);
+                }
+                reaching.insert(Definition { block, index });
+            }
+        }
+        reaching
+    }
+}
+
+/// Computes the definitions reaching the start and end of each block in [flow].
+pub fn reaching_definitions(flow: &Flow) -> Solution<Label, FnvHashSet<Definition>> {
+    solve(&Reaching::new(flow), flow.graph(), &[flow.start()])
+}
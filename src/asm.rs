@@ -34,7 +34,7 @@ pub enum Asm<T: Operand> {
     Cmp(Binary<T>),
     Jmp(Label),
     Jcc(Relop, Label),
-    Call(Label),
+    Call(Label, usize),
     Label(Label),
     Comment(Symbol),
     Direct(Direct),
@@ -42,12 +42,36 @@ pub enum Asm<T: Operand> {
     Ret,
 }
 
+impl <T: Operand> Asm<T> {
+    pub fn map<U: Operand, F: FnMut(T) -> U>(&self, f: &mut F) -> Asm<U> {
+        match self {
+        | Asm::Mov(bin)        => Asm::Mov(bin.map(f)),
+        | Asm::Bin(op, bin)    => Asm::Bin(*op, bin.map(f)),
+        | Asm::Mul(un)         => Asm::Mul(un.map(f)),
+        | Asm::Div(div, un)    => Asm::Div(*div, un.map(f)),
+        | Asm::Un(op, un)      => Asm::Un(*op, un.map(f)),
+        | Asm::Pop(un)         => Asm::Pop(un.map(f)),
+        | Asm::Push(un)        => Asm::Push(un.map(f)),
+        | Asm::Lea(mem, reg)   => Asm::Lea(mem.map(f), f(*reg)),
+        | Asm::Cmp(bin)        => Asm::Cmp(bin.map(f)),
+        | Asm::Jmp(label)      => Asm::Jmp(*label),
+        | Asm::Jcc(op, label)  => Asm::Jcc(*op, *label),
+        | Asm::Call(label, n)  => Asm::Call(*label, *n),
+        | Asm::Label(label)    => Asm::Label(*label),
+        | Asm::Comment(symbol) => Asm::Comment(*symbol),
+        | Asm::Direct(direct)  => Asm::Direct(*direct),
+        | Asm::Cqo             => Asm::Cqo,
+        | Asm::Ret             => Asm::Ret,
+        }
+    }
+}
+
 impl Into<Asm<Reg>> for Asm<Temp> {
     fn into(self) -> Asm<Reg> {
         match self {
         | Asm::Jmp(label)      => Asm::Jmp(label),
         | Asm::Jcc(op, label)  => Asm::Jcc(op, label),
-        | Asm::Call(label)     => Asm::Call(label),
+        | Asm::Call(label, n)  => Asm::Call(label, n),
         | Asm::Label(label)    => Asm::Label(label),
         | Asm::Comment(symbol) => Asm::Comment(symbol),
         | Asm::Direct(direct)  => Asm::Direct(direct),
@@ -95,6 +119,16 @@ impl <T: Operand> Binary<T> {
         | Binary::IM(_, dest) | Binary::RM(_, dest) => Value::Mem(*dest),
         }
     }
+
+    pub fn map<U: Operand, F: FnMut(T) -> U>(&self, f: &mut F) -> Binary<U> {
+        match self {
+        | Binary::IR(imm, reg)     => Binary::IR(*imm, f(*reg)),
+        | Binary::IM(imm, mem)     => Binary::IM(*imm, mem.map(f)),
+        | Binary::RM(reg, mem)     => Binary::RM(f(*reg), mem.map(f)),
+        | Binary::MR(mem, reg)     => Binary::MR(mem.map(f), f(*reg)),
+        | Binary::RR(reg_a, reg_b) => Binary::RR(f(*reg_a), f(*reg_b)),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -110,6 +144,13 @@ impl <T: Operand> Unary<T> {
         | Unary::M(mem) => Value::Mem(*mem),
         }
     }
+
+    pub fn map<U: Operand, F: FnMut(T) -> U>(&self, f: &mut F) -> Unary<U> {
+        match self {
+        | Unary::R(reg) => Unary::R(f(*reg)),
+        | Unary::M(mem) => Unary::M(mem.map(f)),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -188,7 +229,7 @@ impl <T: Operand> fmt::Display for Asm<T> {
         | Asm::Cmp(bin)         => write!(fmt, "cmpq {}", bin),
         | Asm::Jmp(name)        => write!(fmt, "jmp {}", name),
         | Asm::Jcc(op, name)    => write!(fmt, "j{} {}", op,  name),
-        | Asm::Call(name)       => write!(fmt, "call {}", name),
+        | Asm::Call(name, _)    => write!(fmt, "call {}", name),
         | Asm::Cqo              => write!(fmt, "cqo"),
         | Asm::Ret              => write!(fmt, "ret"),
         | Asm::Direct(direct)   => write!(fmt, "{}", direct),
@@ -206,7 +247,21 @@ impl fmt::Display for Direct {
         | Direct::Align(n)      => write!(fmt, ".align {}", n),
         | Direct::Data          => write!(fmt, ".data"),
         | Direct::Text          => write!(fmt, ".text"),
-        | Direct::Str(s)      => write!(fmt, "    .string \"{}\"", s),
+        | Direct::Str(s)        => {
+            let s = s.to_string();
+            write!(fmt, "    .quad {}\n    .ascii \"", s.chars().count())?;
+
+            // Each char stands for a single byte, which the assembler reads in octal
+            for c in s.chars() {
+                match c {
+                | '"' | '\\'  => write!(fmt, "\\{}", c)?,
+                | ' ' ..= '~' => write!(fmt, "{}", c)?,
+                | _           => write!(fmt, "\\{:03o}", c as u32)?,
+                }
+            }
+
+            write!(fmt, "\"")
+        },
         }
     }
 }
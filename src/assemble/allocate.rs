@@ -18,9 +18,9 @@ pub fn allocate<A: Assigner>(unit: Unit<Temp>) -> Unit<Reg> {
     }
 }
 
-pub fn allocate_function<A: Assigner>(asm: Function<Temp>) -> Function<Reg> {
+pub fn allocate_function<A: Assigner>(mut asm: Function<Temp>) -> Function<Reg> {
     let mut allocator = Allocator {
-        assigner: A::new(asm.stack_info.0),
+        assigner: A::new(&mut asm),
         allocated: Vec::new(),
     };
 
@@ -36,7 +36,8 @@ pub fn allocate_function<A: Assigner>(asm: Function<Temp>) -> Function<Reg> {
 pub enum Dir { R, W, RW, }
 
 pub trait Assigner {
-    fn new(stack_size: usize) -> Self;
+    /// Prepares an assignment for [asm], which may be rewritten (e.g. with spill code) beforehand.
+    fn new(asm: &mut Function<Temp>) -> Self;
 
     fn get_stack_size(&self) -> usize;
 
@@ -71,10 +72,16 @@ impl <A: Assigner> Allocator<A> {
 
         let stack_size = self.assigner.get_stack_size();
         let stack_size = if stack_size % 2 == 0 { stack_size } else { stack_size + 1 };
-        let stack_op = Binary::IR(Imm::Int(stack_size as i32 * WORD_SIZE), Reg::RSP);
+        let stack_op = Binary::IR(Imm::Int(stack_size as i64 * WORD_SIZE as i64), Reg::RSP);
 
         self.allocated = mem::replace(&mut self.allocated, Vec::with_capacity(0))
             .into_iter()
+            .filter(|stm| {
+                match stm {
+                | Asm::Mov(Binary::RR(src, dst)) => src != dst,
+                | _ => true,
+                }
+            })
             .map(|stm| {
                 match stm {
                 | Asm::Comment(sym) if sym == sub_rsp => Asm::Bin(Binop::Sub, stack_op),
@@ -135,10 +142,10 @@ pub struct Trivial {
 
 impl Assigner for Trivial {
 
-    fn new(stack_size: usize) -> Self {
+    fn new(asm: &mut Function<Temp>) -> Self {
         Trivial {
             temps: FnvHashMap::default(),
-            stack_size,
+            stack_size: asm.stack_info.0,
             loads: Vec::new(),
             stores: Vec::new(),
         }
@@ -0,0 +1,525 @@
+use std::mem;
+
+use fnv::{FnvHashMap, FnvHashSet};
+
+use config::WORD_SIZE;
+use asm::*;
+use operand::*;
+use analyze::liveness::{Liveness, def_use, is_tracked};
+use assemble::{Assigner, Dir};
+
+/// Registers available for coloring, in order of preference.
+///
+/// Caller-saved registers come first so that callee-saved registers
+/// are only used (and therefore saved) when necessary.
+pub const COLORS: [Reg; 14] = [
+    Reg::RAX,
+    Reg::RCX,
+    Reg::RDX,
+    Reg::RSI,
+    Reg::RDI,
+    Reg::R8,
+    Reg::R9,
+    Reg::R10,
+    Reg::R11,
+    Reg::RBX,
+    Reg::R12,
+    Reg::R13,
+    Reg::R14,
+    Reg::R15,
+];
+
+const K: usize = 14;
+
+/// Register assignment using Appel's iterated register coalescing.
+pub struct Color {
+    colors: FnvHashMap<Temp, Reg>,
+    stack_size: usize,
+}
+
+impl Assigner for Color {
+
+    fn new(asm: &mut Function<Temp>) -> Self {
+
+        let mut stack_size = asm.stack_info.0;
+        let mut introduced = FnvHashSet::default();
+
+        loop {
+            let mut coloring = Coloring::new(&asm.body, &introduced);
+            coloring.color();
+
+            if coloring.spilled.is_empty() {
+                return Color {
+                    colors: coloring.into_colors(),
+                    stack_size,
+                }
+            }
+
+            // Give each spilled temp its own stack slot and retry
+            for temp in coloring.spilled {
+                stack_size += 1;
+                let slot = Mem::RO(Temp::Reg(Reg::RBP), -(stack_size as i32 * WORD_SIZE));
+                asm.body = spill(&asm.body, temp, slot, &mut introduced);
+            }
+        }
+    }
+
+    fn get_stack_size(&self) -> usize {
+        self.stack_size
+    }
+
+    fn store_temps(&mut self, _: &mut Vec<Asm<Reg>>) {}
+
+    fn load_temps(&mut self, _: &mut Vec<Asm<Reg>>) {}
+
+    fn get_temp(&mut self, temp: Temp, _: Dir) -> Reg {
+        match temp {
+        | Temp::Reg(reg) => reg,
+        | _              => *self.colors.get(&temp).expect("Internal error: uncolored temp"),
+        }
+    }
+}
+
+/// Rewrites every occurrence of [temp] in [body] to a fresh temp loaded from
+/// and stored to [slot] around the instruction.
+pub fn spill(body: &[Asm<Temp>], temp: Temp, slot: Mem<Temp>, introduced: &mut FnvHashSet<Temp>) -> Vec<Asm<Temp>> {
+
+    let mut rewritten = Vec::with_capacity(body.len());
+
+    for stm in body {
+
+        let (defs, uses) = def_use(stm);
+
+        if !defs.contains(&temp) && !uses.contains(&temp) {
+            rewritten.push(*stm);
+            continue
+        }
+
+        let fresh = Temp::from_str("SPILL");
+        introduced.insert(fresh);
+
+        if uses.contains(&temp) {
+            rewritten.push(Asm::Mov(Binary::MR(slot, fresh)));
+        }
+
+        rewritten.push(stm.map(&mut |operand| if operand == temp { fresh } else { operand }));
+
+        if defs.contains(&temp) {
+            rewritten.push(Asm::Mov(Binary::RM(fresh, slot)));
+        }
+    }
+
+    rewritten
+}
+
+fn is_precolored(temp: &Temp) -> bool {
+    match temp {
+    | Temp::Reg(_) => true,
+    | _            => false,
+    }
+}
+
+/// Returns the source and destination of a move between two allocatable temps.
+fn get_move(stm: &Asm<Temp>) -> Option<(Temp, Temp)> {
+    match stm {
+    | Asm::Mov(Binary::RR(src, dst)) if is_tracked(src) && is_tracked(dst) => Some((*src, *dst)),
+    | _ => None,
+    }
+}
+
+/// Working state for a single round of iterated register coalescing.
+///
+/// Field names follow Appel, *Modern Compiler Implementation*, chapter 11.
+struct Coloring<'a> {
+    introduced: &'a FnvHashSet<Temp>,
+
+    initial: FnvHashSet<Temp>,
+    simplify_worklist: FnvHashSet<Temp>,
+    freeze_worklist: FnvHashSet<Temp>,
+    spill_worklist: FnvHashSet<Temp>,
+    spilled: FnvHashSet<Temp>,
+    coalesced: FnvHashSet<Temp>,
+    colored: FnvHashSet<Temp>,
+    select_stack: Vec<Temp>,
+    selected: FnvHashSet<Temp>,
+
+    moves: Vec<(Temp, Temp)>,
+    coalesced_moves: FnvHashSet<usize>,
+    constrained_moves: FnvHashSet<usize>,
+    frozen_moves: FnvHashSet<usize>,
+    worklist_moves: FnvHashSet<usize>,
+    active_moves: FnvHashSet<usize>,
+
+    adj_set: FnvHashSet<(Temp, Temp)>,
+    adj_list: FnvHashMap<Temp, Vec<Temp>>,
+    degree: FnvHashMap<Temp, usize>,
+    move_list: FnvHashMap<Temp, FnvHashSet<usize>>,
+    alias: FnvHashMap<Temp, Temp>,
+    color: FnvHashMap<Temp, Reg>,
+    occurrences: FnvHashMap<Temp, usize>,
+}
+
+impl <'a> Coloring<'a> {
+
+    fn new(body: &[Asm<Temp>], introduced: &'a FnvHashSet<Temp>) -> Self {
+        let mut coloring = Coloring {
+            introduced,
+            initial: FnvHashSet::default(),
+            simplify_worklist: FnvHashSet::default(),
+            freeze_worklist: FnvHashSet::default(),
+            spill_worklist: FnvHashSet::default(),
+            spilled: FnvHashSet::default(),
+            coalesced: FnvHashSet::default(),
+            colored: FnvHashSet::default(),
+            select_stack: Vec::new(),
+            selected: FnvHashSet::default(),
+            moves: Vec::new(),
+            coalesced_moves: FnvHashSet::default(),
+            constrained_moves: FnvHashSet::default(),
+            frozen_moves: FnvHashSet::default(),
+            worklist_moves: FnvHashSet::default(),
+            active_moves: FnvHashSet::default(),
+            adj_set: FnvHashSet::default(),
+            adj_list: FnvHashMap::default(),
+            degree: FnvHashMap::default(),
+            move_list: FnvHashMap::default(),
+            alias: FnvHashMap::default(),
+            color: FnvHashMap::default(),
+            occurrences: FnvHashMap::default(),
+        };
+
+        for reg in &COLORS {
+            coloring.color.insert(Temp::Reg(*reg), *reg);
+        }
+
+        coloring.build(body);
+        coloring.make_worklist();
+        coloring
+    }
+
+    fn color(&mut self) {
+        loop {
+            if let Some(temp) = take(&mut self.simplify_worklist) {
+                self.simplify(temp);
+            } else if let Some(index) = take(&mut self.worklist_moves) {
+                self.coalesce(index);
+            } else if let Some(temp) = take(&mut self.freeze_worklist) {
+                self.freeze(temp);
+            } else if !self.spill_worklist.is_empty() {
+                self.select_spill();
+            } else {
+                break
+            }
+        }
+
+        self.assign_colors();
+    }
+
+    fn into_colors(self) -> FnvHashMap<Temp, Reg> {
+        self.color
+    }
+
+    fn degree(&self, temp: &Temp) -> usize {
+        if is_precolored(temp) { return usize::MAX }
+        self.degree.get(temp).cloned().unwrap_or(0)
+    }
+
+    fn build(&mut self, body: &[Asm<Temp>]) {
+
+        let liveness = Liveness::new(body);
+
+        for (i, stm) in body.iter().enumerate() {
+
+            let (defs, uses) = (liveness.defs(i), liveness.uses(i));
+            let mut live = liveness.live_out(i).clone();
+
+            for temp in defs.iter().chain(uses.iter()) {
+                if !is_precolored(temp) {
+                    self.initial.insert(*temp);
+                    *self.occurrences.entry(*temp).or_insert(0) += 1;
+                }
+            }
+
+            // Moves don't cause their source and destination to interfere
+            if let Some(mv) = get_move(stm) {
+                for temp in uses { live.remove(temp); }
+
+                let index = self.moves.len();
+                self.moves.push(mv);
+                self.worklist_moves.insert(index);
+
+                for temp in defs.iter().chain(uses.iter()) {
+                    self.move_list.entry(*temp)
+                        .or_default()
+                        .insert(index);
+                }
+            }
+
+            live.extend(defs.iter().cloned());
+
+            for def in defs {
+                for temp in &live {
+                    self.add_edge(*temp, *def);
+                }
+            }
+        }
+    }
+
+    fn add_edge(&mut self, u: Temp, v: Temp) {
+
+        if u == v || self.adj_set.contains(&(u, v)) { return }
+
+        self.adj_set.insert((u, v));
+        self.adj_set.insert((v, u));
+
+        if !is_precolored(&u) {
+            self.adj_list.entry(u).or_default().push(v);
+            *self.degree.entry(u).or_insert(0) += 1;
+        }
+
+        if !is_precolored(&v) {
+            self.adj_list.entry(v).or_default().push(u);
+            *self.degree.entry(v).or_insert(0) += 1;
+        }
+    }
+
+    fn make_worklist(&mut self) {
+        for temp in mem::take(&mut self.initial) {
+            if self.degree(&temp) >= K {
+                self.spill_worklist.insert(temp);
+            } else if self.is_move_related(&temp) {
+                self.freeze_worklist.insert(temp);
+            } else {
+                self.simplify_worklist.insert(temp);
+            }
+        }
+    }
+
+    fn adjacent(&self, temp: &Temp) -> Vec<Temp> {
+        self.adj_list.get(temp)
+            .map(|adj| {
+                adj.iter()
+                    .filter(|temp| !self.selected.contains(temp) && !self.coalesced.contains(temp))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn node_moves(&self, temp: &Temp) -> Vec<usize> {
+        self.move_list.get(temp)
+            .map(|moves| {
+                moves.iter()
+                    .filter(|index| self.active_moves.contains(index) || self.worklist_moves.contains(index))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn is_move_related(&self, temp: &Temp) -> bool {
+        !self.node_moves(temp).is_empty()
+    }
+
+    fn simplify(&mut self, temp: Temp) {
+        self.select_stack.push(temp);
+        self.selected.insert(temp);
+        for adj in self.adjacent(&temp) {
+            self.decrement_degree(adj);
+        }
+    }
+
+    fn decrement_degree(&mut self, temp: Temp) {
+
+        if is_precolored(&temp) { return }
+
+        let degree = self.degree(&temp);
+        self.degree.insert(temp, degree - 1);
+
+        if degree == K {
+            let mut enable = self.adjacent(&temp);
+            enable.push(temp);
+            self.enable_moves(&enable);
+            self.spill_worklist.remove(&temp);
+            if self.is_move_related(&temp) {
+                self.freeze_worklist.insert(temp);
+            } else {
+                self.simplify_worklist.insert(temp);
+            }
+        }
+    }
+
+    fn enable_moves(&mut self, temps: &[Temp]) {
+        for temp in temps {
+            for index in self.node_moves(temp) {
+                if self.active_moves.remove(&index) {
+                    self.worklist_moves.insert(index);
+                }
+            }
+        }
+    }
+
+    fn coalesce(&mut self, index: usize) {
+
+        let (x, y) = self.moves[index];
+        let (x, y) = (self.get_alias(x), self.get_alias(y));
+        let (u, v) = if is_precolored(&y) { (y, x) } else { (x, y) };
+
+        if u == v {
+            self.coalesced_moves.insert(index);
+            self.add_worklist(u);
+        } else if is_precolored(&v) || self.adj_set.contains(&(u, v)) {
+            self.constrained_moves.insert(index);
+            self.add_worklist(u);
+            self.add_worklist(v);
+        } else if self.can_coalesce(u, v) {
+            self.coalesced_moves.insert(index);
+            self.combine(u, v);
+            self.add_worklist(u);
+        } else {
+            self.active_moves.insert(index);
+        }
+    }
+
+    fn can_coalesce(&self, u: Temp, v: Temp) -> bool {
+        if is_precolored(&u) {
+            // George's criterion
+            self.adjacent(&v)
+                .into_iter()
+                .all(|t| self.degree(&t) < K || is_precolored(&t) || self.adj_set.contains(&(t, u)))
+        } else {
+            // Briggs' criterion
+            let mut adjacent = self.adjacent(&u);
+            adjacent.extend(self.adjacent(&v));
+            adjacent.sort();
+            adjacent.dedup();
+            adjacent.into_iter()
+                .filter(|temp| self.degree(temp) >= K)
+                .count() < K
+        }
+    }
+
+    fn add_worklist(&mut self, temp: Temp) {
+        if !is_precolored(&temp) && !self.is_move_related(&temp) && self.degree(&temp) < K {
+            self.freeze_worklist.remove(&temp);
+            self.simplify_worklist.insert(temp);
+        }
+    }
+
+    fn get_alias(&self, temp: Temp) -> Temp {
+        if self.coalesced.contains(&temp) {
+            self.get_alias(self.alias[&temp])
+        } else {
+            temp
+        }
+    }
+
+    fn combine(&mut self, u: Temp, v: Temp) {
+
+        if !self.freeze_worklist.remove(&v) {
+            self.spill_worklist.remove(&v);
+        }
+
+        self.coalesced.insert(v);
+        self.alias.insert(v, u);
+
+        let v_moves = self.move_list.get(&v).cloned().unwrap_or_default();
+        self.move_list.entry(u)
+            .or_default()
+            .extend(v_moves);
+
+        self.enable_moves(&[v]);
+
+        for t in self.adjacent(&v) {
+            self.add_edge(t, u);
+            self.decrement_degree(t);
+        }
+
+        if self.degree(&u) >= K && self.freeze_worklist.remove(&u) {
+            self.spill_worklist.insert(u);
+        }
+    }
+
+    fn freeze(&mut self, temp: Temp) {
+        self.simplify_worklist.insert(temp);
+        self.freeze_moves(temp);
+    }
+
+    fn freeze_moves(&mut self, u: Temp) {
+        for index in self.node_moves(&u) {
+
+            let (x, y) = self.moves[index];
+            let v = if self.get_alias(y) == self.get_alias(u) {
+                self.get_alias(x)
+            } else {
+                self.get_alias(y)
+            };
+
+            self.active_moves.remove(&index);
+            self.frozen_moves.insert(index);
+
+            if !self.is_move_related(&v) && self.degree(&v) < K && self.freeze_worklist.remove(&v) {
+                self.simplify_worklist.insert(v);
+            }
+        }
+    }
+
+    fn select_spill(&mut self) {
+
+        // Prefer high degree, rarely used temps, and avoid re-spilling spill temps
+        let spill = self.spill_worklist.iter()
+            .max_by_key(|temp| {
+                let introduced = self.introduced.contains(temp);
+                let cost = self.degree(temp) * 1024 / self.occurrences.get(temp).cloned().unwrap_or(1);
+                (!introduced, cost, *temp)
+            })
+            .cloned()
+            .expect("Internal error: empty spill worklist");
+
+        self.spill_worklist.remove(&spill);
+        self.simplify_worklist.insert(spill);
+        self.freeze_moves(spill);
+    }
+
+    fn assign_colors(&mut self) {
+
+        while let Some(temp) = self.select_stack.pop() {
+
+            self.selected.remove(&temp);
+
+            let mut available = COLORS.to_vec();
+
+            for adj in self.adj_list.get(&temp).cloned().unwrap_or_default() {
+                let adj = self.get_alias(adj);
+                if self.colored.contains(&adj) || is_precolored(&adj) {
+                    let color = self.color[&adj];
+                    available.retain(|reg| *reg != color);
+                }
+            }
+
+            match available.first() {
+            | None => {
+                self.spilled.insert(temp);
+            },
+            | Some(reg) => {
+                self.colored.insert(temp);
+                self.color.insert(temp, *reg);
+            },
+            }
+        }
+
+        for temp in self.coalesced.clone() {
+            let alias = self.get_alias(temp);
+            if let Some(reg) = self.color.get(&alias).cloned() {
+                self.color.insert(temp, reg);
+            }
+        }
+    }
+}
+
+/// Removes an arbitrary element from [set].
+fn take<T: Copy + Eq + ::std::hash::Hash>(set: &mut FnvHashSet<T>) -> Option<T> {
+    let next = set.iter().next().cloned();
+    if let Some(next) = next { set.remove(&next); }
+    next
+}
@@ -0,0 +1,198 @@
+use fnv::{FnvHashMap, FnvHashSet};
+
+use config::WORD_SIZE;
+use asm::*;
+use operand::*;
+use assemble::{Assigner, Dir};
+use analyze::liveness::Liveness;
+use assemble::color::{COLORS, spill};
+
+/// Register assignment using Poletto and Sarkar's linear scan.
+///
+/// Trades code quality for speed: live ranges are approximated by a single
+/// interval over the linearized instruction sequence, and no moves are coalesced.
+pub struct Linear {
+    colors: FnvHashMap<Temp, Reg>,
+    stack_size: usize,
+}
+
+impl Assigner for Linear {
+
+    fn new(asm: &mut Function<Temp>) -> Self {
+
+        let mut stack_size = asm.stack_info.0;
+        let mut introduced = FnvHashSet::default();
+
+        loop {
+            let mut scan = Scan::new(&asm.body, &introduced);
+            scan.scan();
+
+            if scan.spilled.is_empty() {
+                return Linear {
+                    colors: scan.colors,
+                    stack_size,
+                }
+            }
+
+            // Give each spilled temp its own stack slot and retry
+            for temp in scan.spilled {
+                stack_size += 1;
+                let slot = Mem::RO(Temp::Reg(Reg::RBP), -(stack_size as i32 * WORD_SIZE));
+                asm.body = spill(&asm.body, temp, slot, &mut introduced);
+            }
+        }
+    }
+
+    fn get_stack_size(&self) -> usize {
+        self.stack_size
+    }
+
+    fn store_temps(&mut self, _: &mut Vec<Asm<Reg>>) {}
+
+    fn load_temps(&mut self, _: &mut Vec<Asm<Reg>>) {}
+
+    fn get_temp(&mut self, temp: Temp, _: Dir) -> Reg {
+        match temp {
+        | Temp::Reg(reg) => reg,
+        | _              => *self.colors.get(&temp).expect("Internal error: unassigned temp"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Interval {
+    temp: Temp,
+    start: usize,
+    end: usize,
+}
+
+/// Working state for a single pass of linear scan.
+struct Scan<'a> {
+    introduced: &'a FnvHashSet<Temp>,
+
+    /// Intervals sorted by increasing start point
+    intervals: Vec<Interval>,
+
+    /// Sorted instruction indices at which each register is fixed
+    fixed: FnvHashMap<Reg, Vec<usize>>,
+
+    /// Intervals currently occupying a register
+    active: Vec<(Interval, Reg)>,
+
+    colors: FnvHashMap<Temp, Reg>,
+    spilled: FnvHashSet<Temp>,
+}
+
+impl <'a> Scan<'a> {
+
+    fn new(body: &[Asm<Temp>], introduced: &'a FnvHashSet<Temp>) -> Self {
+
+        let mut ranges: FnvHashMap<Temp, (usize, usize)> = FnvHashMap::default();
+        let mut fixed: FnvHashMap<Reg, Vec<usize>> = FnvHashMap::default();
+
+        let liveness = Liveness::new(body);
+
+        for i in 0..liveness.len() {
+
+            let present = liveness.defs(i).iter()
+                .chain(liveness.uses(i))
+                .chain(liveness.live_out(i))
+                .cloned()
+                .collect::<FnvHashSet<_>>();
+
+            for temp in present {
+                match temp {
+                | Temp::Reg(reg) => fixed.entry(reg).or_default().push(i),
+                | _ => {
+                    let range = ranges.entry(temp).or_insert((i, i));
+                    range.1 = i;
+                },
+                }
+            }
+        }
+
+        let mut intervals = ranges.into_iter()
+            .map(|(temp, (start, end))| Interval { temp, start, end })
+            .collect::<Vec<_>>();
+
+        // Break ties deterministically so output is stable across runs
+        intervals.sort_by_key(|interval| (interval.start, interval.end, interval.temp));
+
+        Scan {
+            introduced,
+            intervals,
+            fixed,
+            active: Vec::new(),
+            colors: FnvHashMap::default(),
+            spilled: FnvHashSet::default(),
+        }
+    }
+
+    fn scan(&mut self) {
+        for interval in self.intervals.clone() {
+            self.expire(interval.start);
+
+            match self.free_register(&interval) {
+            | Some(reg) => self.assign(interval, reg),
+            | None      => self.spill_at(interval),
+            }
+        }
+    }
+
+    /// Frees registers held by intervals ending before [start].
+    fn expire(&mut self, start: usize) {
+        self.active.retain(|(interval, _)| interval.end >= start);
+    }
+
+    /// Returns true if [reg] is not fixed anywhere within [interval].
+    fn is_unfixed(&self, reg: Reg, interval: &Interval) -> bool {
+        match self.fixed.get(&reg) {
+        | None         => true,
+        | Some(points) => {
+            match points.binary_search(&interval.start) {
+            | Ok(_)  => false,
+            | Err(i) => i == points.len() || points[i] > interval.end,
+            }
+        }
+        }
+    }
+
+    fn free_register(&self, interval: &Interval) -> Option<Reg> {
+        COLORS.iter()
+            .filter(|reg| self.active.iter().all(|(_, used)| used != *reg))
+            .find(|reg| self.is_unfixed(**reg, interval))
+            .cloned()
+    }
+
+    fn assign(&mut self, interval: Interval, reg: Reg) {
+        self.colors.insert(interval.temp, reg);
+        self.active.push((interval, reg));
+    }
+
+    /// Spills either [interval] or the active interval ending last, preferring
+    /// not to spill temps that were themselves introduced by spilling.
+    fn spill_at(&mut self, interval: Interval) {
+
+        let victim = self.active.iter()
+            .enumerate()
+            .filter(|(_, (active, reg))| {
+                !self.introduced.contains(&active.temp)
+                && active.end > interval.end
+                && self.is_unfixed(*reg, &interval)
+            })
+            .max_by_key(|(_, (active, _))| active.end)
+            .map(|(i, _)| i);
+
+        match victim {
+        | Some(i) => {
+            let (active, reg) = self.active.remove(i);
+            self.colors.remove(&active.temp);
+            self.spilled.insert(active.temp);
+            self.assign(interval, reg);
+        },
+        | None => {
+            self.spilled.insert(interval.temp);
+        },
+        }
+    }
+}
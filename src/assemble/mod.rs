@@ -1,5 +1,9 @@
 mod allocate;
+mod color;
+mod linear;
 mod tile;
 
 pub use self::tile::tile;
-pub use self::allocate::{allocate, Assigner, Trivial};
+pub use self::allocate::{allocate, Assigner, Dir, Trivial};
+pub use self::color::Color;
+pub use self::linear::Linear;
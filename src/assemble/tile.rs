@@ -15,6 +15,8 @@ pub fn tile(ir: ir::Unit) -> asm::Unit<Temp> {
                 iter::once(
                         asm::Asm::Direct(asm::Direct::Local(data.label))
                     ).chain(iter::once(
+                        asm::Asm::Direct(asm::Direct::Align(WORD_SIZE))
+                    )).chain(iter::once(
                         asm::Asm::Label(data.label)
                     )).chain(iter::once(
                         asm::Asm::Direct(asm::Direct::Str(data.data))
@@ -32,40 +34,33 @@ pub fn tile_function(ir: ir::Function) -> asm::Function<Temp> {
     let mut tiler = Tiler::default();
     for stm in &ir.body { tiler.tile_stm(stm); }
 
-    let store_rbx = Temp::from_str("STORE_RBX");
-    let store_r12 = Temp::from_str("STORE_R12");
-    let store_r13 = Temp::from_str("STORE_R13");
-    let store_r14 = Temp::from_str("STORE_R14");
-    let store_r15 = Temp::from_str("STORE_R15");
-
     let sub_rsp = store("REPLACE WITH RSP SUBTRACTION");
     let add_rsp = store("REPLACE WITH RSP ADDITION");
 
-    let prologue = vec![
+    let mut prologue = vec![
         asm::Asm::Direct(asm::Direct::Global(ir.label)),
         asm::Asm::Direct(asm::Direct::Align(4)),
         asm::Asm::Label(ir.label),
         asm::Asm::Push(asm::Unary::R(Temp::Reg(Reg::RBP))),
         asm::Asm::Mov(asm::Binary::RR(Temp::Reg(Reg::RSP), Temp::Reg(Reg::RBP))),
         asm::Asm::Comment(sub_rsp),
-        asm::Asm::Mov(asm::Binary::RR(Temp::Reg(Reg::RBX), store_rbx)),
-        asm::Asm::Mov(asm::Binary::RR(Temp::Reg(Reg::R12), store_r12)),
-        asm::Asm::Mov(asm::Binary::RR(Temp::Reg(Reg::R13), store_r13)),
-        asm::Asm::Mov(asm::Binary::RR(Temp::Reg(Reg::R14), store_r14)),
-        asm::Asm::Mov(asm::Binary::RR(Temp::Reg(Reg::R15), store_r15)),
     ];
 
-    let epilogue = vec![
-        asm::Asm::Mov(asm::Binary::RR(store_rbx, Temp::Reg(Reg::RBX))),
-        asm::Asm::Mov(asm::Binary::RR(store_r12, Temp::Reg(Reg::R12))),
-        asm::Asm::Mov(asm::Binary::RR(store_r13, Temp::Reg(Reg::R13))),
-        asm::Asm::Mov(asm::Binary::RR(store_r14, Temp::Reg(Reg::R14))),
-        asm::Asm::Mov(asm::Binary::RR(store_r15, Temp::Reg(Reg::R15))),
+    let mut epilogue = Vec::new();
+
+    // Keep every callee-saved register in a temporary for the body of the function
+    for reg in &Reg::CALLEE_SAVED {
+        let store_reg = Temp::from_symbol(store(&format!("STORE_{:?}", reg)));
+        prologue.push(asm::Asm::Mov(asm::Binary::RR(Temp::Reg(*reg), store_reg)));
+        epilogue.push(asm::Asm::Mov(asm::Binary::RR(store_reg, Temp::Reg(*reg))));
+    }
+
+    epilogue.extend(vec![
         asm::Asm::Comment(add_rsp),
         asm::Asm::Mov(asm::Binary::RR(Temp::Reg(Reg::RBP), Temp::Reg(Reg::RSP))),
         asm::Asm::Pop(asm::Unary::R(Temp::Reg(Reg::RBP))),
         asm::Asm::Ret,
-    ];
+    ]);
 
     asm::Function {
         body: prologue.into_iter()
@@ -144,6 +139,12 @@ impl Tiler {
         use ir::Exp::{Binop, Const};
 
         match exp {
+        // Only moves into registers can take 64-bit immediates
+        | Exp::Const(n) if !is_imm32(*n) => {
+            let temp = Temp::from_str("TILE_IMM64");
+            self.asm.push(asm::Asm::Mov(asm::Binary::IR(Imm::Int(*n), temp)));
+            Value::Reg(temp)
+        },
         | Exp::Const(n) => Value::Imm(Imm::Int(*n)),
         | Exp::Name(l)  => Value::Imm(Imm::Label(*l)),
         | Exp::Temp(t)  => Value::Reg(*t),
@@ -151,18 +152,18 @@ impl Tiler {
 
         // RO memory addressing
         | Exp::Mem(box Binop(box r, ir::Binop::Add, box Const(o)))
-        | Exp::Mem(box Binop(box Const(o), ir::Binop::Add, box r)) => {
+        | Exp::Mem(box Binop(box Const(o), ir::Binop::Add, box r)) if is_imm32(*o) => {
             let r = self.tile_exp(r);
             Value::Mem(Mem::RO(
                 self.into_temp(r),
-                *o
+                *o as i32
             ))
         },
-        | Exp::Mem(box Binop(box r, ir::Binop::Sub, box Const(o))) => {
+        | Exp::Mem(box Binop(box r, ir::Binop::Sub, box Const(o))) if is_imm32(o.wrapping_neg()) => {
             let r = self.tile_exp(r);
             Value::Mem(Mem::RO(
                 self.into_temp(r),
-                -*o
+                o.wrapping_neg() as i32
             ))
         },
 
@@ -296,7 +297,7 @@ impl Tiler {
             }
 
             self.spilled_args = usize::max(self.spilled_args, arg_offset);
-            self.asm.push(asm::Asm::Call(*label));
+            self.asm.push(asm::Asm::Call(*label, args.len()));
             self.asm.push(asm::Asm::Mov(
                 asm::Binary::RR(
                     Temp::Reg(Reg::get_return()),
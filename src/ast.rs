@@ -2,6 +2,7 @@ use std::fmt;
 
 use simple_symbol::Symbol;
 use span::Span;
+use util::escape;
 
 #[derive(Debug)]
 pub enum Dec {
@@ -87,7 +88,7 @@ pub enum Exp {
 
     Var(Var, Span),
 
-    Int(i32, Span),
+    Int(i64, Span),
 
     Str(String, Span),
 
@@ -138,6 +139,7 @@ pub enum Exp {
 
     For {
         name: Symbol,
+        name_span: Span,
         escape: bool,
         lo: Box<Exp>,
         hi: Box<Exp>,
@@ -192,6 +194,36 @@ impl Binop {
         _                                   => false,
         }
     }
+
+    /// How tightly the operator binds, from [LOr] at 0 to multiplicative operators at 4.
+    pub fn precedence(&self) -> usize {
+        match self {
+        | Binop::LOr                                => 0,
+        | Binop::LAnd                               => 1,
+        | Binop::Eq | Binop::Neq | Binop::Lt
+        | Binop::Le | Binop::Gt | Binop::Ge         => 2,
+        | Binop::Add | Binop::Sub                   => 3,
+        | Binop::Mul | Binop::Div | Binop::Mod      => 4,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+        | Binop::Add  => "+",
+        | Binop::Sub  => "-",
+        | Binop::Mul  => "*",
+        | Binop::Div  => "/",
+        | Binop::Mod  => "%",
+        | Binop::Eq   => "=",
+        | Binop::Neq  => "<>",
+        | Binop::Lt   => "<",
+        | Binop::Le   => "<=",
+        | Binop::Gt   => ">",
+        | Binop::Ge   => ">=",
+        | Binop::LAnd => "&",
+        | Binop::LOr  => "|",
+        }
+    }
 }
 
 /// AST pretty printer
@@ -617,7 +649,7 @@ impl DisplayIndent for Exp {
         | Exp::Nil(_)      => { indent!(fmt, level, "nil"); return Ok(()) },
         | Exp::Var(var, _) => { var.display_indent(level, fmt)?; return Ok(()) },
         | Exp::Int(n, _)   => { indent!(fmt, level, n); return Ok(()) },
-        | Exp::Str(s, _)   => { indent!(fmt, level, format!("\"{}\"", s)); return Ok(()) },
+        | Exp::Str(s, _)   => { indent!(fmt, level, format!("\"{}\"", escape(s))); return Ok(()) },
         | _                => (),
         };
 
@@ -708,23 +740,7 @@ impl DisplayIndent for Exp {
 impl DisplayIndent for Binop {
 
     fn display_indent(&self, level: usize, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        let sym = match self {
-        | Binop::Add  => "+",
-        | Binop::Sub  => "-",
-        | Binop::Mul  => "*",
-        | Binop::Div  => "/",
-        | Binop::Mod  => "%",
-        | Binop::Eq   => "=",
-        | Binop::Neq  => "<>",
-        | Binop::Lt   => "<",
-        | Binop::Le   => "<=",
-        | Binop::Gt   => ">",
-        | Binop::Ge   => ">=",
-        | Binop::LAnd => "&",
-        | Binop::LOr  => "|",
-        };
-
-        indent!(fmt, level, sym);
+        indent!(fmt, level, self.symbol());
         Ok(())
     }
 }
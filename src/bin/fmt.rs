@@ -0,0 +1,92 @@
+extern crate structopt;
+extern crate codespan;
+extern crate codespan_reporting;
+extern crate tigerc;
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+use codespan::{CodeMap, FileName};
+use codespan_reporting::emit;
+use codespan_reporting::termcolor::{StandardStream, ColorChoice};
+use structopt::StructOpt;
+
+use tigerc::config::Config;
+use tigerc::format::{Style, format};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "tigerfmt")]
+struct Opt {
+
+    /// Report files that are not formatted instead of printing them, exiting with status 1 if any.
+    #[structopt(long = "check")]
+    check: bool,
+
+    /// Overwrite each file with its formatted source.
+    #[structopt(short = "i", long = "in-place")]
+    in_place: bool,
+
+    /// Number of spaces per level of indentation.
+    #[structopt(long = "indent", default_value = "4")]
+    indent: usize,
+
+    /// Column that lines should stay within where possible.
+    #[structopt(long = "width", default_value = "80")]
+    width: usize,
+
+    /// Accept 64-bit integer literals.
+    #[structopt(long = "int64")]
+    int64: bool,
+
+    /// Files to format, or standard input if there are none.
+    #[structopt(name = "FILE", parse(from_os_str))]
+    files: Vec<PathBuf>,
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    let config = Config { int64: opt.int64, ..Config::default() };
+    let style = Style { indent: opt.indent, width: opt.width };
+    let mut stderr = StandardStream::stderr(ColorChoice::Auto);
+    let mut code = CodeMap::new();
+    let mut exit = 0;
+
+    let files = if opt.files.is_empty() {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source).expect("Internal error: IO");
+        vec![(None, code.add_filemap(FileName::virtual_("<stdin>"), source))]
+    } else {
+        opt.files.iter()
+            .map(|path| (Some(path), code.add_filemap_from_disk(path).expect("Internal error: IO")))
+            .collect()
+    };
+
+    for (path, file) in files {
+        let formatted = match format(file.clone(), config, style) {
+        | Ok(formatted) => formatted,
+        | Err(errs) => {
+            for err in errs {
+                emit(&mut stderr, &code, &err.into()).expect("Internal error: IO");
+            }
+            exit = 1;
+            continue
+        },
+        };
+
+        if opt.check {
+            if formatted != file.src() {
+                println!("{} is not formatted", file.name());
+                exit = 1;
+            }
+        } else if let (true, Some(path)) = (opt.in_place, path) {
+            if formatted != file.src() {
+                fs::write(path, formatted).expect("Internal error: IO");
+            }
+        } else {
+            io::stdout().write_all(formatted.as_bytes()).expect("Internal error: IO");
+        }
+    }
+
+    std::process::exit(exit);
+}
@@ -0,0 +1,14 @@
+extern crate tigerc;
+
+use std::io;
+use std::process;
+
+use tigerc::config::Config;
+use tigerc::lsp::Server;
+
+fn main() {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let code = Server::new(Config::default()).run(&mut stdin.lock(), &mut stdout.lock());
+    process::exit(code)
+}
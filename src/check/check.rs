@@ -1,20 +1,36 @@
-use fnv::{FnvHashSet, FnvHashMap};
+use fnv::{FnvHashMap, FnvHashSet};
+use codespan::ByteIndex;
 use simple_symbol::{store, Symbol};
 
 use ast::*;
+use config::Config;
 use ir;
 use ty::*;
 use operand::Label;
 use check::context::{Binding, VarContext, TypeContext};
 use check::escape::trap_ast;
 use translate::*;
-use error::{Error, TypeError};
+use error::{Error, Lint, Severity, TypeError};
 use span::{Span, IntoSpan};
 
 type Typed = (Ty, ir::Tree);
 
-fn error<T>(span: &Span, err: TypeError) -> Result<T, Error> {
-    Err(Error::semantic(*span, err))
+/// Locations of a function's declaration, for pointing out in errors.
+struct Header {
+    name: Span,
+    args: Vec<Span>,
+}
+
+/// Locations of a record type's declaration, for pointing out in errors.
+struct Record {
+    name: Span,
+    fields: Vec<Span>,
+}
+
+/// Result of an expression that failed to check. Its translation is
+/// never used, since any error prevents the program from compiling.
+fn poison() -> Typed {
+    (Ty::Error, translate_nil())
 }
 
 pub struct Checker {
@@ -22,330 +38,529 @@ pub struct Checker {
     data: Vec<ir::Data>,
     loops: Vec<Label>,
     frames: Vec<Frame>,
+    parents: FnvHashMap<Label, Label>,
+    headers: FnvHashMap<Label, Header>,
+    records: FnvHashMap<usize, Record>,
+    calls: FnvHashMap<ByteIndex, FnvHashSet<Symbol>>,
+    refs: FnvHashMap<ByteIndex, FnvHashSet<Symbol>>,
     vc: VarContext,
     tc: TypeContext,
+    errors: Vec<Error>,
+    lints: Vec<Error>,
+    severity: Option<Severity>,
+    types: Option<Vec<(Span, Ty)>>,
 }
 
 impl Checker {
 
-    pub fn check(ast: &mut Exp) -> Result<ir::Unit, Error> {
+    fn new(config: Config) -> Self {
         let main = Frame::new(
             Label::from_fixed("main"),
             Vec::new(),
         );
 
-        let mut checker = Checker {
+        Checker {
             functions: Vec::new(),
             data: Vec::new(),
             loops: Vec::new(),
             frames: vec![main],
+            parents: FnvHashMap::default(),
+            headers: FnvHashMap::default(),
+            records: FnvHashMap::default(),
+            calls: FnvHashMap::default(),
+            refs: FnvHashMap::default(),
             vc: VarContext::default(),
             tc: TypeContext::default(),
-        };
+            errors: Vec::new(),
+            lints: Vec::new(),
+            severity: config.lint_severity(),
+            types: None,
+        }
+    }
+
+    /// Checks [ast], returning its translation along with any warnings.
+    pub fn check(ast: &mut Exp, config: Config) -> Result<(ir::Unit, Vec<Error>), Vec<Error>> {
+        let mut checker = Checker::new(config);
 
         trap_ast(ast);
 
-        let (_, main_exp) = checker.check_exp(ast)?;
+        let (_, main_exp) = checker.check_exp(ast);
         let main_frame = checker.frames.pop()
             .expect("Internal error: missing frame");
 
+        checker.lints.sort_by_key(|lint| lint.span.start());
+
+        // Lints only stop compilation when promoted to errors
+        let denied = checker.lints.iter().any(|lint| lint.severity == Severity::Error);
+        if !checker.errors.is_empty() || denied {
+            checker.errors.append(&mut checker.lints);
+            return Err(checker.errors)
+        }
+
+        // Discard the program's value so that `main` exits with status 0
         checker.functions.push(
-            ir::Function::new(main_frame, main_exp)
+            ir::Function::new(main_frame, ir::Tree::Nx(main_exp.into()))
         );
 
-        Ok(ir::Unit {
+        let unit = ir::Unit {
             data: checker.data,
             functions: checker.functions,
-        })
+        };
+
+        Ok((unit, checker.lints))
+    }
+
+    /// Checks [ast] without compiling it, returning the type of every variable
+    /// and expression along with all errors and warnings found.
+    pub fn analyze(ast: &mut Exp, config: Config) -> (Vec<(Span, Ty)>, Vec<Error>) {
+        let mut checker = Checker::new(config);
+        checker.types = Some(Vec::new());
+
+        trap_ast(ast);
+        checker.check_exp(ast);
+
+        checker.lints.sort_by_key(|lint| lint.span.start());
+        checker.errors.append(&mut checker.lints);
+        (checker.types.unwrap_or_default(), checker.errors)
+    }
+
+    /// Records an error and continues checking.
+    fn error(&mut self, span: &Span, err: TypeError) {
+        self.errors.push(Error::semantic(*span, err));
     }
 
-    fn check_var(&mut self, var: &Var) -> Result<Typed, Error> {
+    /// Records a lint at the configured severity, if lints are enabled.
+    fn lint(&mut self, span: &Span, lint: Lint) {
+        if let Some(severity) = self.severity {
+            self.lints.push(Error::lint(*span, lint, severity));
+        }
+    }
+
+    /// Records the error in [result], if any, and poisons its type.
+    fn recover(&mut self, result: Result<Ty, Error>) -> Ty {
+        match result {
+        | Ok(ty) => ty,
+        | Err(err) => {
+            self.errors.push(err);
+            Ty::Error
+        },
+        }
+    }
+
+    /// Remembers that [span] has type [ty], if types are being collected.
+    fn record(&mut self, span: Span, ty: &Ty) {
+        if let Some(types) = &mut self.types {
+            types.push((span, ty.clone()));
+        }
+    }
+
+    fn check_var(&mut self, var: &Var) -> Typed {
+        let typed = self.check_var_inner(var);
+        self.record(var.into_span(), &typed.0);
+        typed
+    }
+
+    fn check_var_inner(&mut self, var: &Var) -> Typed {
 
         match var {
         | Var::Simple(name, span) => {
 
-            let var_ty = self.vc.get_var(span, name)?;
-            let exp = translate_simple_var(&self.frames, name);
-            Ok((var_ty, exp))
+            let var_ty = self.vc.get_var(span, name);
+            match self.recover(var_ty) {
+            | Ty::Error => poison(),
+            | var_ty    => (var_ty, translate_simple_var(&self.frames, name)),
+            }
 
         },
         | Var::Field(rec, field, field_span, _) => {
 
-            let (rec_ty, rec_exp) = self.check_var(&*rec)?;
+            let (rec_ty, rec_exp) = self.check_var(&*rec);
 
             // Must be bound to record type
-            match rec_ty {
-            | Ty::Rec(fields, _) => {
+            match &rec_ty {
+            | Ty::Error => poison(),
+            | Ty::Rec(_, fields, id) => {
 
                 // Find corresponding field
-                let field = fields.iter()
+                let found = fields.iter()
                     .enumerate()
                     .find(|(_, (name, _))| field == name)
                     .map(|(i, (_, ty))| (i, self.tc.trace_full(field_span, ty)));
 
                 // Check field type
-                match field {
-                | Some((index, ty)) => Ok((ty?, translate_field_var(rec_exp, index))),
-                | None     => error(field_span, TypeError::UnboundField),
+                match found {
+                | Some((index, ty)) => (self.recover(ty), translate_field_var(rec_exp, index)),
+                | None => {
+                    let declaration = self.records[id].name;
+                    self.error(field_span, TypeError::UnboundField { name: *field, ty: rec_ty.clone(), declaration });
+                    poison()
+                },
                 }
             },
-            | _ => error(&rec.into_span(), TypeError::NotRecord),
+            | _ => {
+                self.error(&rec.into_span(), TypeError::NotRecord(rec_ty.clone()));
+                poison()
+            },
             }
         },
         | Var::Index(arr, index, _) => {
 
-            let (index_ty, index_exp) = self.check_exp(index)?;
+            let (index_ty, index_exp) = self.check_exp(index);
 
             // Index must be integer
             if !index_ty.is_int() {
-                return error(&index.into_span(), TypeError::IndexMismatch)
+                self.error(&index.into_span(), TypeError::IndexMismatch(index_ty));
             }
 
-            let (arr_ty, arr_exp) = self.check_var(&*arr)?;
+            let (arr_ty, arr_exp) = self.check_var(&*arr);
 
             // Get element type
-            if let Ty::Arr(ele_ty, _) = arr_ty {
-                Ok((
-                    *ele_ty.clone(),
-                    translate_index_var(arr_exp, index_exp),
-                ))
-            } else {
-                error(&arr.into_span(), TypeError::NotArr)
+            match arr_ty {
+            | Ty::Error => poison(),
+            | Ty::Arr(_, ele_ty, _) => {
+                let ele_ty = self.tc.trace_full(&arr.into_span(), &ele_ty);
+                (self.recover(ele_ty), translate_index_var(arr_exp, index_exp))
+            },
+            | arr_ty => {
+                self.error(&arr.into_span(), TypeError::NotArr(arr_ty));
+                poison()
+            },
             }
         },
         }
     }
 
-    fn check_exp(&mut self, exp: &Exp) -> Result<Typed, Error> {
+    fn check_exp(&mut self, exp: &Exp) -> Typed {
+        let typed = self.check_exp_inner(exp);
+        self.record(exp.into_span(), &typed.0);
+        typed
+    }
+
+    fn check_exp_inner(&mut self, exp: &Exp) -> Typed {
 
         match exp {
-        | Exp::Nil(_)      => Ok((Ty::Nil, translate_nil())),
-        | Exp::Int(n, _)   => Ok((Ty::Int, translate_int(*n))),
-        | Exp::Str(s, _)   => Ok((Ty::Str, translate_str(&mut self.data, store(s)))),
+        | Exp::Nil(_)      => (Ty::Nil, translate_nil()),
+        | Exp::Int(n, _)   => (Ty::Int, translate_int(*n)),
+        | Exp::Str(s, _)   => (Ty::Str, translate_str(&mut self.data, store(s))),
         | Exp::Var(var, _) => self.check_var(var),
         | Exp::Break(span) => {
             if self.loops.is_empty() {
-                error(span, TypeError::Break)
+                self.error(span, TypeError::Break);
+                poison()
             } else {
-                Ok((Ty::Unit, translate_break(&self.loops)))
+                (Ty::Unit, translate_break(&self.loops))
             }
         },
         | Exp::Call{name, name_span, args, ..} => {
 
             // Get function header
-            let binding = self.vc.get_fun(name_span, name)?;
+            let binding = match self.vc.get_fun(name_span, name) {
+            | Ok(binding) => binding,
+            | Err(err) => {
+
+                // Still look for errors in the arguments
+                self.errors.push(err);
+                for arg in args { self.check_exp(arg); }
+                return poison()
+            },
+            };
 
-            let (arg_tys, ret_ty) = match &binding {
-            | Binding::Fun(arg_tys, ret_ty, _)
-            | Binding::Ext(arg_tys, ret_ty, _) => (arg_tys, ret_ty),
-            | _                                => panic!("Internal error: not function"),
+            let (arg_tys, ret_ty, label) = match &binding {
+            | Binding::Fun(arg_tys, ret_ty, label)
+            | Binding::Ext(arg_tys, ret_ty, label) => (arg_tys, ret_ty, label),
+            | _                                    => panic!("Internal error: not function"),
             };
 
             // Check number of arguments
             if args.len() != arg_tys.len() {
-                return error(name_span, TypeError::CallCountMismatch)
+                let err = TypeError::CallCountMismatch {
+                    name: *name,
+                    expected: arg_tys.len(),
+                    found: args.len(),
+                    declaration: self.headers.get(label).map(|header| header.name),
+                };
+                self.error(name_span, err);
+                for arg in args { self.check_exp(arg); }
+                return (ret_ty.clone(), translate_nil())
             }
 
             let mut arg_exps = Vec::new();
 
             // Check that each argument subtypes formal parameter type
-            for (arg, ty) in args.iter().zip(arg_tys) {
+            for (i, (arg, ty)) in args.iter().zip(arg_tys).enumerate() {
 
-                let (arg_ty, arg_exp) = self.check_exp(arg)?;
+                let (arg_ty, arg_exp) = self.check_exp(arg);
 
                 if !arg_ty.subtypes(&ty) {
-                    return error(&arg.into_span(), TypeError::CallTypeMismatch)
+                    let err = TypeError::CallTypeMismatch {
+                        name: *name,
+                        expected: ty.clone(),
+                        found: arg_ty,
+                        declaration: self.headers.get(label).map(|header| header.args[i]),
+                    };
+                    self.error(&arg.into_span(), err);
                 }
 
                 arg_exps.push(arg_exp);
             }
 
-            Ok((ret_ty.clone(), translate_call(&binding, arg_exps)))
+            let parent = match &binding {
+            | Binding::Fun(_, _, label) => self.parents.get(label).cloned(),
+            | _                         => None,
+            };
+
+            (ret_ty.clone(), translate_call(&self.frames, parent, &binding, arg_exps))
         },
         | Exp::Neg(neg, span) => {
 
-            let (neg_ty, neg_exp) = self.check_exp(neg)?;
+            let (neg_ty, neg_exp) = self.check_exp(neg);
 
             // Unary negation only works on integers
             if !neg_ty.is_int() {
-                return error(span, TypeError::Neg)
+                self.error(span, TypeError::Neg(neg_ty));
             }
 
-            Ok((Ty::Int, translate_neg(neg_exp)))
+            (Ty::Int, translate_neg(neg_exp))
         },
         | Exp::Bin{lhs, op, op_span, rhs, span} => {
 
-            let (lhs_ty, lhs_exp) = self.check_exp(lhs)?;
-            let (rhs_ty, rhs_exp) = self.check_exp(rhs)?;
+            let (lhs_ty, lhs_exp) = self.check_exp(lhs);
+            let (rhs_ty, rhs_exp) = self.check_exp(rhs);
+
+            // Every binary operator results in an integer
+            if lhs_ty.is_error() || rhs_ty.is_error() {
+                return (Ty::Int, translate_nil())
+            }
 
             // No binary operators work on unit
             if lhs_ty == Ty::Unit {
-                return error(&lhs.into_span(), TypeError::BinaryUnit)
+                self.error(&lhs.into_span(), TypeError::BinaryUnit);
+                return (Ty::Int, translate_nil())
             } else if rhs_ty == Ty::Unit {
-                return error(&rhs.into_span(), TypeError::BinaryUnit)
+                self.error(&rhs.into_span(), TypeError::BinaryUnit);
+                return (Ty::Int, translate_nil())
             }
 
             // Equality checking is valid for any L<>R, L=R where R: L
             if op.is_equality() && (lhs_ty.subtypes(&rhs_ty) || rhs_ty.subtypes(&lhs_ty)) {
                 return if lhs_ty == Ty::Nil && rhs_ty == Ty::Nil {
-                    error(span, TypeError::BinaryNil)
+                    self.error(span, TypeError::BinaryNil);
+                    (Ty::Int, translate_nil())
+                } else if lhs_ty == Ty::Str && rhs_ty == Ty::Str {
+                    (Ty::Int, translate_str_bin(lhs_exp, *op, rhs_exp))
                 } else {
-                    Ok((Ty::Int, translate_bin(lhs_exp, *op, rhs_exp)))
+                    (Ty::Int, translate_bin(lhs_exp, *op, rhs_exp))
                 }
             }
 
             // Comparisons are valid for
             // - Str and Str
             // - Int and Int
-            if op.is_comparison() && (lhs_ty == Ty::Int || lhs_ty == Ty::Str) && lhs_ty == rhs_ty {
-                return Ok((Ty::Int, translate_bin(lhs_exp, *op, rhs_exp)))
+            if op.is_comparison() && lhs_ty == Ty::Str && rhs_ty == Ty::Str {
+                return (Ty::Int, translate_str_bin(lhs_exp, *op, rhs_exp))
+            }
+
+            if op.is_comparison() && lhs_ty == Ty::Int && rhs_ty == Ty::Int {
+                return (Ty::Int, translate_bin(lhs_exp, *op, rhs_exp))
             }
 
             // Arithmetic is valid for
             // - Int and Int
             if lhs_ty == Ty::Int && rhs_ty == Ty::Int {
-                return Ok((Ty::Int, translate_bin(lhs_exp, *op, rhs_exp)))
+                return (Ty::Int, translate_bin(lhs_exp, *op, rhs_exp))
             }
 
-            error(op_span, TypeError::BinaryMismatch)
+            self.error(op_span, TypeError::BinaryMismatch { op: *op, lhs: lhs_ty, rhs: rhs_ty });
+            (Ty::Int, translate_nil())
         },
         | Exp::Rec{name, name_span, fields, span} => {
 
-            let rec_ty = self.tc.get_full(name_span, name)?;
-
-            let field_exps = match &rec_ty {
-            | Ty::Rec(field_tys, _) => {
+            let rec_ty = self.tc.get_full(name_span, name);
+            let rec_ty = self.recover(rec_ty);
 
-                if fields.len() != field_tys.len() {
-                    return error(span, TypeError::FieldCountMismatch)
-                }
+            let (field_tys, id) = match &rec_ty {
+            | Ty::Rec(_, field_tys, id) => (field_tys, id),
+            | ty => {
+                if !ty.is_error() { self.error(name_span, TypeError::NotRecord(ty.clone())); }
+                for field in fields { self.check_exp(&field.exp); }
+                return poison()
+            },
+            };
 
-                // Make sure all record fields are fully resolved
-                let field_tys = field_tys.iter()
-                    .map(|(name, ty)| (name, self.tc.trace_full(span, ty)))
-                    .collect::<Vec<_>>();
+            if fields.len() != field_tys.len() {
+                let err = TypeError::FieldCountMismatch {
+                    name: *name,
+                    expected: field_tys.len(),
+                    found: fields.len(),
+                    declaration: self.records[id].name,
+                };
+                self.error(span, err);
+                for field in fields { self.check_exp(&field.exp); }
+                return (rec_ty.clone(), translate_nil())
+            }
 
-                let mut field_exps = Vec::new();
+            // Make sure all record fields are fully resolved
+            let field_tys = field_tys.iter()
+                .map(|(name, ty)| (name, self.tc.trace_full(span, ty)))
+                .collect::<Vec<_>>();
 
-                // Check all field name - value pairs
-                for (field, (field_name, field_ty)) in fields.iter().zip(field_tys) {
+            let mut field_exps = Vec::new();
 
-                    let (field_exp_ty, field_exp) = self.check_exp(&*field.exp)?;
+            // Check all field name - value pairs
+            for (i, (field, (field_name, field_ty))) in fields.iter().zip(field_tys).enumerate() {
 
-                    if &field.name != field_name {
-                        return error(&field.name_span, TypeError::FieldNameMismatch)
-                    }
+                let (field_exp_ty, field_exp) = self.check_exp(&*field.exp);
+                let declaration = self.records[id].fields[i];
 
-                    if !field_exp_ty.subtypes(&field_ty?) {
-                        return error(&field.exp.into_span(), TypeError::FieldTypeMismatch)
-                    }
+                if &field.name != field_name {
+                    let err = TypeError::FieldNameMismatch {
+                        expected: *field_name,
+                        found: field.name,
+                        declaration,
+                    };
+                    self.error(&field.name_span, err);
+                }
 
-                    field_exps.push(field_exp);
+                let field_ty = self.recover(field_ty);
+                if !field_exp_ty.subtypes(&field_ty) {
+                    let err = TypeError::FieldTypeMismatch {
+                        name: *field_name,
+                        expected: field_ty,
+                        found: field_exp_ty,
+                        declaration,
+                    };
+                    self.error(&field.exp.into_span(), err);
                 }
 
-                field_exps
-            },
-            | _ => return error(name_span, TypeError::NotRecord),
-            };
+                field_exps.push(field_exp);
+            }
 
-            Ok((rec_ty, translate_rec(field_exps)))
+            (rec_ty.clone(), translate_rec(field_exps))
         },
         | Exp::Seq(statements, _) => {
 
             // Empty sequence is just unit
-            if statements.len() == 0 { return Ok((Ty::Unit, translate_nil())) }
+            if statements.len() == 0 { return (Ty::Unit, translate_nil()) }
 
             let mut statement_exps = Vec::new();
 
             // Check intermediate expressions
             for i in 0..statements.len() - 1 {
-                let (_, statement_exp) = self.check_exp(&statements[i])?;
+                let (_, statement_exp) = self.check_exp(&statements[i]);
                 statement_exps.push(statement_exp);
             }
 
+            // Nothing after a break is ever evaluated
+            for (statement, next) in statements.iter().zip(&statements[1..]) {
+                if let Exp::Break(_) = statement {
+                    self.lint(&next.into_span(), Lint::Unreachable);
+                    break
+                }
+            }
+
             // Result is type of last exp
-            let (result_ty, result_exp) = self.check_exp(&statements.last().unwrap())?;
+            let (result_ty, result_exp) = self.check_exp(&statements.last().unwrap());
 
             statement_exps.push(result_exp);
 
-            Ok((result_ty, translate_seq(statement_exps)))
+            (result_ty, translate_seq(statement_exps))
 
         },
         | Exp::Ass{name, exp, ..} => {
 
-            let (lhs_ty, lhs_exp) = self.check_var(name)?;
-            let (rhs_ty, rhs_exp) = self.check_exp(exp)?;
+            let (lhs_ty, lhs_exp) = self.check_var(name);
+            let (rhs_ty, rhs_exp) = self.check_exp(exp);
 
             if !rhs_ty.subtypes(&lhs_ty) {
-                return error(&exp.into_span(), TypeError::VarMismatch)
+                let err = TypeError::VarMismatch { expected: lhs_ty, found: rhs_ty, declaration: None };
+                self.error(&exp.into_span(), err);
             }
 
-            Ok((Ty::Unit, translate_ass(lhs_exp, rhs_exp)))
+            (Ty::Unit, translate_ass(lhs_exp, rhs_exp))
         },
         | Exp::If{guard, then, or, ..} => {
 
-            let (guard_ty, guard_exp) = self.check_exp(guard)?;
-            let (then_ty, then_exp) = self.check_exp(then)?;
+            let errors = self.errors.len();
+            let (guard_ty, guard_exp) = self.check_exp(guard);
+            let guard_ok = self.errors.len() == errors;
+            let (then_ty, then_exp) = self.check_exp(then);
 
             // Guard must be boolean
             if !guard_ty.is_int() {
-                return error(&guard.into_span(), TypeError::GuardMismatch)
+                self.error(&guard.into_span(), TypeError::GuardMismatch(guard_ty.clone()));
             }
 
+            // Warn about guards that fold down to a constant, unless they
+            // were translated to placeholders because of errors
+            let guard_exp = if guard_ty == Ty::Int {
+                let guard_cond: ir::Cond = guard_exp.into();
+                match fold_cond(&guard_cond) {
+                | Some(value) if guard_ok => self.lint(&guard.into_span(), Lint::ConstantGuard(value)),
+                | _                       => (),
+                }
+                guard_cond.into()
+            } else {
+                guard_exp
+            };
+
             if let Some(exp) = or {
 
                 // For if-else, both branches must return the same type
-                let (or_ty, or_exp) = self.check_exp(&*exp)?;
+                let (or_ty, or_exp) = self.check_exp(&*exp);
 
                 if !then_ty.subtypes(&or_ty) && !or_ty.subtypes(&then_ty) {
-                    return error(&exp.into_span(), TypeError::BranchMismatch)
+                    let err = TypeError::BranchMismatch { then: then_ty, or: or_ty, then_span: then.into_span() };
+                    self.error(&exp.into_span(), err);
+                    return poison()
                 }
 
-                Ok((then_ty, translate_if(guard_exp, then_exp, Some(or_exp))))
+                // Prefer the branch that checked, if any
+                let ty = if then_ty.is_error() { or_ty } else { then_ty };
+                (ty, translate_if(guard_exp, then_exp, Some(or_exp)))
 
             } else {
 
                 // For if, branch must have no expression
-                if then_ty != Ty::Unit {
-                    return error(&then.into_span(), TypeError::UnusedBranch)
+                if !then_ty.is_unit() {
+                    self.error(&then.into_span(), TypeError::UnusedBranch(then_ty));
                 }
 
-                Ok((Ty::Unit, translate_if(guard_exp, then_exp, None)))
+                (Ty::Unit, translate_if(guard_exp, then_exp, None))
             }
         },
         | Exp::While{guard, body, ..} => {
 
-            let (guard_ty, guard_exp) = self.check_exp(guard)?;
+            let (guard_ty, guard_exp) = self.check_exp(guard);
 
             // Guard must be boolean
             if !guard_ty.is_int() {
-                return error(&guard.into_span(), TypeError::GuardMismatch)
+                self.error(&guard.into_span(), TypeError::GuardMismatch(guard_ty));
             }
 
             // Enter loop body
             let s_label = Label::from_str("START_WHILE");
             self.loops.push(s_label);
-            let (body_ty, body_exp) = self.check_exp(body)?;
+            let (body_ty, body_exp) = self.check_exp(body);
             self.loops.pop().expect("Internal error: missing loop");
 
             // Body must be unit
             if !body_ty.is_unit() {
-                return error(&body.into_span(), TypeError::UnusedWhileBody)
+                self.error(&body.into_span(), TypeError::UnusedWhileBody(body_ty));
             }
 
-            Ok((Ty::Unit, translate_while(s_label, guard_exp, body_exp)))
+            (Ty::Unit, translate_while(s_label, guard_exp, body_exp))
         },
-        | Exp::For{name, escape, lo, hi, body, ..} => {
+        | Exp::For{name, name_span, escape, lo, hi, body, ..} => {
 
-            let (lo_ty, lo_exp) = self.check_exp(lo)?;
-            let (hi_ty, hi_exp) = self.check_exp(hi)?;
+            let (lo_ty, lo_exp) = self.check_exp(lo);
+            let (hi_ty, hi_exp) = self.check_exp(hi);
 
             if !lo_ty.is_int() {
-                return error(&lo.into_span(), TypeError::ForBound)
+                self.error(&lo.into_span(), TypeError::ForBound(lo_ty));
             }
 
             if !hi_ty.is_int() {
-                return error(&hi.into_span(), TypeError::ForBound)
+                self.error(&hi.into_span(), TypeError::ForBound(hi_ty));
             }
 
             // Enter loop body with new environment and binding
@@ -354,20 +569,21 @@ impl Checker {
 
             self.vc.push();
             self.vc.insert(*name, Binding::Var(Ty::Int));
+            self.record(*name_span, &Ty::Int);
             self.loops.push(label);
 
             // Check body with updated VarContext
-            let (body_ty, body_exp) = self.check_exp(&*body)?;
+            let (body_ty, body_exp) = self.check_exp(&*body);
 
             if !body_ty.is_unit() {
-                return error(&body.into_span(), TypeError::UnusedForBody)
+                self.error(&body.into_span(), TypeError::UnusedForBody(body_ty));
             }
 
             // Pop environment
             self.vc.pop();
             self.loops.pop().expect("Internal error: missing loop");
 
-            Ok((Ty::Unit, translate_for(label, index_exp, lo_exp, hi_exp, body_exp)))
+            (Ty::Unit, translate_for(label, index_exp, lo_exp, hi_exp, body_exp))
         },
         | Exp::Let{decs, body, ..} => {
 
@@ -378,87 +594,187 @@ impl Checker {
             let mut dec_exps = Vec::new();
 
             for dec in decs {
-                if let Some(dec_exp) = self.check_dec(&*dec)? {
+                if let Some(dec_exp) = self.check_dec(&*dec) {
                     dec_exps.push(dec_exp);
                 }
             }
 
-            let (body_ty, body_exp) = self.check_exp(&*body)?;
+            let (body_ty, body_exp) = self.check_exp(&*body);
 
-            self.vc.pop();
-            self.tc.pop();
+            let used_vars = self.vc.pop();
+            let used_types = self.tc.pop();
+            self.lint_unused(decs, &used_vars, &used_types);
 
-            Ok((body_ty, translate_let(dec_exps, body_exp)))
+            (body_ty, translate_let(dec_exps, body_exp))
         },
         | Exp::Arr{name, name_span, size, init, ..} => {
 
+            let arr_ty = self.tc.get_full(name_span, name);
+            let arr_ty = self.recover(arr_ty);
+
             // Look up element type
-            let elem = match self.tc.get_full(name_span, name)? {
-            | Ty::Arr(elem, _) => *elem,
-            | _                => return error(name_span, TypeError::NotArr),
+            let elem = match &arr_ty {
+            | Ty::Arr(_, elem, _) => {
+                let elem = self.tc.trace_full(name_span, elem);
+                Some(self.recover(elem))
+            },
+            | Ty::Error           => None,
+            | ty                  => {
+                self.error(name_span, TypeError::NotArr(ty.clone()));
+                None
+            },
             };
 
-            let (size_ty, size_exp) = self.check_exp(&*size)?;
+            let (size_ty, size_exp) = self.check_exp(&*size);
 
             // Size must be integer
             if !size_ty.is_int() {
-                return error(&size.into_span(), TypeError::ArrSize)
+                self.error(&size.into_span(), TypeError::ArrSize(size_ty));
             }
 
-            let (init_ty, init_exp) = self.check_exp(&*init)?;
+            let (init_ty, init_exp) = self.check_exp(&*init);
+
+            let elem = match elem {
+            | Some(elem) => elem,
+            | None       => return poison(),
+            };
 
             // Initialization expression must subtype element type
             if !init_ty.subtypes(&elem) {
-                return error(&init.into_span(), TypeError::ArrMismatch)
+                self.error(&init.into_span(), TypeError::ArrMismatch { expected: elem, found: init_ty });
             }
 
-            Ok((self.tc.get_full(name_span, name)?, translate_arr(size_exp, init_exp)))
+            (arr_ty, translate_arr(size_exp, init_exp))
         },
         }
     }
 
-    fn check_unique(names: impl Iterator<Item = (Symbol, Span)>) -> Result<(), Error> {
-        let mut unique = FnvHashSet::default();
+    /// Warns about declarations that are never referred to in their `let`.
+    fn lint_unused(&mut self, decs: &[Dec], used_vars: &FnvHashSet<Symbol>, used_types: &FnvHashSet<Symbol>) {
+        for dec in decs {
+            match dec {
+            | Dec::Var{name, name_span, ..} => {
+                if !used_vars.contains(name) { self.lint(name_span, Lint::UnusedVar(*name)) }
+            },
+            | Dec::Fun(funs, _) => {
+
+                // Functions are only used if reachable from outside their group
+                let mut reached = funs.iter()
+                    .map(|fun| fun.name)
+                    .filter(|name| used_vars.contains(name))
+                    .collect::<FnvHashSet<_>>();
+
+                let mut stack = reached.iter().cloned().collect::<Vec<_>>();
+                while let Some(name) = stack.pop() {
+                    for fun in funs.iter().filter(|fun| fun.name == name) {
+                        for call in &self.calls[&fun.name_span.start()] {
+                            if reached.insert(*call) { stack.push(*call) }
+                        }
+                    }
+                }
+
+                for fun in funs {
+                    if !reached.contains(&fun.name) { self.lint(&fun.name_span, Lint::UnusedFun(fun.name)) }
+                }
+            },
+            | Dec::Type(decs, _) => {
+
+                // Types are only used if reachable from outside their group
+                let mut reached = decs.iter()
+                    .map(|dec| dec.name)
+                    .filter(|name| used_types.contains(name))
+                    .collect::<FnvHashSet<_>>();
+
+                let mut stack = reached.iter().cloned().collect::<Vec<_>>();
+                while let Some(name) = stack.pop() {
+                    for dec in decs.iter().filter(|dec| dec.name == name) {
+                        for reference in &self.refs[&dec.name_span.start()] {
+                            if reached.insert(*reference) { stack.push(*reference) }
+                        }
+                    }
+                }
+
+                for dec in decs {
+                    if !reached.contains(&dec.name) { self.lint(&dec.name_span, Lint::UnusedType(dec.name)) }
+                }
+            },
+            }
+        }
+    }
+
+    /// Warns about declarations that hide a function from the standard library.
+    fn lint_builtin(&mut self, name: &Symbol, span: &Span) {
+        if self.vc.is_builtin(name) { self.lint(span, Lint::ShadowBuiltin(*name)) }
+    }
+
+    fn check_unique(&mut self, names: impl Iterator<Item = (Symbol, Span)>) {
+        let mut unique = FnvHashMap::default();
         for (name, name_span) in names {
-            if unique.contains(&name) { return error(&name_span, TypeError::DecConflict) }
-            unique.insert(name);
+            match unique.get(&name) {
+            | Some(previous) => self.error(&name_span, TypeError::DecConflict { name, previous: *previous }),
+            | None           => { unique.insert(name, name_span); },
+            }
         }
-        Ok(())
     }
 
-    fn check_dec(&mut self, dec: &Dec) -> Result<Option<ir::Tree>, Error> {
+    fn check_dec(&mut self, dec: &Dec) -> Option<ir::Tree> {
         match dec {
         | Dec::Fun(funs, _) => {
 
             // Make sure all top-level names are unique
-            Self::check_unique(funs.iter().map(|fun| (fun.name, fun.name_span)))?;
+            self.check_unique(funs.iter().map(|fun| (fun.name, fun.name_span)));
 
             let mut labels = FnvHashMap::default();
+            let mut headers = Vec::new();
 
             // Initialize top-level bindings
             for fun in funs {
 
                 let label = Label::from_symbol(fun.name);
                 labels.insert(fun.name, label);
+
+                self.lint_builtin(&fun.name, &fun.name_span);
+                for arg in &fun.args { self.lint_builtin(&arg.name, &arg.name_span); }
+
+                // Remember enclosing frame for static links
+                let parent = self.frames.last()
+                    .expect("Internal error: missing frame")
+                    .label();
+                self.parents.insert(label, parent);
                 let mut args = Vec::new();
 
                 // Get formal parameter types
                 for arg in &fun.args {
-                    args.push(self.tc.get_full(&arg.name_span, &arg.ty)?);
+                    let arg_ty = self.tc.get_full(&arg.name_span, &arg.ty);
+                    args.push(self.recover(arg_ty));
                 }
 
                 // Get return type
                 let ret = match &fun.rets {
                 | None => Ty::Unit,
-                | Some(name) => self.tc.get_full(&fun.rets_span.unwrap(), name)?,
+                | Some(name) => {
+                    let ret = self.tc.get_full(&fun.rets_span.unwrap(), name);
+                    self.recover(ret)
+                },
                 };
 
+                // Remember where the header was declared
+                self.headers.insert(label, Header {
+                    name: fun.name_span,
+                    args: fun.args.iter().map(|arg| arg.span).collect(),
+                });
+
                 // Update environment with function header
+                headers.push((args.clone(), ret.clone()));
                 self.vc.insert(fun.name, Binding::Fun(args, ret, label));
             }
 
+            // Set aside lookups from before the group, so that calls
+            // between its own functions can be told apart
+            let mut used = self.vc.take_used();
+
             // Evaluate bodies with all function headers
-            for fun in funs {
+            for (fun, (arg_tys, ret_ty)) in funs.iter().zip(headers) {
 
                 let label = labels.get(&fun.name)
                     .expect("Internal error: missing label");
@@ -469,28 +785,31 @@ impl Checker {
                 );
 
                 // Add parameter bindings to body context
-                for arg in &fun.args {
-                    let arg_ty = self.tc.get_full(&arg.name_span, &arg.ty)?;
+                for (arg, arg_ty) in fun.args.iter().zip(arg_tys) {
                     self.vc.insert(arg.name, Binding::Var(arg_ty));
                 }
 
-
                 // Evaluate body with updated context
-                let (body_ty, body_exp) = self.check_exp(&fun.body)?;
+                let (body_ty, body_exp) = self.check_exp(&fun.body);
+
+                let used_args = self.vc.pop();
+                for arg in fun.args.iter().filter(|arg| !used_args.contains(&arg.name)) {
+                    self.lint(&arg.name_span, Lint::UnusedArg(arg.name));
+                }
 
-                self.vc.pop();
+                // Remember calls within the group for finding unused functions
+                let (calls, others) = self.vc.take_used()
+                    .into_iter()
+                    .partition(|name| labels.contains_key(name));
+                self.calls.insert(fun.name_span.start(), calls);
+                used.extend(others);
                 let frame = self.frames.pop()
                     .expect("Internal error: missing frame");
 
-                // Get return type
-                let ret_ty = match &fun.rets {
-                | None      => Ty::Unit,
-                | Some(ret) => self.tc.get_full(&fun.rets_span.unwrap(), ret)?,
-                };
-
                 // Make sure body expression subtypes return
                 if !body_ty.subtypes(&ret_ty) {
-                    return error(&fun.body.into_span(), TypeError::ReturnMismatch)
+                    let err = TypeError::ReturnMismatch { expected: ret_ty, found: body_ty, declaration: fun.rets_span };
+                    self.error(&fun.body.into_span(), err);
                 }
 
                 self.functions.push(
@@ -498,65 +817,102 @@ impl Checker {
                 );
             }
 
-            Ok(None)
+            self.vc.extend_used(used);
+            None
         },
         | Dec::Var{name, name_span, escape, ty, ty_span, init, ..} => {
 
             // Initialization expression type
-            let (init_ty, init_exp) = self.check_exp(&init)?;
-
-            // Can't assign nil without type annotation
-            if init_ty == Ty::Nil && ty.is_none() {
-                return error(name_span, TypeError::UnknownNil)
-            }
+            let (init_ty, init_exp) = self.check_exp(&init);
+            self.lint_builtin(name, name_span);
 
             // Type annotation on variable
-            match ty {
-            | None     => self.vc.insert(*name, Binding::Var(init_ty.clone())),
+            let var_ty = match ty {
+            | None if init_ty == Ty::Nil => {
+
+                // Can't assign nil without type annotation
+                self.error(name_span, TypeError::UnknownNil);
+                Ty::Error
+            },
+            | None     => init_ty,
             | Some(id) => {
 
                 // Make sure initialization matches annotation
-                let name_ty = self.tc.get_full(&ty_span.unwrap(), id)?;
+                let name_ty = self.tc.get_full(&ty_span.unwrap(), id);
+                let name_ty = self.recover(name_ty);
                 if !init_ty.subtypes(&name_ty) {
-                    return error(&init.into_span(), TypeError::VarMismatch)
+                    let err = TypeError::VarMismatch { expected: name_ty.clone(), found: init_ty, declaration: *ty_span };
+                    self.error(&init.into_span(), err);
                 }
-
-                self.vc.insert(*name, Binding::Var(name_ty));
+                name_ty
             },
             };
 
-            Ok(Some(translate_var_dec(&mut self.frames, *name, *escape, init_exp)))
+            self.record(*name_span, &var_ty);
+            self.vc.insert(*name, Binding::Var(var_ty));
+
+            Some(translate_var_dec(&mut self.frames, *name, *escape, init_exp))
         },
         | Dec::Type(decs, _) => {
 
             // Make sure all top-level names are unique
-            Self::check_unique(decs.iter().map(|dec| (dec.name, dec.name_span)))?;
+            self.check_unique(decs.iter().map(|dec| (dec.name, dec.name_span)));
 
             // Initialize top-level declarations
             for dec in decs {
                 self.tc.insert(dec.name, Ty::Name(dec.name, None));
             }
 
+            // Set aside lookups from before the group, so that references
+            // between its own types can be told apart
+            let mut used = self.tc.take_used();
+
             // Fill in type bodies
             for dec in decs {
-                let ty = self.check_type(&dec.ty)?;
+                let ty = self.check_type(dec);
                 self.tc.insert(dec.name, Ty::Name(dec.name, Some(Box::new(ty))));
+
+                // Remember references within the group for finding unused types
+                let (refs, others) = self.tc.take_used()
+                    .into_iter()
+                    .partition(|name| decs.iter().any(|dec| dec.name == *name));
+                self.refs.insert(dec.name_span.start(), refs);
+                used.extend(others);
+            }
+
+            self.tc.extend_used(used);
+
+            // Poison cycles of type aliases so that they are never traced
+            for cycle in find_cycles(decs) {
+                for dec in &cycle {
+                    self.tc.insert(dec.name, Ty::Name(dec.name, Some(Box::new(Ty::Error))));
+                }
+
+                let names = cycle.iter()
+                    .map(|dec| (dec.name, dec.span))
+                    .collect();
+
+                self.error(&cycle[0].span, TypeError::NotIndirect(names));
             }
 
-            Ok(None)
+            None
         },
         }
     }
 
-    fn check_type(&self, ty: &Type) -> Result<Ty, Error> {
+    fn check_type(&mut self, dec: &TypeDec) -> Ty {
 
-        match ty {
-        | Type::Name(name, span) => self.tc.get_partial(span, name),
+        match &dec.ty {
+        | Type::Name(name, span) => {
+            let ty = self.tc.get_partial(span, name);
+            self.recover(ty)
+        },
         | Type::Arr(name, name_span, _) => {
 
             // Look up array element type
-            let elem_ty = Box::new(self.tc.get_partial(name_span, name)?);
-            Ok(Ty::Arr(elem_ty, TyID::next()))
+            let elem_ty = self.tc.get_partial(name_span, name);
+            let elem_ty = Box::new(self.recover(elem_ty));
+            Ty::Arr(dec.name, elem_ty, TyID::next())
 
         },
         | Type::Rec(decs, _) => {
@@ -565,12 +921,68 @@ impl Checker {
 
             // Look up each field type
             for dec in decs {
-                fields.push((dec.name, self.tc.get_partial(&dec.ty_span, &dec.ty)?));
+                let field_ty = self.tc.get_partial(&dec.ty_span, &dec.ty);
+                fields.push((dec.name, self.recover(field_ty)));
             }
 
-            Ok(Ty::Rec(fields, TyID::next()))
+            let id = TyID::next();
+            self.records.insert(id, Record {
+                name: dec.name_span,
+                fields: decs.iter().map(|dec| dec.span).collect(),
+            });
+
+            Ty::Rec(dec.name, fields, id)
 
         },
         }
     }
 }
+
+/// Finds every cycle of type aliases in a mutually recursive group, such as
+/// `type a = b type b = a`, starting each from its earliest declaration.
+fn find_cycles(decs: &[TypeDec]) -> Vec<Vec<&TypeDec>> {
+
+    // Only aliases to other types in the same group can form a cycle
+    let mut indices = FnvHashMap::default();
+    let mut conflicts = FnvHashSet::default();
+    for (i, dec) in decs.iter().enumerate() {
+        if indices.insert(dec.name, i).is_some() { conflicts.insert(dec.name); }
+    }
+
+    // Conflicting names are already reported, and don't refer to a single declaration
+    for name in &conflicts { indices.remove(name); }
+
+    let alias = |i: usize| match &decs[i].ty {
+    | Type::Name(name, _) => indices.get(name).cloned(),
+    | _                   => None,
+    };
+
+    let mut visited = vec![false; decs.len()];
+    let mut cycles = Vec::new();
+
+    for start in 0..decs.len() {
+
+        // Follow aliases until reaching a visited or concrete type
+        let mut path = Vec::new();
+        let mut next = Some(start);
+        while let Some(i) = next {
+            if visited[i] { break }
+            visited[i] = true;
+            path.push(i);
+            next = alias(i);
+        }
+
+        // Only a cycle if the path looped back onto itself
+        let end = match next.and_then(|i| path.iter().position(|j| *j == i)) {
+        | Some(end) => end,
+        | None      => continue,
+        };
+
+        let mut cycle = path.split_off(end);
+        let first = (0..cycle.len()).min_by_key(|i| cycle[*i]).unwrap();
+        cycle.rotate_left(first);
+        cycles.push(cycle.into_iter().map(|i| &decs[i]).collect());
+    }
+
+    cycles
+}
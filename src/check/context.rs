@@ -1,4 +1,6 @@
-use fnv::FnvHashMap;
+use std::mem;
+
+use fnv::{FnvHashMap, FnvHashSet};
 use simple_symbol::{store, Symbol};
 
 use ty::Ty;
@@ -8,8 +10,21 @@ use span::Span;
 
 pub type Context<T> = Vec<FnvHashMap<Symbol, T>>;
 
+/// Names looked up in each scope of a [Context], for finding unused declarations.
+pub type Used = Vec<FnvHashSet<Symbol>>;
+
+/// Records a lookup of [name] in the scope that declares it.
+fn mark<T>(context: &Context<T>, used: &mut Used, name: &Symbol) {
+    for (env, used) in context.iter().rev().zip(used.iter_mut().rev()) {
+        if env.contains_key(name) {
+            used.insert(*name);
+            return
+        }
+    }
+}
+
 #[derive(Debug)]
-pub struct VarContext(Context<Binding>);
+pub struct VarContext(Context<Binding>, Used);
 
 #[derive(Debug, Clone)]
 pub enum Binding {
@@ -34,7 +49,7 @@ impl Default for VarContext {
                 store("not")       => Binding::Ext(vec![Ty::Int], Ty::Int, Label::from_fixed("__not__")),
                 store("exit")      => Binding::Ext(vec![Ty::Int], Ty::Unit, Label::from_fixed("__exit__"))
             }
-        ])
+        ], vec![FnvHashSet::default()])
     }
 }
 
@@ -46,37 +61,56 @@ impl VarContext {
 
     pub fn push(&mut self) {
         self.0.push(FnvHashMap::default());
+        self.1.push(FnvHashSet::default());
     }
 
-    pub fn pop(&mut self) {
+    /// Leaves the innermost scope, returning the names looked up in it.
+    pub fn pop(&mut self) -> FnvHashSet<Symbol> {
         self.0.pop().expect("Internal error: no variable context");
+        self.1.pop().expect("Internal error: no variable context")
+    }
+
+    /// Forgets and returns the names looked up in the innermost scope so far.
+    pub fn take_used(&mut self) -> FnvHashSet<Symbol> {
+        mem::take(self.1.last_mut().expect("Internal error: no variable context"))
+    }
+
+    /// Records [names] as looked up in the innermost scope.
+    pub fn extend_used(&mut self, names: FnvHashSet<Symbol>) {
+        self.1.last_mut().expect("Internal error: no variable context").extend(names);
+    }
+
+    pub fn is_builtin(&self, name: &Symbol) -> bool {
+        self.0[0].contains_key(name)
     }
 
-    pub fn get_var(&self, span: &Span, name: &Symbol) -> Result<Ty, Error> {
+    pub fn get_var(&mut self, span: &Span, name: &Symbol) -> Result<Ty, Error> {
+        mark(&self.0, &mut self.1, name);
         for env in self.0.iter().rev() {
             match env.get(name) {
             | Some(Binding::Var(ty))   => return Ok(ty.clone()),
-            | Some(_)                  => return Err(Error::semantic(*span, TypeError::NotVar)),
+            | Some(_)                  => return Err(Error::semantic(*span, TypeError::NotVar(*name))),
             | None                     => (),
             };
         }
-        Err(Error::semantic(*span, TypeError::UnboundVar))
+        Err(Error::semantic(*span, TypeError::UnboundVar(*name)))
     }
 
-    pub fn get_fun(&self, span: &Span, name: &Symbol) -> Result<Binding, Error> {
+    pub fn get_fun(&mut self, span: &Span, name: &Symbol) -> Result<Binding, Error> {
+        mark(&self.0, &mut self.1, name);
         for env in self.0.iter().rev() {
             match env.get(name) {
-            | Some(Binding::Var(_)) => return Err(Error::semantic(*span, TypeError::NotFun)),
+            | Some(Binding::Var(_)) => return Err(Error::semantic(*span, TypeError::NotFun(*name))),
             | Some(binding)         => return Ok(binding.clone()),
             | _                     => (),
             }
         }
-        Err(Error::semantic(*span, TypeError::UnboundFun))
+        Err(Error::semantic(*span, TypeError::UnboundFun(*name)))
     }
 }
 
 #[derive(Debug)]
-pub struct TypeContext(Context<Ty>);
+pub struct TypeContext(Context<Ty>, Used);
 
 impl Default for TypeContext {
     fn default() -> Self {
@@ -85,7 +119,7 @@ impl Default for TypeContext {
                 store("int")    => Ty::Int,
                 store("string") => Ty::Str
             }
-        ])
+        ], vec![FnvHashSet::default()])
     }
 }
 
@@ -97,24 +131,38 @@ impl TypeContext {
 
     pub fn push(&mut self) {
         self.0.push(FnvHashMap::default());
+        self.1.push(FnvHashSet::default());
     }
 
-    pub fn pop(&mut self) {
+    /// Leaves the innermost scope, returning the names looked up in it.
+    pub fn pop(&mut self) -> FnvHashSet<Symbol> {
         self.0.pop().expect("Internal error: no type context");
+        self.1.pop().expect("Internal error: no type context")
+    }
+
+    /// Forgets and returns the names looked up in the innermost scope so far.
+    pub fn take_used(&mut self) -> FnvHashSet<Symbol> {
+        mem::take(self.1.last_mut().expect("Internal error: no type context"))
+    }
+
+    /// Records [names] as looked up in the innermost scope.
+    pub fn extend_used(&mut self, names: FnvHashSet<Symbol>) {
+        self.1.last_mut().expect("Internal error: no type context").extend(names);
     }
 
     fn trace_partial(&self, ty: &Ty) -> Ty {
         match ty {
-        | Ty::Arr(elem, id) => Ty::Arr(Box::new(self.trace_partial(&*elem)), *id),
-        | _                 => ty.clone(),
+        | Ty::Arr(name, elem, id) => Ty::Arr(*name, Box::new(self.trace_partial(&*elem)), *id),
+        | _                       => ty.clone(),
         }
     }
 
-    pub fn get_partial(&self, span: &Span, name: &Symbol) -> Result<Ty, Error> {
+    pub fn get_partial(&mut self, span: &Span, name: &Symbol) -> Result<Ty, Error> {
+        mark(&self.0, &mut self.1, name);
         for env in self.0.iter().rev() {
             if let Some(ty) = env.get(name) { return Ok(self.trace_partial(&*ty)) }
         }
-        Err(Error::semantic(*span, TypeError::UnboundType))
+        Err(Error::semantic(*span, TypeError::UnboundType(*name)))
     }
 
     pub fn trace_full(&self, span: &Span, ty: &Ty) -> Result<Ty, Error> {
@@ -122,18 +170,22 @@ impl TypeContext {
         | Ty::Name(name, opt) => {
             match opt {
             | Some(ty) => self.trace_full(span, &*ty),
-            | _        => Ok(self.get_full(span, name).unwrap()),
+            | _        => Ok(self.find_full(span, name).unwrap()),
             }
         },
-        | Ty::Arr(elem, id) => Ok(Ty::Arr(Box::new(self.trace_full(span, &*elem)?), *id)),
-        | _                 => Ok(ty.clone()),
+        | _                   => Ok(ty.clone()),
         }
     }
 
-    pub fn get_full(&self, span: &Span, name: &Symbol) -> Result<Ty, Error> {
+    pub fn get_full(&mut self, span: &Span, name: &Symbol) -> Result<Ty, Error> {
+        mark(&self.0, &mut self.1, name);
+        self.find_full(span, name)
+    }
+
+    fn find_full(&self, span: &Span, name: &Symbol) -> Result<Ty, Error> {
         for env in self.0.iter().rev() {
             if let Some(ty) = env.get(name) { return Ok(self.trace_full(span, &*ty)?) }
         }
-        Err(Error::semantic(*span, TypeError::UnboundType))
+        Err(Error::semantic(*span, TypeError::UnboundType(*name)))
     }
 }
@@ -22,7 +22,9 @@ fn trap_name(depth: usize, escaped: &mut Escaped, name: &Symbol, escape: &mut bo
 fn trap_var(depth: usize, escaped: &mut Escaped, var: &mut Var) {
     match var {
     | Var::Simple(name, _) => {
-        escaped.insert(*name, depth);
+        // Keep the deepest usage, which determines whether the variable escapes
+        let usage = escaped.entry(*name).or_insert(depth);
+        *usage = usize::max(*usage, depth);
     },
     | Var::Field(rec, _, _, _) => {
         trap_var(depth, escaped, rec)
@@ -5,7 +5,17 @@ mod escape;
 use ir;
 use ast;
 use error;
+use config::Config;
+use span::Span;
+use ty::Ty;
 
-pub fn check(mut ast: ast::Exp) -> Result<ir::Unit, error::Error> {
-    self::check::Checker::check(&mut ast)
+/// Checks [ast], returning its translation along with any warnings.
+pub fn check(mut ast: ast::Exp, config: Config) -> Result<(ir::Unit, Vec<error::Error>), Vec<error::Error>> {
+    self::check::Checker::check(&mut ast, config)
+}
+
+/// Checks [ast] without compiling it, returning the type of every variable
+/// and expression along with all errors and warnings found.
+pub fn analyze(ast: &mut ast::Exp, config: Config) -> (Vec<(Span, Ty)>, Vec<error::Error>) {
+    self::check::Checker::analyze(ast, config)
 }
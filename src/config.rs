@@ -1 +1,57 @@
+use std::str::FromStr;
+
+use error::{LexError, Severity};
+
 pub const WORD_SIZE: i32 = 8;
+
+/// Options that change how Tiger programs are read and checked.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Config {
+
+    /// Whether `int` literals may use the full 64-bit range of the backend.
+    pub int64: bool,
+
+    /// How to report lints found while type checking.
+    pub warnings: Warnings,
+}
+
+impl Config {
+
+    /// Magnitude of the smallest representable `int`, which is one more than the largest.
+    pub fn max_magnitude(&self) -> u64 {
+        if self.int64 { 1 << 63 } else { 1 << 31 }
+    }
+
+    pub fn int_error(&self) -> LexError {
+        if self.int64 { LexError::Integer64 } else { LexError::Integer }
+    }
+
+    /// Severity to report lints with, or [None] if they are ignored.
+    pub fn lint_severity(&self) -> Option<Severity> {
+        match self.warnings {
+        | Warnings::Allow => None,
+        | Warnings::Warn  => Some(Severity::Warning),
+        | Warnings::Deny  => Some(Severity::Error),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Warnings {
+    Allow,
+    #[default]
+    Warn,
+    Deny,
+}
+
+impl FromStr for Warnings {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+        | "allow" => Ok(Warnings::Allow),
+        | "warn"  => Ok(Warnings::Warn),
+        | "deny"  => Ok(Warnings::Deny),
+        | _       => Err(format!("unknown warning level: {}", s)),
+        }
+    }
+}
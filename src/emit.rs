@@ -0,0 +1,530 @@
+use std::str::FromStr;
+
+use codespan::ByteIndex;
+
+use asm;
+use ast;
+use ir;
+use error::{Error, Kind, Severity};
+use json::Json;
+use lex::TokenStream;
+use operand::{Imm, Label, Mem, Operand};
+use phase::Item;
+use span::Span;
+
+/// How phases write their diagnostic files.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Emit {
+
+    /// Human-readable dumps, such as the S-expressions in `.parsed` files.
+    #[default]
+    Text,
+
+    /// A single JSON object per file, such as `.parsed.json`, following the schema below.
+    Json,
+}
+
+impl FromStr for Emit {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+        | "text" => Ok(Emit::Text),
+        | "json" => Ok(Emit::Json),
+        | _      => Err(format!("unknown emit format: {}", s)),
+        }
+    }
+}
+
+/// Converts phase outputs into JSON, with spans as byte offsets from the start of the source file.
+///
+/// Every node is an object whose `kind` member names its variant, followed by its
+/// `span` if it has one, and then its children in the order they appear in the source.
+pub struct Emitter {
+    base: ByteIndex,
+}
+
+fn kind(kind: &str, mut members: Vec<(&str, Json)>) -> Json {
+    members.insert(0, ("kind", kind.into()));
+    Json::object(members)
+}
+
+fn symbol<T: ToString>(symbol: T) -> Json {
+    symbol.to_string().into()
+}
+
+fn list<T, F: Fn(&T) -> Json>(items: &[T], f: F) -> Json {
+    items.iter().map(f).collect::<Vec<_>>().into()
+}
+
+impl Emitter {
+
+    pub fn new(base: ByteIndex) -> Self {
+        Emitter { base }
+    }
+
+    // ### Output file
+    //
+    // ```
+    // { "tokens": [<TOKEN>], "warnings": [<DIAGNOSTIC>] }
+    // { "ast": <EXP>, "warnings": [<DIAGNOSTIC>] }
+    // { "valid": true, "warnings": [<DIAGNOSTIC>] }
+    // { "ir": <IR_UNIT>, "warnings": [<DIAGNOSTIC>] }
+    // { "asm": <ASM_UNIT>, "warnings": [<DIAGNOSTIC>] }
+    // { "exit": <INT>, "warnings": [<DIAGNOSTIC>] }
+    // { "errors": [<DIAGNOSTIC>] }
+    // ```
+    //
+    // Type checked programs are only reported as valid, like the text dump.
+    pub fn output(&self, item: &Result<Item, Vec<Error>>, warnings: &[Error]) -> Json {
+        let (key, value) = match item {
+        | Err(errs) => return Json::object(vec![("errors", list(errs, |err| self.diagnostic(err)))]),
+        | Ok(Item::Source(_))          => panic!("Internal error: emitting source"),
+        | Ok(Item::Tokens(tokens))     => ("tokens", self.tokens(tokens)),
+        | Ok(Item::Syntax(ast))        => ("ast", self.exp(ast)),
+        | Ok(Item::Typed(_))           => ("valid", true.into()),
+        | Ok(Item::Intermediate(unit)) => ("ir", self.ir_unit(unit)),
+        | Ok(Item::Abstract(unit))     => ("asm", self.asm_unit(unit)),
+        | Ok(Item::Assembly(unit))     => ("asm", self.asm_unit(unit)),
+        | Ok(Item::Exit(code))         => ("exit", Json::Int(*code as i64)),
+        };
+        Json::object(vec![
+            (key, value),
+            ("warnings", list(warnings, |warning| self.diagnostic(warning))),
+        ])
+    }
+
+    // ### Span
+    //
+    // ```
+    // { "start": <INT>, "end": <INT> }
+    // ```
+    fn span(&self, span: &Span) -> Json {
+        Json::object(vec![
+            ("start", (span.start().to_usize() - self.base.to_usize()).into()),
+            ("end", (span.end().to_usize() - self.base.to_usize()).into()),
+        ])
+    }
+
+    fn node(&self, name: &str, span: &Span, mut members: Vec<(&str, Json)>) -> Json {
+        members.insert(0, ("span", self.span(span)));
+        kind(name, members)
+    }
+
+    // ### Diagnostic
+    //
+    // ```
+    // {
+    //   "severity": "error" | "warning",
+    //   "category": "lexical" | "syntactic" | "semantic" | "lint",
+    //   "span": <SPAN>,
+    //   "message": <STRING>,
+    //   "related": [{ "span": <SPAN>, "message": <STRING> }]
+    // }
+    // ```
+    fn diagnostic(&self, error: &Error) -> Json {
+        let severity = match error.severity {
+        | Severity::Error   => "error",
+        | Severity::Warning => "warning",
+        };
+
+        let related = match &error.kind {
+        | Kind::Semantic(err) => err.secondary(),
+        | _                   => Vec::new(),
+        };
+
+        let message: String = (&error.kind).into();
+        Json::object(vec![
+            ("severity", severity.into()),
+            ("category", error.category().into()),
+            ("span", self.span(&error.span)),
+            ("message", message.into()),
+            ("related", list(&related, |(span, message)| Json::object(vec![
+                ("span", self.span(span)),
+                ("message", message.as_str().into()),
+            ]))),
+        ])
+    }
+
+    // ### Token
+    //
+    // ```
+    // { "category": "KEYWORD" | "OPERATOR" | "SYMBOL" | "INTEGER" | "STRING" | "IDENTIFIER", "text": <STRING>, "span": <SPAN> }
+    // ```
+    //
+    // The text is as printed in `.lexed` files, so strings are quoted and escaped.
+    fn tokens(&self, tokens: &TokenStream) -> Json {
+        list(tokens.tokens(), |(start, token, end)| {
+            let token = token.to_string();
+            let mut words = token.splitn(2, ' ');
+            Json::object(vec![
+                ("category", words.next().into()),
+                ("text", words.next().into()),
+                ("span", self.span(&Span::new(*start, *end))),
+            ])
+        })
+    }
+
+    // ### AST expression
+    //
+    // ```
+    // { "kind": "Break" | "Nil", "span": <SPAN> }
+    // { "kind": "Var", "span": <SPAN>, "var": <VAR> }
+    // { "kind": "Int", "span": <SPAN>, "value": <INT> }
+    // { "kind": "Str", "span": <SPAN>, "value": <STRING> }
+    // { "kind": "Call", "span": <SPAN>, "name": <STRING>, "name_span": <SPAN>, "args": [<EXP>] }
+    // { "kind": "Neg", "span": <SPAN>, "exp": <EXP> }
+    // { "kind": "Bin", "span": <SPAN>, "lhs": <EXP>, "op": "+" | "-" | ..., "op_span": <SPAN>, "rhs": <EXP> }
+    // { "kind": "Rec", "span": <SPAN>, "name": <STRING>, "name_span": <SPAN>, "fields": [<FIELD>] }
+    // { "kind": "Seq", "span": <SPAN>, "exps": [<EXP>] }
+    // { "kind": "Ass", "span": <SPAN>, "var": <VAR>, "exp": <EXP> }
+    // { "kind": "If", "span": <SPAN>, "guard": <EXP>, "then": <EXP>, "else": <EXP> | null }
+    // { "kind": "While", "span": <SPAN>, "guard": <EXP>, "body": <EXP> }
+    // { "kind": "For", "span": <SPAN>, "name": <STRING>, "name_span": <SPAN>, "escape": <BOOL>, "lo": <EXP>, "hi": <EXP>, "body": <EXP> }
+    // { "kind": "Let", "span": <SPAN>, "decs": [<DEC>], "body": <EXP> }
+    // { "kind": "Arr", "span": <SPAN>, "name": <STRING>, "name_span": <SPAN>, "size": <EXP>, "init": <EXP> }
+    //
+    // <FIELD> = { "name": <STRING>, "name_span": <SPAN>, "exp": <EXP>, "span": <SPAN> }
+    // ```
+    fn exp(&self, exp: &ast::Exp) -> Json {
+        match exp {
+        | ast::Exp::Break(span)      => self.node("Break", span, vec![]),
+        | ast::Exp::Nil(span)        => self.node("Nil", span, vec![]),
+        | ast::Exp::Var(var, span)   => self.node("Var", span, vec![("var", self.var(var))]),
+        | ast::Exp::Int(n, span)     => self.node("Int", span, vec![("value", Json::Int(*n))]),
+        | ast::Exp::Str(s, span)     => self.node("Str", span, vec![("value", s.as_str().into())]),
+        | ast::Exp::Neg(exp, span)   => self.node("Neg", span, vec![("exp", self.exp(exp))]),
+        | ast::Exp::Seq(exps, span)  => self.node("Seq", span, vec![("exps", list(exps, |exp| self.exp(exp)))]),
+        | ast::Exp::Call{name, name_span, args, span} => self.node("Call", span, vec![
+            ("name", symbol(name)),
+            ("name_span", self.span(name_span)),
+            ("args", list(args, |arg| self.exp(arg))),
+        ]),
+        | ast::Exp::Bin{lhs, op, op_span, rhs, span} => self.node("Bin", span, vec![
+            ("lhs", self.exp(lhs)),
+            ("op", op.symbol().into()),
+            ("op_span", self.span(op_span)),
+            ("rhs", self.exp(rhs)),
+        ]),
+        | ast::Exp::Rec{name, name_span, fields, span} => self.node("Rec", span, vec![
+            ("name", symbol(name)),
+            ("name_span", self.span(name_span)),
+            ("fields", list(fields, |field| Json::object(vec![
+                ("name", symbol(field.name)),
+                ("name_span", self.span(&field.name_span)),
+                ("exp", self.exp(&field.exp)),
+                ("span", self.span(&field.span)),
+            ]))),
+        ]),
+        | ast::Exp::Ass{name, exp, span} => self.node("Ass", span, vec![
+            ("var", self.var(name)),
+            ("exp", self.exp(exp)),
+        ]),
+        | ast::Exp::If{guard, then, or, span} => self.node("If", span, vec![
+            ("guard", self.exp(guard)),
+            ("then", self.exp(then)),
+            ("else", or.as_ref().map(|or| self.exp(or)).into()),
+        ]),
+        | ast::Exp::While{guard, body, span} => self.node("While", span, vec![
+            ("guard", self.exp(guard)),
+            ("body", self.exp(body)),
+        ]),
+        | ast::Exp::For{name, name_span, escape, lo, hi, body, span} => self.node("For", span, vec![
+            ("name", symbol(name)),
+            ("name_span", self.span(name_span)),
+            ("escape", (*escape).into()),
+            ("lo", self.exp(lo)),
+            ("hi", self.exp(hi)),
+            ("body", self.exp(body)),
+        ]),
+        | ast::Exp::Let{decs, body, span} => self.node("Let", span, vec![
+            ("decs", list(decs, |dec| self.dec(dec))),
+            ("body", self.exp(body)),
+        ]),
+        | ast::Exp::Arr{name, name_span, size, init, span} => self.node("Arr", span, vec![
+            ("name", symbol(name)),
+            ("name_span", self.span(name_span)),
+            ("size", self.exp(size)),
+            ("init", self.exp(init)),
+        ]),
+        }
+    }
+
+    // ### AST variable
+    //
+    // ```
+    // { "kind": "Simple", "span": <SPAN>, "name": <STRING> }
+    // { "kind": "Field", "span": <SPAN>, "var": <VAR>, "field": <STRING>, "field_span": <SPAN> }
+    // { "kind": "Index", "span": <SPAN>, "var": <VAR>, "index": <EXP> }
+    // ```
+    fn var(&self, var: &ast::Var) -> Json {
+        match var {
+        | ast::Var::Simple(name, span) => self.node("Simple", span, vec![("name", symbol(name))]),
+        | ast::Var::Field(var, field, field_span, span) => self.node("Field", span, vec![
+            ("var", self.var(var)),
+            ("field", symbol(field)),
+            ("field_span", self.span(field_span)),
+        ]),
+        | ast::Var::Index(var, index, span) => self.node("Index", span, vec![
+            ("var", self.var(var)),
+            ("index", self.exp(index)),
+        ]),
+        }
+    }
+
+    // ### AST declaration
+    //
+    // ```
+    // { "kind": "Fun", "span": <SPAN>, "funs": [<FUNDEC>] }
+    // { "kind": "Var", "span": <SPAN>, "name": <STRING>, "name_span": <SPAN>, "escape": <BOOL>, "ty": <STRING> | null, "ty_span": <SPAN> | null, "init": <EXP> }
+    // { "kind": "Type", "span": <SPAN>, "types": [<TYPEDEC>] }
+    //
+    // <FUNDEC>   = { "name": <STRING>, "name_span": <SPAN>, "args": [<FIELDDEC>], "rets": <STRING> | null, "rets_span": <SPAN> | null, "body": <EXP>, "span": <SPAN> }
+    // <TYPEDEC>  = { "name": <STRING>, "name_span": <SPAN>, "ty": <TYPE>, "span": <SPAN> }
+    // <FIELDDEC> = { "name": <STRING>, "name_span": <SPAN>, "escape": <BOOL>, "ty": <STRING>, "ty_span": <SPAN>, "span": <SPAN> }
+    //
+    // <TYPE> = { "kind": "Name", "span": <SPAN>, "name": <STRING> }
+    //        | { "kind": "Rec", "span": <SPAN>, "fields": [<FIELDDEC>] }
+    //        | { "kind": "Arr", "span": <SPAN>, "name": <STRING>, "name_span": <SPAN> }
+    // ```
+    fn dec(&self, dec: &ast::Dec) -> Json {
+        match dec {
+        | ast::Dec::Fun(funs, span) => self.node("Fun", span, vec![
+            ("funs", list(funs, |fun| Json::object(vec![
+                ("name", symbol(fun.name)),
+                ("name_span", self.span(&fun.name_span)),
+                ("args", list(&fun.args, |arg| self.field_dec(arg))),
+                ("rets", fun.rets.map(symbol).into()),
+                ("rets_span", fun.rets_span.map(|span| self.span(&span)).into()),
+                ("body", self.exp(&fun.body)),
+                ("span", self.span(&fun.span)),
+            ]))),
+        ]),
+        | ast::Dec::Var{name, name_span, escape, ty, ty_span, init, span} => self.node("Var", span, vec![
+            ("name", symbol(name)),
+            ("name_span", self.span(name_span)),
+            ("escape", (*escape).into()),
+            ("ty", ty.map(symbol).into()),
+            ("ty_span", ty_span.map(|span| self.span(&span)).into()),
+            ("init", self.exp(init)),
+        ]),
+        | ast::Dec::Type(types, span) => self.node("Type", span, vec![
+            ("types", list(types, |dec| Json::object(vec![
+                ("name", symbol(dec.name)),
+                ("name_span", self.span(&dec.name_span)),
+                ("ty", self.ty(&dec.ty)),
+                ("span", self.span(&dec.span)),
+            ]))),
+        ]),
+        }
+    }
+
+    fn ty(&self, ty: &ast::Type) -> Json {
+        match ty {
+        | ast::Type::Name(name, span) => self.node("Name", span, vec![("name", symbol(name))]),
+        | ast::Type::Rec(fields, span) => self.node("Rec", span, vec![
+            ("fields", list(fields, |field| self.field_dec(field))),
+        ]),
+        | ast::Type::Arr(name, name_span, span) => self.node("Arr", span, vec![
+            ("name", symbol(name)),
+            ("name_span", self.span(name_span)),
+        ]),
+        }
+    }
+
+    fn field_dec(&self, field: &ast::FieldDec) -> Json {
+        Json::object(vec![
+            ("name", symbol(field.name)),
+            ("name_span", self.span(&field.name_span)),
+            ("escape", field.escape.into()),
+            ("ty", symbol(field.ty)),
+            ("ty_span", self.span(&field.ty_span)),
+            ("span", self.span(&field.span)),
+        ])
+    }
+
+    // ### IR unit
+    //
+    // ```
+    // {
+    //   "data": [{ "label": <STRING>, "value": <STRING> }],
+    //   "functions": [{ "label": <STRING>, "escapes": <INT>, "body": [<STM>] }]
+    // }
+    // ```
+    fn ir_unit(&self, unit: &ir::Unit) -> Json {
+        Json::object(vec![
+            ("data", list(&unit.data, |data| Json::object(vec![
+                ("label", symbol(data.label)),
+                ("value", symbol(data.data)),
+            ]))),
+            ("functions", list(&unit.functions, |function| Json::object(vec![
+                ("label", symbol(function.label)),
+                ("escapes", function.escapes.into()),
+                ("body", list(&function.body, |stm| self.ir_stm(stm))),
+            ]))),
+        ])
+    }
+
+    // ### IR statement
+    //
+    // ```
+    // { "kind": "Move", "src": <IR_EXP>, "dst": <IR_EXP> }
+    // { "kind": "Exp", "exp": <IR_EXP> }
+    // { "kind": "Jump", "target": <IR_EXP>, "labels": [<STRING>] }
+    // { "kind": "CJump", "lhs": <IR_EXP>, "op": "EQ" | "NE" | "LT" | "GT" | "LE" | "GE", "rhs": <IR_EXP>, "true": <STRING>, "false": <STRING> }
+    // { "kind": "Seq", "stms": [<STM>] }
+    // { "kind": "Label", "label": <STRING> }
+    // { "kind": "Comment", "text": <STRING> }
+    // ```
+    fn ir_stm(&self, stm: &ir::Stm) -> Json {
+        match stm {
+        | ir::Stm::Move(src, dst) => kind("Move", vec![("src", self.ir_exp(src)), ("dst", self.ir_exp(dst))]),
+        | ir::Stm::Exp(exp)       => kind("Exp", vec![("exp", self.ir_exp(exp))]),
+        | ir::Stm::Jump(target, labels) => kind("Jump", vec![
+            ("target", self.ir_exp(target)),
+            ("labels", list(labels, |label| symbol(label))),
+        ]),
+        | ir::Stm::CJump(lhs, op, rhs, t, f) => kind("CJump", vec![
+            ("lhs", self.ir_exp(lhs)),
+            ("op", symbol(op)),
+            ("rhs", self.ir_exp(rhs)),
+            ("true", symbol(t)),
+            ("false", symbol(f)),
+        ]),
+        | ir::Stm::Seq(stms)      => kind("Seq", vec![("stms", list(stms, |stm| self.ir_stm(stm)))]),
+        | ir::Stm::Label(label)   => kind("Label", vec![("label", symbol(label))]),
+        | ir::Stm::Comment(text)  => kind("Comment", vec![("text", text.as_str().into())]),
+        }
+    }
+
+    // ### IR expression
+    //
+    // ```
+    // { "kind": "Const", "value": <INT> }
+    // { "kind": "Name", "label": <STRING> }
+    // { "kind": "Temp", "temp": <STRING> }
+    // { "kind": "Binop", "lhs": <IR_EXP>, "op": "ADD" | "SUB" | "MUL" | "DIV" | "MOD" | "LAND" | "LOR" | "XOR", "rhs": <IR_EXP> }
+    // { "kind": "Mem", "address": <IR_EXP> }
+    // { "kind": "Call", "function": <IR_EXP>, "args": [<IR_EXP>] }
+    // { "kind": "ESeq", "stm": <STM>, "exp": <IR_EXP> }
+    // ```
+    fn ir_exp(&self, exp: &ir::Exp) -> Json {
+        match exp {
+        | ir::Exp::Const(n)    => kind("Const", vec![("value", Json::Int(*n))]),
+        | ir::Exp::Name(label) => kind("Name", vec![("label", symbol(label))]),
+        | ir::Exp::Temp(temp)  => kind("Temp", vec![("temp", symbol(temp))]),
+        | ir::Exp::Mem(exp)    => kind("Mem", vec![("address", self.ir_exp(exp))]),
+        | ir::Exp::Binop(lhs, op, rhs) => kind("Binop", vec![
+            ("lhs", self.ir_exp(lhs)),
+            ("op", symbol(op)),
+            ("rhs", self.ir_exp(rhs)),
+        ]),
+        | ir::Exp::Call(function, args) => kind("Call", vec![
+            ("function", self.ir_exp(function)),
+            ("args", list(args, |arg| self.ir_exp(arg))),
+        ]),
+        | ir::Exp::ESeq(stm, exp) => kind("ESeq", vec![
+            ("stm", self.ir_stm(stm)),
+            ("exp", self.ir_exp(exp)),
+        ]),
+        }
+    }
+
+    // ### Assembly unit
+    //
+    // ```
+    // {
+    //   "data": [<ASM>],
+    //   "functions": [{ "stack_size": <INT>, "body": [<ASM>] }]
+    // }
+    // ```
+    //
+    // The stack size counts the 8-byte slots for escaping variables and outgoing arguments,
+    // not including slots for temporaries spilled during register allocation.
+    fn asm_unit<T: Operand>(&self, unit: &asm::Unit<T>) -> Json {
+        Json::object(vec![
+            ("data", list(&unit.data, |asm| self.asm(asm))),
+            ("functions", list(&unit.functions, |function| Json::object(vec![
+                ("stack_size", function.stack_info.0.into()),
+                ("body", list(&function.body, |asm| self.asm(asm))),
+            ]))),
+        ])
+    }
+
+    // ### Assembly instruction
+    //
+    // ```
+    // { "kind": "Instruction", "mnemonic": <STRING>, "operands": [<OPERAND>] }
+    // { "kind": "Label", "label": <STRING> }
+    // { "kind": "Comment", "text": <STRING> }
+    // { "kind": "Directive", "name": "local" | "globl" | "align" | "string" | "data" | "text", "value": <STRING> | <INT> | null }
+    //
+    // <OPERAND> = { "kind": "Reg", "name": <STRING> }
+    //           | { "kind": "Mem", "base": <STRING>, "offset": <INT> }
+    //           | { "kind": "Imm", "value": <INT> }
+    //           | { "kind": "Imm", "label": <STRING> }
+    //           | { "kind": "Label", "label": <STRING> }
+    // ```
+    //
+    // Operands are in AT&T order, with the source before the destination. Temporaries
+    // in abstract assembly are named like registers, as in `TEMP_x_12` or `TEMP_RAX`.
+    fn asm<T: Operand>(&self, asm: &asm::Asm<T>) -> Json {
+        let instruction = |mnemonic: String, operands: Vec<Json>| kind("Instruction", vec![
+            ("mnemonic", mnemonic.into()),
+            ("operands", operands.into()),
+        ]);
+        let binary = |binary: &asm::Binary<T>| vec![self.value(binary.source()), self.value(binary.dest())];
+        let unary = |unary: &asm::Unary<T>| vec![self.value(unary.source())];
+        let label = |label: &Label| kind("Label", vec![("label", symbol(label))]);
+
+        match asm {
+        | asm::Asm::Mov(bin)        => instruction("movq".to_string(), binary(bin)),
+        | asm::Asm::Bin(op, bin)    => instruction(op.to_string(), binary(bin)),
+        | asm::Asm::Mul(un)         => instruction("imulq".to_string(), unary(un)),
+        | asm::Asm::Div(_, un)      => instruction("idivq".to_string(), unary(un)),
+        | asm::Asm::Un(op, un)      => instruction(op.to_string(), unary(un)),
+        | asm::Asm::Pop(un)         => instruction("popq".to_string(), unary(un)),
+        | asm::Asm::Push(un)        => instruction("pushq".to_string(), unary(un)),
+        | asm::Asm::Lea(mem, reg)   => instruction("leaq".to_string(), vec![self.mem(mem), self.reg(reg)]),
+        | asm::Asm::Cmp(bin)        => instruction("cmpq".to_string(), binary(bin)),
+        | asm::Asm::Jmp(target)     => instruction("jmp".to_string(), vec![label(target)]),
+        | asm::Asm::Jcc(op, target) => instruction(format!("j{}", op), vec![label(target)]),
+        | asm::Asm::Call(target, _) => instruction("call".to_string(), vec![label(target)]),
+        | asm::Asm::Cqo             => instruction("cqo".to_string(), vec![]),
+        | asm::Asm::Ret             => instruction("ret".to_string(), vec![]),
+        | asm::Asm::Label(target)   => kind("Label", vec![("label", symbol(target))]),
+        | asm::Asm::Comment(text)   => kind("Comment", vec![("text", symbol(text))]),
+        | asm::Asm::Direct(direct)  => {
+            let (name, value) = match direct {
+            | asm::Direct::Local(label)  => ("local", symbol(label)),
+            | asm::Direct::Global(label) => ("globl", symbol(label)),
+            | asm::Direct::Align(n)      => ("align", Json::Int(*n as i64)),
+            | asm::Direct::Str(s)        => ("string", symbol(s)),
+            | asm::Direct::Data          => ("data", Json::Null),
+            | asm::Direct::Text          => ("text", Json::Null),
+            };
+            kind("Directive", vec![("name", name.into()), ("value", value)])
+        },
+        }
+    }
+
+    fn value<T: Operand>(&self, value: asm::Value<T>) -> Json {
+        match value {
+        | asm::Value::Reg(reg)           => self.reg(&reg),
+        | asm::Value::Mem(mem)           => self.mem(&mem),
+        | asm::Value::Imm(Imm::Int(n))   => kind("Imm", vec![("value", Json::Int(n))]),
+        | asm::Value::Imm(Imm::Label(l)) => kind("Imm", vec![("label", symbol(l))]),
+        }
+    }
+
+    fn reg<T: Operand>(&self, reg: &T) -> Json {
+        kind("Reg", vec![("name", symbol(reg))])
+    }
+
+    fn mem<T: Operand>(&self, mem: &Mem<T>) -> Json {
+        let offset = match mem {
+        | Mem::R(_)          => 0,
+        | Mem::RO(_, offset) => *offset as i64,
+        };
+        kind("Mem", vec![("base", symbol(mem.base())), ("offset", Json::Int(offset))])
+    }
+}
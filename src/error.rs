@@ -1,14 +1,20 @@
+use std::fmt;
+
 use codespan::{ByteIndex, CodeMap};
 use codespan_reporting::{Diagnostic, Label};
 use lalrpop_util::ParseError as LalrpopError;
+use simple_symbol::Symbol;
 
+use ast::Binop;
 use token::Token;
+use ty::Ty;
 use span::Span;
 
 #[derive(Debug, Clone)]
 pub struct Error {
     pub span: Span,
     pub kind: Kind,
+    pub severity: Severity,
 }
 
 impl Error {
@@ -16,44 +22,71 @@ impl Error {
         let file = files.find_file(self.span.start()).unwrap();
         let (row, col) = file.location(self.span.start()).unwrap();
 
-        let category = match self.kind {
+        let message: String = (&self.kind).into();
+        format!("{}:{} {} {}: {}", row.number(), col.number(), self.category(), self.severity, message)
+    }
+
+    /// Phase that found the error.
+    pub fn category(&self) -> &'static str {
+        match self.kind {
         | Kind::Lexical(_)   => "lexical",
         | Kind::Syntactic(_) => "syntactic",
         | Kind::Semantic(_)  => "semantic",
-        };
-
-        let message: String = (&self.kind).into();
-        format!("{}:{} {} error: {}", row.number(), col.number(), category, message)
+        | Kind::Lint(_)      => "lint",
+        }
     }
 
     pub fn lexical(start: ByteIndex, end: ByteIndex, err: LexError) -> Self {
-        Error { span: Span::new(start, end), kind: Kind::Lexical(err), }
+        Error { span: Span::new(start, end), kind: Kind::Lexical(err), severity: Severity::Error, }
     }
 
     pub fn syntactic(start: ByteIndex, end: ByteIndex, err: ParseError) -> Self {
-        Error { span: Span::new(start, end), kind: Kind::Syntactic(err), }
+        Error { span: Span::new(start, end), kind: Kind::Syntactic(err), severity: Severity::Error, }
     }
 
     pub fn semantic(span: Span, err: TypeError) -> Self {
-        Error { span, kind: Kind::Semantic(err), }
+        Error { span, kind: Kind::Semantic(err), severity: Severity::Error, }
+    }
+
+    pub fn lint(span: Span, lint: Lint, severity: Severity) -> Self {
+        Error { span, kind: Kind::Lint(lint), severity, }
     }
 }
 
 impl Into<Diagnostic> for Error {
     fn into(self) -> Diagnostic {
-        let Error { span, kind } = self;
+        let Error { span, kind, severity } = self;
 
-        let labels = vec![Label::new_primary(span)];
+        let mut labels = vec![Label::new_primary(span)];
 
         if let Kind::Semantic(err) = &kind {
-            match err {
-            | TypeError::Break => (),
-            |
-            _ => (),
-            };
+            for (span, message) in err.secondary() {
+                labels.push(Label::new_secondary(span).with_message(message));
+            }
+        }
+
+        let diagnostic = match severity {
+        | Severity::Error   => Diagnostic::new_error(&kind),
+        | Severity::Warning => Diagnostic::new_warning(&kind),
         };
 
-        Diagnostic::new_error(&kind).with_labels(labels)
+        diagnostic.with_labels(labels)
+    }
+}
+
+/// Whether a diagnostic stops compilation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+        | Severity::Error   => write!(fmt, "error"),
+        | Severity::Warning => write!(fmt, "warning"),
+        }
     }
 }
 
@@ -62,6 +95,7 @@ pub enum Kind {
     Lexical(LexError),
     Syntactic(ParseError),
     Semantic(TypeError),
+    Lint(Lint),
 }
 
 impl <'a> Into<String> for &'a Kind {
@@ -70,6 +104,7 @@ impl <'a> Into<String> for &'a Kind {
         | Kind::Lexical(err)   => err.into(),
         | Kind::Syntactic(err) => err.into(),
         | Kind::Semantic(err)  => err.into(),
+        | Kind::Lint(lint)     => lint.into(),
         }
     }
 }
@@ -77,92 +112,156 @@ impl <'a> Into<String> for &'a Kind {
 #[derive(Debug, Clone)]
 pub enum LexError {
     Comment,
+    UnterminatedComment,
     Integer,
+    Integer64,
     Unknown,
+    UnterminatedString,
+    InvalidEscape,
+    InvalidControl,
+    InvalidDecimal,
+    DecimalRange,
+    UnterminatedFormat,
 }
 
 impl <'a> Into<String> for &'a LexError {
     fn into(self) -> String {
         match self {
-        | LexError::Comment => "Comments must begin with [/*].".to_string(),
-        | LexError::Integer => "Integers must be between −2,147,483,648 and 2,147,483,647.".to_string(),
-        | LexError::Unknown => "Unknown token.".to_string(),
+        | LexError::Comment             => "Comments must begin with [/*].".to_string(),
+        | LexError::UnterminatedComment => "Comments must end with [*/].".to_string(),
+        | LexError::Integer             => "Integers must be between −2,147,483,648 and 2,147,483,647.".to_string(),
+        | LexError::Integer64           => "Integers must be between −9,223,372,036,854,775,808 and 9,223,372,036,854,775,807.".to_string(),
+        | LexError::Unknown             => "Unknown token.".to_string(),
+        | LexError::UnterminatedString  => "Strings must end with [\"].".to_string(),
+        | LexError::InvalidEscape       => "Escape sequences must be one of [\\n], [\\t], [\\^c], [\\ddd], [\\\"], [\\\\], or [\\f___f\\].".to_string(),
+        | LexError::InvalidControl      => "Control characters must be one of [\\^@] through [\\^_], [\\^a] through [\\^z], or [\\^?].".to_string(),
+        | LexError::InvalidDecimal      => "Decimal escape sequences must have exactly three digits.".to_string(),
+        | LexError::DecimalRange        => "Decimal escape sequences must be between 000 and 255.".to_string(),
+        | LexError::UnterminatedFormat  => "Whitespace in escape sequences must end with [\\].".to_string(),
         }
     }
 }
 
+/// Syntax errors, where [Unexpected] and [EOF] carry the names of the grammar
+/// terminals that would have been accepted instead, and [Malformed] the invariant
+/// that IR read from a file breaks.
 #[derive(Debug, Clone)]
 pub enum ParseError {
     Extra,
-    Unexpected,
-    EOF,
+    Unexpected(Vec<String>),
+    EOF(Vec<String>),
+    Malformed(&'static str),
 }
 
+/// Type errors, which carry the names and types involved along with the
+/// spans of any related declarations.
 #[derive(Debug, Clone)]
 pub enum TypeError {
     Break,
 
-    CallCountMismatch,
-    CallTypeMismatch,
-    UnboundFun,
-    NotFun,
-    ReturnMismatch,
+    CallCountMismatch { name: Symbol, expected: usize, found: usize, declaration: Option<Span> },
+    CallTypeMismatch { name: Symbol, expected: Ty, found: Ty, declaration: Option<Span> },
+    UnboundFun(Symbol),
+    NotFun(Symbol),
+    ReturnMismatch { expected: Ty, found: Ty, declaration: Option<Span> },
 
-    Neg,
+    Neg(Ty),
 
-    DecConflict,
+    DecConflict { name: Symbol, previous: Span },
 
-    BinaryMismatch,
+    BinaryMismatch { op: Binop, lhs: Ty, rhs: Ty },
     BinaryUnit,
     BinaryNil,
 
     UnboundRecord,
-    NotRecord,
-    FieldCountMismatch,
-    FieldTypeMismatch,
-    FieldNameMismatch,
+    NotRecord(Ty),
+    FieldCountMismatch { name: Symbol, expected: usize, found: usize, declaration: Span },
+    FieldTypeMismatch { name: Symbol, expected: Ty, found: Ty, declaration: Span },
+    FieldNameMismatch { expected: Symbol, found: Symbol, declaration: Span },
 
     UnusedExp,
 
-    UnboundVar,
-    NotVar,
-    VarMismatch,
+    UnboundVar(Symbol),
+    NotVar(Symbol),
+    VarMismatch { expected: Ty, found: Ty, declaration: Option<Span> },
 
-    GuardMismatch,
-    BranchMismatch,
-    UnusedBranch,
+    GuardMismatch(Ty),
+    BranchMismatch { then: Ty, or: Ty, then_span: Span },
+    UnusedBranch(Ty),
 
-    UnusedWhileBody,
+    UnusedWhileBody(Ty),
 
-    ForBound,
-    UnusedForBody,
+    ForBound(Ty),
+    UnusedForBody(Ty),
 
     UnboundArr,
-    NotArr,
-    ArrMismatch,
-    ArrSize,
+    NotArr(Ty),
+    ArrMismatch { expected: Ty, found: Ty },
+    ArrSize(Ty),
 
-    UnboundType,
+    UnboundType(Symbol),
 
-    UnboundField,
+    UnboundField { name: Symbol, ty: Ty, declaration: Span },
 
-    IndexMismatch,
+    IndexMismatch(Ty),
 
     UnknownNil,
 
-    NotIndirect,
+    NotIndirect(Vec<(Symbol, Span)>),
+}
+
+impl TypeError {
+
+    /// Related declarations to point out alongside the error itself.
+    pub fn secondary(&self) -> Vec<(Span, String)> {
+        match self {
+        | TypeError::CallCountMismatch { name, declaration: Some(span), .. } => {
+            vec![(*span, format!("function [{}] declared here", name))]
+        },
+        | TypeError::CallTypeMismatch { declaration: Some(span), .. } => {
+            vec![(*span, "parameter declared here".to_string())]
+        },
+        | TypeError::ReturnMismatch { expected, declaration: Some(span), .. } => {
+            vec![(*span, format!("return type [{}] declared here", expected))]
+        },
+        | TypeError::VarMismatch { expected, declaration: Some(span), .. } => {
+            vec![(*span, format!("type [{}] declared here", expected))]
+        },
+        | TypeError::DecConflict { name, previous } => {
+            vec![(*previous, format!("[{}] first declared here", name))]
+        },
+        | TypeError::FieldCountMismatch { name, declaration, .. }
+        | TypeError::UnboundField { ty: Ty::Rec(name, _, _), declaration, .. } => {
+            vec![(*declaration, format!("record [{}] declared here", name))]
+        },
+        | TypeError::FieldNameMismatch { expected: name, declaration, .. }
+        | TypeError::FieldTypeMismatch { name, declaration, .. } => {
+            vec![(*declaration, format!("field [{}] declared here", name))]
+        },
+        | TypeError::BranchMismatch { then, then_span, .. } => {
+            vec![(*then_span, format!("then branch has type [{}]", then))]
+        },
+        | TypeError::NotIndirect(cycle) => {
+            cycle.iter()
+                .skip(1)
+                .map(|(name, span)| (*span, format!("[{}] declared here", name)))
+                .collect()
+        },
+        | _ => Vec::new(),
+        }
+    }
 }
 
 impl Into<Error> for LalrpopError<ByteIndex, Token, Error> {
     fn into(self) -> Error {
         match self {
-        | LalrpopError::User { .. }
+        | LalrpopError::User { error }                        => error,
         | LalrpopError::InvalidToken { .. }                   => panic!("Internal error: should be covered by custom lexer"),
         | LalrpopError::ExtraToken { token: (start, _, end) } => Error::syntactic(start, end, ParseError::Extra),
-        | LalrpopError::UnrecognizedToken { token, .. }       => {
+        | LalrpopError::UnrecognizedToken { token, expected } => {
             match token {
-            | None => Error::syntactic(0.into(), 0.into(), ParseError::EOF),
-            | Some((start, _, end)) => Error::syntactic(start, end, ParseError::Unexpected),
+            | None => Error::syntactic(0.into(), 0.into(), ParseError::EOF(expected)), // Located by the parser
+            | Some((start, _, end)) => Error::syntactic(start, end, ParseError::Unexpected(expected)),
             }
         },
         }
@@ -172,51 +271,156 @@ impl Into<Error> for LalrpopError<ByteIndex, Token, Error> {
 impl <'a> Into<String> for &'a ParseError {
     fn into(self) -> String {
         match self {
-        | ParseError::Extra      => "Extra tokens encountered.".to_string(),
-        | ParseError::Unexpected => "Unexpected token encountered.".to_string(),
-        | ParseError::EOF        => "Unexpected EOF encountered.".to_string(),
+        | ParseError::Extra                => "Extra tokens encountered.".to_string(),
+        | ParseError::Unexpected(expected) => format!("Unexpected token encountered{}", describe_expected(expected)),
+        | ParseError::EOF(expected)        => format!("Unexpected EOF encountered{}", describe_expected(expected)),
+        | ParseError::Malformed(reason)    => format!("Malformed IR: {}.", reason),
         }
     }
 }
 
+/// Lists the tokens the parser would have accepted, ending the sentence.
+fn describe_expected(expected: &[String]) -> String {
+    let expected = expected.iter()
+        .map(|terminal| match describe_terminal(terminal) {
+        // Brackets can't be told apart when wrapped in brackets
+        | bracket @ "[" | bracket @ "]" => format!("'{}'", bracket),
+        | terminal                      => format!("[{}]", terminal),
+        })
+        .collect::<Vec<_>>();
+
+    match expected.len() {
+    | 0 => ".".to_string(),
+    | 1 => format!("; expected {}.", expected[0]),
+    | 2 => format!("; expected {} or {}.", expected[0], expected[1]),
+    | n => format!("; expected one of {}, or {}.", expected[..n - 1].join(", "), expected[n - 1]),
+    }
+}
+
+/// Converts a terminal name from the grammar back into Tiger syntax.
+fn describe_terminal(terminal: &str) -> &str {
+    match terminal {
+    | "TYPE"      => "type",
+    | "VAR"       => "var",
+    | "FUNCTION"  => "function",
+    | "BREAK"     => "break",
+    | "OF"        => "of",
+    | "END"       => "end",
+    | "IN"        => "in",
+    | "NIL"       => "nil",
+    | "LET"       => "let",
+    | "DO"        => "do",
+    | "TO"        => "to",
+    | "FOR"       => "for",
+    | "WHILE"     => "while",
+    | "ELSE"      => "else",
+    | "THEN"      => "then",
+    | "IF"        => "if",
+    | "ARRAY"     => "array",
+    | "ASSIGN"    => ":=",
+    | "LOR"       => "|",
+    | "LAND"      => "&",
+    | "GE"        => ">=",
+    | "GT"        => ">",
+    | "LE"        => "<=",
+    | "LT"        => "<",
+    | "NEQ"       => "<>",
+    | "EQ"        => "=",
+    | "DIV"       => "/",
+    | "MUL"       => "*",
+    | "MOD"       => "%",
+    | "SUB"       => "-",
+    | "ADD"       => "+",
+    | "DOT"       => ".",
+    | "LBRACE"    => "[",
+    | "RBRACE"    => "]",
+    | "LBRACK"    => "{",
+    | "RBRACK"    => "}",
+    | "LPAREN"    => "(",
+    | "RPAREN"    => ")",
+    | "SEMICOLON" => ";",
+    | "COLON"     => ":",
+    | "COMMA"     => ",",
+    | "INT"       => "integer",
+    | "STR"       => "string",
+    | "ID"        => "identifier",
+    | _           => terminal,
+    }
+}
+
 impl <'a> Into<String> for &'a TypeError {
     fn into(self) -> String {
         match self {
-        | TypeError::Break              => "Cannot break outside of a loop.".to_string(),
-        | TypeError::CallCountMismatch  => "Wrong number of arguments to function.".to_string(),
-        | TypeError::CallTypeMismatch   => "Wrong type of argument to function.".to_string(),
-        | TypeError::UnboundFun         => "Could not find function.".to_string(),
-        | TypeError::NotFun             => "Not a function.".to_string(),
-        | TypeError::ReturnMismatch     => "Function return type doesn't match body.".to_string(),
-        | TypeError::DecConflict        => "Conflicting declarations in mutually recursive group.".to_string(),
-        | TypeError::Neg                => "Can only negate integers.".to_string(),
-        | TypeError::BinaryMismatch     => "Wrong arguments for binary operator.".to_string(),
-        | TypeError::BinaryUnit         => "Cannot operate on unit value".to_string(),
-        | TypeError::BinaryNil          => "Cannot compare two nil values".to_string(),
-        | TypeError::UnboundRecord      => "Could not find record.".to_string(),
-        | TypeError::NotRecord          => "Not a record.".to_string(),
-        | TypeError::FieldCountMismatch => "Number of fields doesn't match record type.".to_string(),
-        | TypeError::FieldNameMismatch  => "Incorrect name for field.".to_string(),
-        | TypeError::FieldTypeMismatch  => "Incorrect type for field.".to_string(),
-        | TypeError::UnusedExp          => "Unused expression.".to_string(),
-        | TypeError::UnboundVar         => "Could not find variable.".to_string(),
-        | TypeError::NotVar             => "Expected variable.".to_string(),
-        | TypeError::VarMismatch        => "Incorrect type for assignment.".to_string(),
-        | TypeError::GuardMismatch      => "Guard expression must be an integer.".to_string(),
-        | TypeError::BranchMismatch     => "Branches must return the same type.".to_string(),
-        | TypeError::UnusedBranch       => "If branches must return unit.".to_string(),
-        | TypeError::UnusedWhileBody    => "While body must return unit.".to_string(),
-        | TypeError::ForBound           => "For bounds must be integers.".to_string(),
-        | TypeError::UnusedForBody      => "For body must return unit.".to_string(),
-        | TypeError::UnboundArr         => "Could not find array.".to_string(),
-        | TypeError::NotArr             => "Not an array.".to_string(),
-        | TypeError::ArrMismatch        => "Array initializer doesn't match array type.".to_string(),
-        | TypeError::ArrSize            => "Array size must be integer.".to_string(),
-        | TypeError::UnboundType        => "Could not find type.".to_string(),
-        | TypeError::UnboundField       => "Unbound record field.".to_string(),
-        | TypeError::IndexMismatch      => "Array indices must be integers.".to_string(),
-        | TypeError::UnknownNil         => "Cannot infer type for nil.".to_string(),
-        | TypeError::NotIndirect        => "Recursive types must pass through arrays or records.".to_string(),
+        | TypeError::Break                                            => "Cannot break outside of a loop.".to_string(),
+        | TypeError::CallCountMismatch { name, expected, found, .. }  => format!("Wrong number of arguments to function [{}]: expected {} but found {}.", name, expected, found),
+        | TypeError::CallTypeMismatch { name, expected, found, .. }   => format!("Wrong type of argument to function [{}]: expected [{}] but found [{}].", name, expected, found),
+        | TypeError::UnboundFun(name)                                 => format!("Could not find function [{}].", name),
+        | TypeError::NotFun(name)                                     => format!("Expected [{}] to be a function, but found a variable.", name),
+        | TypeError::ReturnMismatch { expected, found, .. }           => format!("Function return type doesn't match body: expected [{}] but found [{}].", expected, found),
+        | TypeError::DecConflict { name, .. }                         => format!("Conflicting declarations of [{}] in mutually recursive group.", name),
+        | TypeError::Neg(ty)                                          => format!("Can only negate integers, but found [{}].", ty),
+        | TypeError::BinaryMismatch { op, lhs, rhs }                  => format!("Wrong arguments for binary operator [{}]: found [{}] and [{}].", op.symbol(), lhs, rhs),
+        | TypeError::BinaryUnit                                       => "Cannot operate on unit value".to_string(),
+        | TypeError::BinaryNil                                        => "Cannot compare two nil values".to_string(),
+        | TypeError::UnboundRecord                                    => "Could not find record.".to_string(),
+        | TypeError::NotRecord(ty)                                    => format!("Not a record: found [{}].", ty),
+        | TypeError::FieldCountMismatch { name, expected, found, .. } => format!("Number of fields doesn't match record type [{}]: expected {} but found {}.", name, expected, found),
+        | TypeError::FieldNameMismatch { expected, found, .. }        => format!("Incorrect name for field: expected [{}] but found [{}].", expected, found),
+        | TypeError::FieldTypeMismatch { name, expected, found, .. }  => format!("Incorrect type for field [{}]: expected [{}] but found [{}].", name, expected, found),
+        | TypeError::UnusedExp                                        => "Unused expression.".to_string(),
+        | TypeError::UnboundVar(name)                                 => format!("Could not find variable [{}].", name),
+        | TypeError::NotVar(name)                                     => format!("Expected [{}] to be a variable, but found a function.", name),
+        | TypeError::VarMismatch { expected, found, .. }              => format!("Incorrect type for assignment: expected [{}] but found [{}].", expected, found),
+        | TypeError::GuardMismatch(ty)                                => format!("Guard expression must be an integer, but found [{}].", ty),
+        | TypeError::BranchMismatch { then, or, .. }                  => format!("Branches must return the same type, but found [{}] and [{}].", then, or),
+        | TypeError::UnusedBranch(ty)                                 => format!("If branches must return unit, but found [{}].", ty),
+        | TypeError::UnusedWhileBody(ty)                              => format!("While body must return unit, but found [{}].", ty),
+        | TypeError::ForBound(ty)                                     => format!("For bounds must be integers, but found [{}].", ty),
+        | TypeError::UnusedForBody(ty)                                => format!("For body must return unit, but found [{}].", ty),
+        | TypeError::UnboundArr                                       => "Could not find array.".to_string(),
+        | TypeError::NotArr(ty)                                       => format!("Not an array: found [{}].", ty),
+        | TypeError::ArrMismatch { expected, found }                  => format!("Array initializer doesn't match array type: expected [{}] but found [{}].", expected, found),
+        | TypeError::ArrSize(ty)                                      => format!("Array size must be integer, but found [{}].", ty),
+        | TypeError::UnboundType(name)                                => format!("Could not find type [{}].", name),
+        | TypeError::UnboundField { name, ty, .. }                    => format!("Record type [{}] has no field [{}].", ty, name),
+        | TypeError::IndexMismatch(ty)                                => format!("Array indices must be integers, but found [{}].", ty),
+        | TypeError::UnknownNil                                       => "Cannot infer type for nil.".to_string(),
+        | TypeError::NotIndirect(cycle)                               => format!("Recursive types must pass through arrays or records, but found cycle {}.", describe_cycle(cycle)),
+        }
+    }
+}
+
+/// Lists the types in a cycle, returning to where it started.
+fn describe_cycle(cycle: &[(Symbol, Span)]) -> String {
+    cycle.iter()
+        .chain(cycle.first())
+        .map(|(name, _)| format!("[{}]", name))
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// Valid code that is probably a mistake, found while type checking.
+#[derive(Debug, Clone)]
+pub enum Lint {
+    UnusedVar(Symbol),
+    UnusedArg(Symbol),
+    UnusedType(Symbol),
+    UnusedFun(Symbol),
+    Unreachable,
+    ConstantGuard(bool),
+    ShadowBuiltin(Symbol),
+}
+
+impl From<&Lint> for String {
+    fn from(lint: &Lint) -> Self {
+        match lint {
+        | Lint::UnusedVar(name)      => format!("Unused variable [{}].", name),
+        | Lint::UnusedArg(name)      => format!("Unused argument [{}].", name),
+        | Lint::UnusedType(name)     => format!("Unused type [{}].", name),
+        | Lint::UnusedFun(name)      => format!("Unused function [{}].", name),
+        | Lint::Unreachable          => "Unreachable code after [break].".to_string(),
+        | Lint::ConstantGuard(value) => format!("If guard is always {}.", value),
+        | Lint::ShadowBuiltin(name)  => format!("Declaration shadows builtin function [{}].", name),
         }
     }
 }
@@ -0,0 +1,126 @@
+/// Description of formatted text that leaves line breaks up to the renderer,
+/// following Wadler's "A prettier printer".
+#[derive(Debug, Clone)]
+pub enum Doc {
+    Text(String),
+
+    /// Space if the enclosing group fits on one line, otherwise a newline.
+    Line,
+
+    /// Nothing if the enclosing group fits on one line, otherwise a newline.
+    Soft,
+
+    /// Newline that breaks every enclosing group.
+    Hard,
+
+    /// Indents each newline in the doc by one more level.
+    Nest(Box<Doc>),
+
+    /// Lays out the doc on a single line if it fits, breaking every [Line] and [Soft] otherwise.
+    Group(Box<Doc>),
+
+    Cat(Vec<Doc>),
+}
+
+pub fn text<S: Into<String>>(text: S) -> Doc {
+    Doc::Text(text.into())
+}
+
+pub fn nest(doc: Doc) -> Doc {
+    Doc::Nest(Box::new(doc))
+}
+
+pub fn group(doc: Doc) -> Doc {
+    Doc::Group(Box::new(doc))
+}
+
+pub fn cat(docs: Vec<Doc>) -> Doc {
+    Doc::Cat(docs)
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+type Command<'d> = (usize, Mode, &'d Doc);
+
+/// Lays out [doc] within [width] columns where possible, indenting by [indent] spaces per level.
+pub fn render(doc: &Doc, indent: usize, width: usize) -> String {
+    let mut out = String::new();
+    let mut column = 0;
+    let mut stack: Vec<Command> = vec![(0, Mode::Break, doc)];
+
+    while let Some((level, mode, doc)) = stack.pop() {
+        match doc {
+        | Doc::Text(text) => {
+            out.push_str(text);
+            column = match text.rfind('\n') {
+            | Some(i) => text[i + 1..].chars().count(),
+            | None    => column + text.chars().count(),
+            };
+        },
+        | Doc::Line if mode == Mode::Flat => { out.push(' '); column += 1; },
+        | Doc::Soft if mode == Mode::Flat => (),
+        | Doc::Line
+        | Doc::Soft
+        | Doc::Hard => {
+            let trimmed = out.trim_end_matches(' ').len();
+            out.truncate(trimmed);
+            out.push('\n');
+            column = level * indent;
+            out.push_str(&" ".repeat(column));
+        },
+        | Doc::Nest(doc) => stack.push((level + 1, mode, doc)),
+        | Doc::Group(doc) => {
+            let remaining = width as isize - column as isize;
+            let mode = if mode == Mode::Flat || fits(remaining, doc, &stack) { Mode::Flat } else { Mode::Break };
+            stack.push((level, mode, doc));
+        },
+        | Doc::Cat(docs) => for doc in docs.iter().rev() {
+            stack.push((level, mode, doc));
+        },
+        }
+    }
+
+    let trimmed = out.trim_end_matches(' ').len();
+    out.truncate(trimmed);
+    out
+}
+
+/// Whether [doc] fits in [remaining] columns when laid out flat,
+/// along with whatever follows it up to the next newline.
+fn fits(mut remaining: isize, doc: &Doc, rest: &[Command]) -> bool {
+    let mut stack = vec![(Mode::Flat, doc)];
+    let mut rest = rest.iter().rev();
+
+    loop {
+        let (mode, doc) = match stack.pop() {
+        | Some(next) => next,
+        | None => match rest.next() {
+            | Some((_, mode, doc)) => (*mode, *doc),
+            | None                 => return true,
+            },
+        };
+
+        match doc {
+        | Doc::Text(text) => match text.find('\n') {
+            | Some(_) => return mode == Mode::Break,
+            | None    => remaining -= text.chars().count() as isize,
+            },
+        | Doc::Line if mode == Mode::Flat => remaining -= 1,
+        | Doc::Soft if mode == Mode::Flat => (),
+        | Doc::Line
+        | Doc::Soft
+        | Doc::Hard => return mode == Mode::Break,
+        | Doc::Nest(doc)
+        | Doc::Group(doc) => stack.push((mode, doc)),
+        | Doc::Cat(docs) => for doc in docs.iter().rev() {
+            stack.push((mode, doc));
+        },
+        }
+
+        if remaining < 0 { return false }
+    }
+}
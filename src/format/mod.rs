@@ -0,0 +1,61 @@
+mod doc;
+mod printer;
+
+use std::sync::Arc;
+
+use codespan::{CodeMap, FileMap, FileName};
+
+use ast::Exp;
+use config::Config;
+use error::Error;
+use lex;
+use parse;
+use span::Span;
+use self::printer::Printer;
+
+/// Layout options for formatted source.
+#[derive(Copy, Clone, Debug)]
+pub struct Style {
+
+    /// Number of spaces per level of indentation.
+    pub indent: usize,
+
+    /// Column that lines should stay within where possible.
+    pub width: usize,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style { indent: 4, width: 80 }
+    }
+}
+
+/// Re-emits the program in [source] in canonical Tiger syntax, keeping its comments.
+pub fn format(source: Arc<FileMap>, config: Config, style: Style) -> Result<String, Vec<Error>> {
+    let tokens = lex::lex(source.clone(), config).map_err(|err| vec![err])?;
+    let comments = tokens.comments().to_vec();
+    let ast = parse::parse(tokens, config)?;
+
+    let doc = Printer::new(source.src(), source.span().start(), &comments).program(&ast);
+    let formatted = doc::render(&doc, style.indent, style.width);
+
+    verify(&source, &comments, &ast, &formatted, config);
+    Ok(formatted)
+}
+
+/// Makes sure that [formatted] has the same comments as [source] and parses to the
+/// same program, which is printed without spans. Any difference is a formatter bug.
+fn verify(source: &FileMap, comments: &[Span], ast: &Exp, formatted: &str, config: Config) {
+    let mut code = CodeMap::new();
+    let file = code.add_filemap(FileName::virtual_("formatted"), formatted.to_string());
+    let tokens = lex::lex(file.clone(), config)
+        .expect("Internal error: formatted program does not lex");
+
+    let before = comments.iter().map(|span| source.src_slice(*span));
+    let after = tokens.comments().iter().map(|span| file.src_slice(*span));
+    assert!(before.eq(after), "Internal error: formatting changed comments");
+
+    let reparsed = parse::parse(tokens, config)
+        .unwrap_or_else(|_| panic!("Internal error: formatted program does not parse"));
+    assert!(ast.to_string() == reparsed.to_string(), "Internal error: formatting changed the program");
+}
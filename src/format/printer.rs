@@ -0,0 +1,398 @@
+use codespan::{ByteIndex, ByteOffset};
+
+use ast::*;
+use span::{Span, IntoSpan};
+use util::escape;
+use format::doc::{Doc, cat, group, nest, text};
+
+/// Declaration within a `let`, flattened out of its recursive group.
+enum Item<'a> {
+    Fun(&'a FunDec),
+    Type(&'a TypeDec),
+    Var(&'a Dec),
+}
+
+impl <'a> IntoSpan for Item<'a> {
+    fn into_span(&self) -> Span {
+        match self {
+        | Item::Fun(dec)  => dec.into_span(),
+        | Item::Type(dec) => dec.into_span(),
+        | Item::Var(dec)  => dec.into_span(),
+        }
+    }
+}
+
+/// Converts a program into a [Doc], placing each comment of the original
+/// source before the first node that follows it.
+pub struct Printer<'s> {
+    source: &'s str,
+    base: ByteIndex,
+    comments: &'s [Span],
+    next: usize,
+}
+
+impl <'s> Printer<'s> {
+
+    pub fn new(source: &'s str, base: ByteIndex, comments: &'s [Span]) -> Self {
+        Printer { source, base, comments, next: 0 }
+    }
+
+    pub fn program(&mut self, exp: &Exp) -> Doc {
+        let body = self.exp(exp);
+        let eof = self.base + ByteOffset(self.source.len() as i64);
+        let (comments, _) = self.closing(eof);
+        cat(vec![body, comments, Doc::Hard])
+    }
+
+    fn offset(&self, index: ByteIndex) -> usize {
+        index.to_usize() - self.base.to_usize()
+    }
+
+    fn slice(&self, span: &Span) -> &'s str {
+        &self.source[self.offset(span.start())..self.offset(span.end())]
+    }
+
+    /// Whether code comes before [comment] on its line.
+    fn after_code(&self, comment: &Span) -> bool {
+        let line = self.source[..self.offset(comment.start())].rsplit('\n').next();
+        line.is_some_and(|line| !line.trim().is_empty())
+    }
+
+    /// Whether code comes after [comment] on its line.
+    fn before_code(&self, comment: &Span) -> bool {
+        let line = self.source[self.offset(comment.end())..].split('\n').next();
+        line.is_some_and(|line| !line.trim().is_empty())
+    }
+
+    /// Whether the source has an empty line between [start] and [end].
+    fn blank(&self, start: ByteIndex, end: ByteIndex) -> bool {
+        let gap = &self.source[self.offset(start)..self.offset(end)];
+        let lines = gap.split('\n').collect::<Vec<_>>();
+        lines.len() > 2 && lines[1..lines.len() - 1].iter().any(|line| line.trim().is_empty())
+    }
+
+    /// Start of the first token at or after [index].
+    fn token(&self, mut index: ByteIndex) -> ByteIndex {
+        loop {
+            let rest = &self.source[self.offset(index)..];
+            let skipped = rest.len() - rest.trim_start().len();
+            index += ByteOffset(skipped as i64);
+            match self.comments.iter().find(|comment| comment.start() == index) {
+            | Some(comment) => index = comment.end(),
+            | None          => return index,
+            }
+        }
+    }
+
+    /// Comments before [index], each followed by a space if code shares its line or a newline otherwise.
+    fn leading(&mut self, index: ByteIndex) -> Doc {
+        let mut docs = Vec::new();
+        while let Some(comment) = self.comments.get(self.next) {
+            if comment.start() >= index { break }
+            self.next += 1;
+            docs.push(text(self.slice(comment)));
+            docs.push(if self.before_code(comment) { text(" ") } else { Doc::Hard });
+        }
+        cat(docs)
+    }
+
+    /// Comments before [index] that share a line with the code before them,
+    /// and whether there were any.
+    fn trailing(&mut self, index: ByteIndex) -> (Doc, bool) {
+        let mut docs = Vec::new();
+        while let Some(comment) = self.comments.get(self.next) {
+            if comment.start() >= index || !self.after_code(comment) { break }
+            self.next += 1;
+            docs.push(text(" "));
+            docs.push(text(self.slice(comment)));
+        }
+        let found = !docs.is_empty();
+        (cat(docs), found)
+    }
+
+    /// Comments before the closing token at [index], and whether there were any.
+    fn closing(&mut self, index: ByteIndex) -> (Doc, bool) {
+        let mut docs = Vec::new();
+        while let Some(comment) = self.comments.get(self.next) {
+            if comment.start() >= index { break }
+            self.next += 1;
+            docs.push(if self.after_code(comment) { text(" ") } else { Doc::Hard });
+            docs.push(text(self.slice(comment)));
+        }
+        let found = !docs.is_empty();
+        (cat(docs), found)
+    }
+
+    /// Separates [items] by [sep] and [line], keeping comments after an
+    /// item on its line and any blank lines between items.
+    fn list<T, F>(&mut self, items: &[T], sep: &str, line: Doc, mut print: F) -> Doc
+        where T: IntoSpan, F: FnMut(&mut Self, &T) -> Doc
+    {
+        let mut docs = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            docs.push(print(self, item));
+            let next = match items.get(i + 1) {
+            | Some(next) => next.into_span().start(),
+            | None       => continue,
+            };
+
+            docs.push(text(sep));
+            let (comments, found) = self.trailing(next);
+            docs.push(comments);
+
+            let limit = match self.comments.get(self.next) {
+            | Some(comment) if comment.start() < next => comment.start(),
+            | _                                       => next,
+            };
+
+            if self.blank(item.into_span().end(), limit) {
+                docs.push(Doc::Hard);
+                docs.push(Doc::Hard);
+            } else if found {
+                docs.push(Doc::Hard);
+            } else {
+                docs.push(line.clone());
+            }
+        }
+        cat(docs)
+    }
+
+    /// Wraps [items] in [open] and the matching closing delimiter, either all on one line or one per line.
+    /// Non-empty braces are padded with spaces when on one line.
+    fn enclose<T, F>(&mut self, open: &str, items: &[T], sep: &str, end: ByteIndex, print: F) -> Doc
+        where T: IntoSpan, F: FnMut(&mut Self, &T) -> Doc
+    {
+        let close = if open == "{" { "}" } else { ")" };
+        if items.is_empty() { return self.enclose_comments(open, close, end) }
+        let line = if open == "{" && !items.is_empty() { Doc::Line } else { Doc::Soft };
+        let items = self.list(items, sep, Doc::Line, print);
+        let (comments, found) = self.closing(end);
+        group(cat(vec![
+            text(open),
+            nest(cat(vec![line.clone(), items, comments])),
+            if found { Doc::Hard } else { line },
+            text(close),
+        ]))
+    }
+
+    /// Wraps the comments before [end] in [open] and [close], keeping comments that
+    /// followed the opening delimiter on its line.
+    fn enclose_comments(&mut self, open: &str, close: &str, end: ByteIndex) -> Doc {
+        let mut docs = Vec::new();
+        let mut inline = true;
+        while let Some(comment) = self.comments.get(self.next) {
+            if comment.start() >= end { break }
+            self.next += 1;
+            if !self.after_code(comment) {
+                inline = false;
+                docs.push(Doc::Hard);
+            } else if !docs.is_empty() {
+                docs.push(text(" "));
+            }
+            docs.push(text(self.slice(comment)));
+        }
+        cat(vec![
+            text(open),
+            nest(cat(docs)),
+            if inline { text("") } else { Doc::Hard },
+            text(close),
+        ])
+    }
+
+    fn exp(&mut self, exp: &Exp) -> Doc {
+        let leading = self.leading(exp.into_span().start());
+        let doc = match exp {
+        | Exp::Break(_)    => text("break"),
+        | Exp::Nil(_)      => text("nil"),
+        | Exp::Var(var, _) => self.var(var),
+        | Exp::Int(n, _)   => text(n.to_string()),
+        | Exp::Str(s, _)   => text(format!("\"{}\"", escape(s))),
+        | Exp::Call{name, args, span, ..} => {
+            let args = self.enclose("(", args, ",", span.end(), Self::exp);
+            cat(vec![text(name.to_string()), args])
+        },
+        | Exp::Neg(exp, _) => cat(vec![text("-"), self.exp(exp)]),
+        | Exp::Bin{op, ..} => {
+
+            // Print chains of operators with the same precedence together
+            let mut operands = Vec::new();
+            let mut lhs = exp;
+            while let Exp::Bin{lhs: next, op: next_op, rhs, ..} = lhs {
+                if next_op.precedence() != op.precedence() { break }
+                operands.push((next_op, rhs));
+                lhs = next;
+            }
+
+            let mut docs = Vec::new();
+            let first = self.exp(lhs);
+            for (op, rhs) in operands.into_iter().rev() {
+                docs.push(Doc::Line);
+                docs.push(text(format!("{} ", op.symbol())));
+                docs.push(self.exp(rhs));
+            }
+            group(cat(vec![first, nest(cat(docs))]))
+        },
+        | Exp::Rec{name, fields, span, ..} => {
+            let fields = self.enclose("{", fields, ",", span.end(), Self::field);
+            cat(vec![text(format!("{} ", name)), fields])
+        },
+        | Exp::Seq(exps, span) => {
+            self.enclose("(", exps, ";", span.end(), Self::exp)
+        },
+        | Exp::Ass{name, exp, ..} => {
+            cat(vec![self.var(name), text(" := "), self.exp(exp)])
+        },
+        | Exp::If{guard, then, or, ..} => group(self.branches(guard, then, or)),
+        | Exp::While{guard, body, ..} => {
+            group(cat(vec![
+                text("while "),
+                self.exp(guard),
+                text(" do"),
+                nest(cat(vec![Doc::Line, self.exp(body)])),
+            ]))
+        },
+        | Exp::For{name, lo, hi, body, ..} => {
+            group(cat(vec![
+                text(format!("for {} := ", name)),
+                self.exp(lo),
+                text(" to "),
+                self.exp(hi),
+                text(" do"),
+                nest(cat(vec![Doc::Line, self.exp(body)])),
+            ]))
+        },
+        | Exp::Let{decs, body, span} => {
+            let decs_end = decs.last().map_or(span.start(), |dec| dec.into_span().end());
+            let decs = self.decs(decs);
+            let (before_in, _) = self.closing(self.token(decs_end));
+
+            let body = match &**body {
+            | Exp::Seq(exps, _) => self.list(exps, ";", Doc::Hard, Self::exp),
+            | exp               => self.exp(exp),
+            };
+
+            let (before_end, _) = self.closing(span.end());
+            cat(vec![
+                text("let"),
+                nest(cat(vec![Doc::Hard, decs, before_in])),
+                Doc::Hard,
+                text("in"),
+                nest(cat(vec![Doc::Hard, body, before_end])),
+                Doc::Hard,
+                text("end"),
+            ])
+        },
+        | Exp::Arr{name, size, init, ..} => {
+            cat(vec![
+                text(format!("{}[", name)),
+                self.exp(size),
+                text("] of "),
+                self.exp(init),
+            ])
+        },
+        };
+        cat(vec![leading, doc])
+    }
+
+    /// Lays out an `if` along with any `else if` branches that follow it.
+    fn branches(&mut self, guard: &Exp, then: &Exp, or: &Option<Box<Exp>>) -> Doc {
+        let mut docs = vec![
+            text("if "),
+            self.exp(guard),
+            text(" then"),
+            nest(cat(vec![Doc::Line, self.exp(then)])),
+        ];
+
+        match or.as_ref().map(|or| &**or) {
+        | None => (),
+        | Some(Exp::If{guard, then, or, span}) => {
+            docs.push(Doc::Line);
+            docs.push(self.leading(span.start()));
+            docs.push(text("else "));
+            docs.push(self.branches(guard, then, or));
+        },
+        | Some(or) => {
+            docs.push(Doc::Line);
+            docs.push(text("else"));
+            docs.push(nest(cat(vec![Doc::Line, self.exp(or)])));
+        },
+        }
+
+        cat(docs)
+    }
+
+    fn field(&mut self, field: &Field) -> Doc {
+        let leading = self.leading(field.span.start());
+        cat(vec![leading, text(format!("{} = ", field.name)), self.exp(&field.exp)])
+    }
+
+    fn var(&mut self, var: &Var) -> Doc {
+        match var {
+        | Var::Simple(name, _)        => text(name.to_string()),
+        | Var::Field(var, field, _, _) => cat(vec![self.var(var), text(format!(".{}", field))]),
+        | Var::Index(var, index, _)   => {
+            cat(vec![self.var(var), text("["), self.exp(index), text("]")])
+        },
+        }
+    }
+
+    fn decs(&mut self, decs: &[Dec]) -> Doc {
+        let mut items = Vec::new();
+        for dec in decs {
+            match dec {
+            | Dec::Fun(funs, _)   => items.extend(funs.iter().map(Item::Fun)),
+            | Dec::Type(types, _) => items.extend(types.iter().map(Item::Type)),
+            | Dec::Var{..}        => items.push(Item::Var(dec)),
+            }
+        }
+        self.list(&items, "", Doc::Hard, Self::item)
+    }
+
+    fn item(&mut self, item: &Item) -> Doc {
+        let leading = self.leading(item.into_span().start());
+        let doc = match item {
+        | Item::Type(dec) => {
+            cat(vec![text(format!("type {} = ", dec.name)), self.ty(&dec.ty)])
+        },
+        | Item::Var(Dec::Var{name, ty, init, ..}) => {
+            let ty = ty.map_or(String::new(), |ty| format!(": {}", ty));
+            cat(vec![text(format!("var {}{} := ", name, ty)), self.exp(init)])
+        },
+        | Item::Var(_) => panic!("Internal error: expected variable declaration"),
+        | Item::Fun(fun) => {
+            let close = match fun.args.last() {
+            | Some(arg) => self.token(arg.span.end()),
+            // Skip past the opening parenthesis
+            | None      => {
+                let open = self.token(fun.name_span.end());
+                self.token(open + ByteOffset(1))
+            },
+            };
+            let args = self.enclose("(", &fun.args, ",", close, Self::field_dec);
+            let rets = fun.rets.map_or(String::new(), |rets| format!(": {}", rets));
+            group(cat(vec![
+                text(format!("function {}", fun.name)),
+                args,
+                text(format!("{} =", rets)),
+                nest(cat(vec![Doc::Line, self.exp(&fun.body)])),
+            ]))
+        },
+        };
+        cat(vec![leading, doc])
+    }
+
+    fn ty(&mut self, ty: &Type) -> Doc {
+        match ty {
+        | Type::Name(name, _)    => text(name.to_string()),
+        | Type::Arr(name, _, _)  => text(format!("array of {}", name)),
+        | Type::Rec(fields, span) => {
+            self.enclose("{", fields, ",", span.end(), Self::field_dec)
+        },
+        }
+    }
+
+    fn field_dec(&mut self, field: &FieldDec) -> Doc {
+        let leading = self.leading(field.span.start());
+        cat(vec![leading, text(format!("{}: {}", field.name, field.ty))])
+    }
+}
@@ -0,0 +1,348 @@
+use std::mem;
+
+use fnv::FnvHashMap;
+use simple_symbol::Symbol;
+
+use asm::*;
+use config::WORD_SIZE;
+use operand::{Imm, Label, Mem, Operand, Reg, Temp};
+use interpret::{Signal, exit_code, to_bytes};
+use interpret::memory::Memory;
+use interpret::runtime::{EXTERNALS, Runtime};
+
+/// Value left in caller-saved registers after every call, so that
+/// allocations relying on them surviving a call are caught.
+const POISON: i64 = 0xDEAD_BEEF;
+
+/// Base of the fake return addresses pushed by `call`.
+const RETURN_ADDRESS: i64 = 0x4000_0000;
+
+/// Operands the machine can execute: hardware registers live in a single
+/// register file, while any other temps are virtual and local to a frame.
+pub trait Register: Operand {
+    fn to_reg(&self) -> Option<Reg>;
+}
+
+impl Register for Reg {
+    fn to_reg(&self) -> Option<Reg> { Some(*self) }
+}
+
+impl Register for Temp {
+    fn to_reg(&self) -> Option<Reg> {
+        match self {
+        | Temp::Reg(reg) => Some(*reg),
+        | _              => None,
+        }
+    }
+}
+
+/// Executes [unit] starting from `main`, returning the process exit code.
+///
+/// Accepts both abstract assembly, where every temp is an unbounded virtual
+/// register, and allocated assembly.
+pub fn interpret_asm<T: Register>(unit: &Unit<T>) -> i32 {
+    let mut machine = Machine::new(unit);
+    let result = machine.run();
+    machine.runtime.flush();
+    match result {
+    | Ok(())      => 0,
+    | Err(signal) => exit_code(signal),
+    }
+}
+
+/// Saved caller state while a function executes.
+struct Frame<T: Register> {
+    function: usize,
+    pc: usize,
+    temps: FnvHashMap<T, i64>,
+}
+
+struct Machine<'unit, T: 'unit + Register> {
+    functions: &'unit [Function<T>],
+    externals: FnvHashMap<Label, &'static str>,
+    targets: FnvHashMap<Label, (usize, usize)>,
+    addresses: FnvHashMap<Label, i64>,
+    registers: FnvHashMap<Reg, i64>,
+    temps: FnvHashMap<T, i64>,
+    flags: (i64, i64),
+    frames: Vec<Frame<T>>,
+    memory: Memory,
+    runtime: Runtime,
+}
+
+impl <'unit, T: 'unit + Register> Machine<'unit, T> {
+
+    fn new(unit: &'unit Unit<T>) -> Self {
+        let mut memory = Memory::default();
+        let mut addresses = FnvHashMap::default();
+
+        // Copy string literals from the data section onto the heap
+        let mut label = None;
+        for directive in &unit.data {
+            match directive {
+            | Asm::Label(next) => label = Some(*next),
+            | Asm::Direct(Direct::Str(data)) => {
+                let label = label.take().expect("Internal error: unlabeled string");
+                let bytes = to_bytes(*data);
+                let address = memory.malloc_str(&bytes)
+                    .expect("Internal error: allocating data");
+                addresses.insert(label, address);
+            },
+            | _ => (),
+            }
+        }
+
+        let mut targets = FnvHashMap::default();
+        for (i, function) in unit.functions.iter().enumerate() {
+            for (pc, asm) in function.body.iter().enumerate() {
+                if let Asm::Label(label) = asm { targets.insert(*label, (i, pc)); }
+            }
+        }
+
+        let registers = hashmap! {
+            Reg::RSP => memory.stack_top(),
+            Reg::RBP => memory.stack_top()
+        };
+
+        Machine {
+            functions: &unit.functions,
+            externals: EXTERNALS.iter()
+                .map(|name| (Label::from_fixed(name), *name))
+                .collect(),
+            targets,
+            addresses,
+            registers,
+            temps: FnvHashMap::default(),
+            flags: (0, 0),
+            frames: Vec::new(),
+            memory,
+            runtime: Runtime::default(),
+        }
+    }
+
+    fn run(&mut self) -> Result<(), Signal> {
+
+        let (mut function, mut pc) = self.targets[&Label::from_fixed("main")];
+        self.push(RETURN_ADDRESS)?;
+
+        loop {
+            let asm = self.functions[function].body[pc];
+            pc += 1;
+
+            match asm {
+            | Asm::Mov(binary) => {
+                let value = self.read_value(binary.source())?;
+                self.write_value(binary.dest(), value)?;
+            },
+            | Asm::Bin(op, binary) => {
+                let src = self.read_value(binary.source())?;
+                let dst = self.read_value(binary.dest())?;
+                let value = match op {
+                | Binop::Add => dst.wrapping_add(src),
+                | Binop::Sub => dst.wrapping_sub(src),
+                | Binop::And => dst & src,
+                | Binop::Or  => dst | src,
+                | Binop::XOr => dst ^ src,
+                };
+                self.write_value(binary.dest(), value)?;
+            },
+            | Asm::Mul(unary) => {
+                let product = self.read_reg(Reg::RAX) as i128 * self.read_value(unary.source())? as i128;
+                self.write_reg(Reg::RAX, product as i64);
+                self.write_reg(Reg::RDX, (product >> 64) as i64);
+            },
+            | Asm::Div(_, unary) => {
+                let dividend = (self.read_reg(Reg::RDX) as i128) << 64
+                    | self.read_reg(Reg::RAX) as u64 as i128;
+                let divisor = self.read_value(unary.source())? as i128;
+                if divisor == 0 { return Err(Signal::Arithmetic) }
+                let quotient = dividend / divisor;
+                if quotient != quotient as i64 as i128 { return Err(Signal::Arithmetic) }
+                self.write_reg(Reg::RAX, quotient as i64);
+                self.write_reg(Reg::RDX, (dividend % divisor) as i64);
+            },
+            | Asm::Cqo => {
+                let sign = self.read_reg(Reg::RAX) >> 63;
+                self.write_reg(Reg::RDX, sign);
+            },
+            | Asm::Un(op, unary) => {
+                let value = self.read_value(unary.source())?;
+                let value = match op {
+                | Unop::Inc => value.wrapping_add(1),
+                | Unop::Dec => value.wrapping_sub(1),
+                | Unop::Not => !value,
+                | Unop::Neg => value.wrapping_neg(),
+                };
+                self.write_value(unary.source(), value)?;
+            },
+            | Asm::Push(unary) => {
+                let value = self.read_value(unary.source())?;
+                self.push(value)?;
+            },
+            | Asm::Pop(unary) => {
+                let value = self.pop()?;
+                self.write_value(unary.source(), value)?;
+            },
+            | Asm::Lea(mem, dst) => {
+                let address = self.address(mem);
+                self.write(dst, address);
+            },
+            | Asm::Cmp(binary) => {
+                self.flags = (
+                    self.read_value(binary.dest())?,
+                    self.read_value(binary.source())?,
+                );
+            },
+            | Asm::Jmp(label) => {
+                pc = self.jump(function, label);
+            },
+            | Asm::Jcc(op, label) => {
+                let (lhs, rhs) = self.flags;
+                let taken = match op {
+                | Relop::E  => lhs == rhs,
+                | Relop::Ne => lhs != rhs,
+                | Relop::G  => lhs > rhs,
+                | Relop::Ge => lhs >= rhs,
+                | Relop::L  => lhs < rhs,
+                | Relop::Le => lhs <= rhs,
+                };
+                if taken { pc = self.jump(function, label); }
+            },
+            | Asm::Call(label, arity) => {
+                if let Some(name) = self.externals.get(&label) {
+                    let args = (0..arity)
+                        .map(|i| self.read_reg(Reg::get_argument(i)))
+                        .collect::<Vec<_>>();
+                    let result = self.runtime.call(&mut self.memory, name, &args)?;
+                    self.clobber(result);
+                    continue
+                }
+
+                let address = RETURN_ADDRESS + self.frames.len() as i64 + 1;
+                self.push(address)?;
+                self.frames.push(Frame {
+                    function,
+                    pc,
+                    temps: mem::take(&mut self.temps),
+                });
+
+                let (callee, start) = *self.targets.get(&label)
+                    .expect("Internal error: call to unknown function");
+                function = callee;
+                pc = start;
+            },
+            | Asm::Ret => {
+                let address = self.pop()?;
+                if address != RETURN_ADDRESS + self.frames.len() as i64 {
+                    return Err(Signal::Segfault)
+                }
+                match self.frames.pop() {
+                | None => return Ok(()),
+                | Some(frame) => {
+                    function = frame.function;
+                    pc = frame.pc;
+                    self.temps = frame.temps;
+                    let result = self.read_reg(Reg::get_return());
+                    self.clobber(result);
+                },
+                }
+            },
+            | Asm::Comment(comment) => self.resize_stack(function, comment),
+            | Asm::Label(_)
+            | Asm::Direct(_) => (),
+            }
+        }
+    }
+
+    /// Replaces the stack size placeholders left in abstract assembly.
+    fn resize_stack(&mut self, function: usize, comment: Symbol) {
+        let (size, sub_rsp, add_rsp) = self.functions[function].stack_info;
+        let size = (size + size % 2) as i64 * WORD_SIZE as i64;
+        let rsp = self.read_reg(Reg::RSP);
+        if comment == sub_rsp {
+            self.write_reg(Reg::RSP, rsp - size);
+        } else if comment == add_rsp {
+            self.write_reg(Reg::RSP, rsp + size);
+        }
+    }
+
+    fn jump(&self, function: usize, label: Label) -> usize {
+        match self.targets.get(&label) {
+        | Some((target, pc)) if *target == function => *pc,
+        | _ => panic!("Internal error: jump to missing label"),
+        }
+    }
+
+    fn push(&mut self, value: i64) -> Result<(), Signal> {
+        let rsp = self.read_reg(Reg::RSP) - WORD_SIZE as i64;
+        self.write_reg(Reg::RSP, rsp);
+        self.memory.store(rsp, value)
+    }
+
+    fn pop(&mut self) -> Result<i64, Signal> {
+        let rsp = self.read_reg(Reg::RSP);
+        self.write_reg(Reg::RSP, rsp + WORD_SIZE as i64);
+        self.memory.load(rsp)
+    }
+
+    fn address(&self, mem: Mem<T>) -> i64 {
+        match mem {
+        | Mem::R(base)          => self.read(base),
+        | Mem::RO(base, offset) => self.read(base).wrapping_add(offset as i64),
+        }
+    }
+
+    fn read_value(&mut self, value: Value<T>) -> Result<i64, Signal> {
+        match value {
+        | Value::Reg(temp)            => Ok(self.read(temp)),
+        | Value::Mem(mem)             => {
+            let address = self.address(mem);
+            self.memory.load(address)
+        },
+        | Value::Imm(Imm::Int(n))     => Ok(n),
+        | Value::Imm(Imm::Label(label)) => {
+            Ok(*self.addresses.get(&label).expect("Internal error: address of unknown label"))
+        },
+        }
+    }
+
+    fn write_value(&mut self, value: Value<T>, data: i64) -> Result<(), Signal> {
+        match value {
+        | Value::Reg(temp) => { self.write(temp, data); Ok(()) },
+        | Value::Mem(mem)  => {
+            let address = self.address(mem);
+            self.memory.store(address, data)
+        },
+        | Value::Imm(_)    => panic!("Internal error: writing to immediate"),
+        }
+    }
+
+    fn read_reg(&self, reg: Reg) -> i64 {
+        self.registers.get(&reg).cloned().unwrap_or(0)
+    }
+
+    fn write_reg(&mut self, reg: Reg, value: i64) {
+        self.registers.insert(reg, value);
+    }
+
+    /// Overwrites the caller-saved registers after a call that returned [result],
+    /// so that code relying on them surviving the call misbehaves like it would natively.
+    fn clobber(&mut self, result: i64) {
+        for reg in &Reg::CALLER_SAVED { self.write_reg(*reg, POISON); }
+        self.write_reg(Reg::get_return(), result);
+    }
+
+    fn read(&self, temp: T) -> i64 {
+        match temp.to_reg() {
+        | Some(reg) => self.read_reg(reg),
+        | None      => *self.temps.get(&temp).expect("Internal error: read of undefined temp"),
+        }
+    }
+
+    fn write(&mut self, temp: T, value: i64) {
+        match temp.to_reg() {
+        | Some(reg) => self.write_reg(reg, value),
+        | None      => { self.temps.insert(temp, value); },
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use config::WORD_SIZE;
+use interpret::Signal;
+
+const HEAP_START: i64 = 0x1000_0000;
+const STACK_END: i64 = 0x7000_0000;
+const STACK_SIZE: i64 = 1 << 23;
+
+/// Simulated little-endian address space with a bump-allocated heap
+/// growing upward and a stack growing downward from [STACK_END].
+pub struct Memory {
+    heap: Vec<u8>,
+    stack: Vec<u8>,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Memory {
+            heap: Vec::new(),
+            stack: vec![0; STACK_SIZE as usize],
+        }
+    }
+}
+
+impl Memory {
+
+    /// Initial value of the stack pointer.
+    pub fn stack_top(&self) -> i64 {
+        STACK_END
+    }
+
+    /// Allocates [size] zeroed bytes on the heap, returning their address.
+    pub fn malloc(&mut self, size: i64) -> Result<i64, Signal> {
+        if size < 0 { return Err(Signal::Segfault) }
+        let word = WORD_SIZE as i64;
+        let address = HEAP_START + self.heap.len() as i64;
+        let size = i64::max((size + word - 1) / word * word, word);
+        self.heap.resize(self.heap.len() + size as usize, 0);
+        Ok(address)
+    }
+
+    /// Allocates a length-prefixed copy of [bytes] on the heap.
+    pub fn malloc_str(&mut self, bytes: &[u8]) -> Result<i64, Signal> {
+        let word = WORD_SIZE as i64;
+        let address = self.malloc(word + bytes.len() as i64)?;
+        self.store(address, bytes.len() as i64)?;
+        for (i, byte) in bytes.iter().enumerate() {
+            self.store_byte(address + word + i as i64, *byte)?;
+        }
+        Ok(address)
+    }
+
+    fn locate(&mut self, address: i64) -> Result<&mut u8, Signal> {
+        if (HEAP_START..HEAP_START + self.heap.len() as i64).contains(&address) {
+            Ok(&mut self.heap[(address - HEAP_START) as usize])
+        } else if (STACK_END - STACK_SIZE..STACK_END).contains(&address) {
+            Ok(&mut self.stack[(address - STACK_END + STACK_SIZE) as usize])
+        } else {
+            Err(Signal::Segfault)
+        }
+    }
+
+    pub fn load_byte(&mut self, address: i64) -> Result<u8, Signal> {
+        self.locate(address).map(|byte| *byte)
+    }
+
+    pub fn store_byte(&mut self, address: i64, value: u8) -> Result<(), Signal> {
+        self.locate(address).map(|byte| *byte = value)
+    }
+
+    pub fn load(&mut self, address: i64) -> Result<i64, Signal> {
+        let mut value = 0;
+        for i in (0..WORD_SIZE as i64).rev() {
+            value = (value << 8) | self.load_byte(address + i)? as i64;
+        }
+        Ok(value)
+    }
+
+    pub fn store(&mut self, address: i64, value: i64) -> Result<(), Signal> {
+        for i in 0..WORD_SIZE as i64 {
+            self.store_byte(address + i, (value >> (8 * i)) as u8)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the length-prefixed string starting at [address].
+    pub fn load_str(&mut self, address: i64) -> Result<Vec<u8>, Signal> {
+        let size = self.load(address)?;
+        (0..size)
+            .map(|i| self.load_byte(address + WORD_SIZE as i64 + i))
+            .collect()
+    }
+}
@@ -0,0 +1,39 @@
+mod machine;
+mod memory;
+mod runtime;
+mod tree;
+
+use simple_symbol::Symbol;
+
+use operand::Label;
+
+pub use self::machine::interpret_asm;
+pub use self::tree::interpret_ir;
+
+/// Non-local control flow during interpretation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Signal {
+    Jump(Label),
+    Exit(i32),
+    Segfault,
+    Arithmetic,
+}
+
+/// Converts the final [signal] of a program into its process exit code,
+/// reporting faults the way the operating system would.
+fn exit_code(signal: Signal) -> i32 {
+    match signal {
+    | Signal::Exit(code) => code & 0xFF,
+    | Signal::Segfault   => { eprintln!("Segmentation fault"); 139 },
+    | Signal::Arithmetic => { eprintln!("Floating point exception"); 136 },
+    | Signal::Jump(_)    => panic!("Internal error: jump to missing label"),
+    }
+}
+
+/// Recovers the bytes of a string literal, whose chars each stand for a single byte.
+fn to_bytes(string: Symbol) -> Vec<u8> {
+    string.to_string()
+        .chars()
+        .map(|c| c as u8)
+        .collect()
+}
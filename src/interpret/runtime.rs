@@ -0,0 +1,125 @@
+use std::io::{self, Read, Write};
+
+use config::WORD_SIZE;
+use interpret::Signal;
+use interpret::memory::Memory;
+
+/// Functions provided by the runtime.
+pub const EXTERNALS: [&str; 14] = [
+    "__prints__",
+    "__printi__",
+    "__flush__",
+    "__getchar__",
+    "__ord__",
+    "__chr__",
+    "__size__",
+    "__substring__",
+    "__concat__",
+    "__not__",
+    "__exit__",
+    "malloc",
+    "init_array",
+    "string_compare",
+];
+
+/// Simulated C runtime, mirroring `runtime/libtiger.c`.
+pub struct Runtime {
+    output: io::BufWriter<io::Stdout>,
+    input: io::Bytes<io::BufReader<io::Stdin>>,
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Runtime {
+            output: io::BufWriter::new(io::stdout()),
+            input: io::BufReader::new(io::stdin()).bytes(),
+        }
+    }
+}
+
+impl Runtime {
+
+    pub fn flush(&mut self) {
+        self.output.flush().expect("Internal error: IO");
+    }
+
+    fn error(&mut self, message: &str) -> Signal {
+        self.flush();
+        eprintln!("{}", message);
+        Signal::Exit(1)
+    }
+
+    /// Calls the external function [name] with [args].
+    pub fn call(&mut self, memory: &mut Memory, name: &str, args: &[i64]) -> Result<i64, Signal> {
+        match (name, args) {
+        | ("__prints__", &[s]) => {
+            let bytes = memory.load_str(s)?;
+            self.output.write_all(&bytes).expect("Internal error: IO");
+            Ok(0)
+        },
+        | ("__printi__", &[i]) => {
+            write!(self.output, "{}", i).expect("Internal error: IO");
+            Ok(0)
+        },
+        | ("__flush__", &[]) => {
+            self.flush();
+            Ok(0)
+        },
+        | ("__getchar__", &[]) => {
+            self.flush();
+            match self.input.next() {
+            | Some(Ok(byte)) => memory.malloc_str(&[byte]),
+            | _              => memory.malloc_str(&[]),
+            }
+        },
+        | ("__ord__", &[s]) => {
+            match memory.load_str(s)?.first() {
+            | None       => Ok(-1),
+            | Some(byte) => Ok(*byte as i64),
+            }
+        },
+        | ("__chr__", &[i]) => {
+            if !(0..=255).contains(&i) { return Err(self.error("chr: character out of range")) }
+            memory.malloc_str(&[i as u8])
+        },
+        | ("__size__", &[s]) => {
+            memory.load_str(s).map(|bytes| bytes.len() as i64)
+        },
+        | ("__substring__", &[s, first, n]) => {
+            let bytes = memory.load_str(s)?;
+            if first < 0 || n < 0 || first + n > bytes.len() as i64 {
+                return Err(self.error("substring: index out of range"))
+            }
+            memory.malloc_str(&bytes[first as usize..(first + n) as usize])
+        },
+        | ("__concat__", &[a, b]) => {
+            let mut bytes = memory.load_str(a)?;
+            bytes.extend(memory.load_str(b)?);
+            memory.malloc_str(&bytes)
+        },
+        | ("__not__", &[i]) => {
+            Ok((i == 0) as i64)
+        },
+        | ("__exit__", &[i]) => {
+            self.flush();
+            Err(Signal::Exit(i as i32))
+        },
+        | ("malloc", &[size]) => {
+            memory.malloc(size)
+        },
+        | ("init_array", &[size, init]) => {
+            let array = memory.malloc(size * WORD_SIZE as i64)?;
+            for i in 0..size {
+                memory.store(array + i * WORD_SIZE as i64, init)?;
+            }
+            Ok(array)
+        },
+        | ("string_compare", &[a, b]) => {
+            let a = memory.load_str(a)?;
+            let b = memory.load_str(b)?;
+            Ok(a.cmp(&b) as i64)
+        },
+        | _ => panic!("Internal error: unknown external function {} with {} arguments", name, args.len()),
+        }
+    }
+}
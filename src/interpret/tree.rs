@@ -0,0 +1,359 @@
+use std::thread;
+
+use fnv::{FnvHashMap, FnvHashSet};
+
+use config::WORD_SIZE;
+use ir::*;
+use operand::{Label, Reg, Temp};
+use interpret::{Signal, exit_code, to_bytes};
+use interpret::memory::Memory;
+use interpret::runtime::{EXTERNALS, Runtime};
+
+/// Native stack reserved for each thread of the recursive interpreter.
+const HOST_STACK_SIZE: usize = 1 << 26;
+
+/// Every this many nested calls, the interpreter continues on a new thread with a
+/// fresh native stack, so that only the emulated stack limits how deep programs recurse.
+const CALLS_PER_THREAD: usize = 1 << 12;
+
+/// Executes [unit] starting from `main`, returning the process exit code.
+///
+/// Works at any stage of the IR pipeline: nested `Seq` and `ESeq` trees are
+/// supported, with jumps resolved against the closest enclosing sequence.
+pub fn interpret_ir(unit: &Unit) -> i32 {
+
+    // Symbols are thread-local, so all names must be resolved up front
+    let mut interpreter = Interpreter::new(unit);
+    let main = Label::from_fixed("main");
+
+    on_new_stack(move || interpreter.run(main))
+}
+
+/// Runs [f] to completion on a new thread with [HOST_STACK_SIZE] of native stack.
+fn on_new_stack<T: Send, F: FnOnce() -> T + Send>(f: F) -> T {
+    thread::scope(|scope| {
+        thread::Builder::new()
+            .stack_size(HOST_STACK_SIZE)
+            .spawn_scoped(scope, f)
+            .expect("Internal error: spawning interpreter thread")
+            .join()
+            .expect("Internal error: interpreter thread panicked")
+    })
+}
+
+struct Interpreter<'unit> {
+    functions: FnvHashMap<Label, &'unit Function>,
+    externals: FnvHashMap<Label, &'static str>,
+    labels: FnvHashMap<Label, i64>,
+    defined: FnvHashSet<Label>,
+    registers: FnvHashMap<Reg, i64>,
+    memory: Memory,
+    runtime: Runtime,
+    calls_left: usize,
+}
+
+impl <'unit> Interpreter<'unit> {
+
+    fn new(unit: &'unit Unit) -> Self {
+        let mut memory = Memory::default();
+        let registers = hashmap! {
+            Reg::RSP => memory.stack_top(),
+            Reg::RBP => memory.stack_top()
+        };
+
+        // Copy string literals from the data section onto the heap
+        let addresses = unit.data.iter()
+            .map(|data| {
+                let bytes = to_bytes(data.data);
+                let address = memory.malloc_str(&bytes)
+                    .expect("Internal error: allocating data");
+                (data.label, address)
+            })
+            .collect();
+
+        Interpreter {
+            functions: unit.functions.iter()
+                .map(|function| (function.label, function))
+                .collect(),
+            externals: EXTERNALS.iter()
+                .map(|name| (Label::from_fixed(name), *name))
+                .collect(),
+            labels: addresses,
+            defined: unit.functions.iter()
+                .flat_map(|function| function.body.iter())
+                .flat_map(labels)
+                .collect(),
+            registers,
+            memory,
+            runtime: Runtime::default(),
+            calls_left: CALLS_PER_THREAD,
+        }
+    }
+
+    fn run(&mut self, main: Label) -> i32 {
+        let result = self.call(main, Vec::new());
+        self.runtime.flush();
+        match result {
+        | Ok(_)       => 0,
+        | Err(signal) => exit_code(signal),
+        }
+    }
+
+    fn push(&mut self, value: i64) -> Result<(), Signal> {
+        let rsp = self.registers[&Reg::RSP] - WORD_SIZE as i64;
+        self.registers.insert(Reg::RSP, rsp);
+        self.memory.store(rsp, value)
+    }
+
+    fn pop(&mut self) -> Result<i64, Signal> {
+        let rsp = self.registers[&Reg::RSP];
+        self.registers.insert(Reg::RSP, rsp + WORD_SIZE as i64);
+        self.memory.load(rsp)
+    }
+
+    /// Calls [label] with [args], following the System V calling convention
+    /// for stack layout so that frame offsets match compiled code.
+    fn call(&mut self, label: Label, args: Vec<i64>) -> Result<i64, Signal> {
+
+        let function = match (self.functions.get(&label), self.externals.get(&label)) {
+        | (Some(function), _) => *function,
+        | (None, Some(name))  => return self.runtime.call(&mut self.memory, name, &args),
+        | (None, None)        => panic!("Internal error: call to unknown function"),
+        };
+
+        // Arguments past the sixth are passed on the stack
+        for arg in args.iter().skip(6).rev() {
+            self.push(*arg)?;
+        }
+
+        let mut temps = FnvHashMap::default();
+        for (i, arg) in args.iter().take(6).enumerate() {
+            self.registers.insert(Reg::get_argument(i), *arg);
+        }
+
+        // Return address and saved base pointer
+        self.push(0)?;
+        let rbp = self.registers[&Reg::RBP];
+        self.push(rbp)?;
+        let rsp = self.registers[&Reg::RSP];
+        self.registers.insert(Reg::RBP, rsp);
+        self.registers.insert(Reg::RSP, rsp - function.escapes as i64 * WORD_SIZE as i64);
+
+        // Running out of the emulated stack above is the only stack overflow
+        let result = if self.calls_left == 0 {
+            self.calls_left = CALLS_PER_THREAD;
+            let interpreter = &mut *self;
+            let result = on_new_stack(move || interpreter.exec_seq(&mut temps, &function.body, None));
+            self.calls_left = 0;
+            result
+        } else {
+            self.calls_left -= 1;
+            let result = self.exec_seq(&mut temps, &function.body, None);
+            self.calls_left += 1;
+            result
+        };
+
+        match result {
+        | Err(Signal::Jump(_))     => panic!("Internal error: jump to missing label"),
+        | Err(signal)              => return Err(signal),
+        | Ok(())                   => (),
+        }
+
+        // Restore caller frame
+        let rbp = self.registers[&Reg::RBP];
+        self.registers.insert(Reg::RSP, rbp);
+        let rbp = self.pop()?;
+        self.registers.insert(Reg::RBP, rbp);
+        self.pop()?;
+        let rsp = self.registers[&Reg::RSP] + args.len().saturating_sub(6) as i64 * WORD_SIZE as i64;
+        self.registers.insert(Reg::RSP, rsp);
+
+        Ok(self.registers.get(&Reg::get_return()).cloned().unwrap_or(0))
+    }
+
+    /// Executes [stms] from the beginning, or from [target] if provided.
+    ///
+    /// Jumps to labels outside of [stms] are propagated to the caller.
+    fn exec_seq(&mut self, temps: &mut FnvHashMap<Temp, i64>, stms: &[Stm], mut target: Option<Label>) -> Result<(), Signal> {
+
+        let mut i = 0;
+
+        loop {
+            if let Some(label) = target.take() {
+
+                let j = match stms.iter().position(|stm| contains(stm, label)) {
+                | Some(j) => j,
+                | None    => return Err(Signal::Jump(label)),
+                };
+
+                let result = match &stms[j] {
+                | Stm::Seq(inner) => self.exec_seq(temps, inner, Some(label)),
+                | _               => Ok(()),
+                };
+
+                match result {
+                | Ok(())                   => i = j + 1,
+                | Err(Signal::Jump(label)) => { target = Some(label); continue },
+                | Err(signal)              => return Err(signal),
+                }
+            }
+
+            if i >= stms.len() { return Ok(()) }
+
+            match self.exec_stm(temps, &stms[i]) {
+            | Ok(())                   => i += 1,
+            | Err(Signal::Jump(label)) => target = Some(label),
+            | Err(signal)              => return Err(signal),
+            }
+        }
+    }
+
+    fn exec_stm(&mut self, temps: &mut FnvHashMap<Temp, i64>, stm: &Stm) -> Result<(), Signal> {
+        match stm {
+        | Stm::Move(src, dst) => {
+            // The source is evaluated before the destination, as canonize orders them
+            let value = self.eval(temps, src)?;
+            self.assign(temps, dst, value)
+        },
+        | Stm::Exp(exp) => self.eval(temps, exp).map(|_| ()),
+        | Stm::Jump(Exp::Name(label), _) => Err(Signal::Jump(*label)),
+        | Stm::Jump(_, _) => panic!("Internal error: computed jump"),
+        | Stm::CJump(lhs, op, rhs, t, f) => {
+            let lhs = self.eval(temps, lhs)?;
+            let rhs = self.eval(temps, rhs)?;
+            let taken = match op {
+            | Relop::Eq => lhs == rhs,
+            | Relop::Ne => lhs != rhs,
+            | Relop::Lt => lhs < rhs,
+            | Relop::Gt => lhs > rhs,
+            | Relop::Le => lhs <= rhs,
+            | Relop::Ge => lhs >= rhs,
+            };
+            // Reordered IR falls through to the false branch
+            match (taken, self.defined.contains(f)) {
+            | (true, _)      => Err(Signal::Jump(*t)),
+            | (false, true)  => Err(Signal::Jump(*f)),
+            | (false, false) => Ok(()),
+            }
+        },
+        | Stm::Seq(stms) => self.exec_seq(temps, stms, None),
+        | Stm::Label(_)
+        | Stm::Comment(_) => Ok(()),
+        }
+    }
+
+    /// Stores [value] into the temporary or memory that [dst] refers to.
+    fn assign(&mut self, temps: &mut FnvHashMap<Temp, i64>, dst: &Exp, value: i64) -> Result<(), Signal> {
+        match dst {
+        | Exp::Temp(temp) => {
+            self.write(temps, *temp, value);
+            Ok(())
+        },
+        | Exp::Mem(address) => {
+            let address = self.eval(temps, address)?;
+            self.memory.store(address, value)
+        },
+        | Exp::ESeq(stm, dst) => {
+            self.exec_stm(temps, stm)?;
+            self.assign(temps, dst, value)
+        },
+        | _ => panic!("Internal error: move into non-lvalue"),
+        }
+    }
+
+    fn eval(&mut self, temps: &mut FnvHashMap<Temp, i64>, exp: &Exp) -> Result<i64, Signal> {
+        match exp {
+        | Exp::Const(n) => Ok(*n),
+        | Exp::Name(label) => {
+            Ok(*self.labels.get(label).expect("Internal error: address of unknown label"))
+        },
+        | Exp::Temp(temp) => Ok(self.read(temps, *temp)),
+        | Exp::Binop(lhs, op, rhs) => {
+            let lhs = self.eval(temps, lhs)?;
+            let rhs = self.eval(temps, rhs)?;
+            binop(lhs, *op, rhs)
+        },
+        | Exp::Mem(address) => {
+            let address = self.eval(temps, address)?;
+            self.memory.load(address)
+        },
+        | Exp::Call(name, args) => {
+            let label = match **name {
+            | Exp::Name(label) => label,
+            | _                => panic!("Internal error: computed call"),
+            };
+            let mut values = Vec::with_capacity(args.len());
+            for arg in args {
+                values.push(self.eval(temps, arg)?);
+            }
+            self.call(label, values)
+        },
+        | Exp::ESeq(stm, exp) => {
+            self.exec_stm(temps, stm)?;
+            self.eval(temps, exp)
+        },
+        }
+    }
+
+    fn read(&self, temps: &FnvHashMap<Temp, i64>, temp: Temp) -> i64 {
+        match temp {
+        | Temp::Reg(reg) => self.registers.get(&reg).cloned().unwrap_or(0),
+        | _              => *temps.get(&temp).expect("Internal error: read of undefined temp"),
+        }
+    }
+
+    fn write(&mut self, temps: &mut FnvHashMap<Temp, i64>, temp: Temp, value: i64) {
+        match temp {
+        | Temp::Reg(reg) => { self.registers.insert(reg, value); },
+        | _              => { temps.insert(temp, value); },
+        }
+    }
+}
+
+/// Collects the labels defined anywhere within [stm], including inside `ESeq`s.
+fn labels(stm: &Stm) -> Vec<Label> {
+    match stm {
+    | Stm::Label(label)        => vec![*label],
+    | Stm::Seq(stms)           => stms.iter().flat_map(labels).collect(),
+    | Stm::Exp(exp)            => exp_labels(exp),
+    | Stm::Move(src, dst)      => exp_labels(src).into_iter().chain(exp_labels(dst)).collect(),
+    | Stm::CJump(l, _, r, _, _) => exp_labels(l).into_iter().chain(exp_labels(r)).collect(),
+    | Stm::Jump(_, _)
+    | Stm::Comment(_)          => Vec::new(),
+    }
+}
+
+fn exp_labels(exp: &Exp) -> Vec<Label> {
+    match exp {
+    | Exp::Binop(l, _, r) => exp_labels(l).into_iter().chain(exp_labels(r)).collect(),
+    | Exp::Mem(exp)       => exp_labels(exp),
+    | Exp::Call(_, args)  => args.iter().flat_map(exp_labels).collect(),
+    | Exp::ESeq(stm, exp) => labels(stm).into_iter().chain(exp_labels(exp)).collect(),
+    | Exp::Const(_)
+    | Exp::Name(_)
+    | Exp::Temp(_)        => Vec::new(),
+    }
+}
+
+/// Returns true if [label] is defined by [stm] or any nested sequence.
+fn contains(stm: &Stm, label: Label) -> bool {
+    match stm {
+    | Stm::Label(other) => *other == label,
+    | Stm::Seq(stms)    => stms.iter().any(|stm| contains(stm, label)),
+    | _                 => false,
+    }
+}
+
+/// Evaluates [op] with 64-bit wrapping semantics, matching the generated assembly.
+pub fn binop(lhs: i64, op: Binop, rhs: i64) -> Result<i64, Signal> {
+    match op {
+    | Binop::Add => Ok(lhs.wrapping_add(rhs)),
+    | Binop::Sub => Ok(lhs.wrapping_sub(rhs)),
+    | Binop::Mul => Ok(lhs.wrapping_mul(rhs)),
+    | Binop::Div => lhs.checked_div(rhs).ok_or(Signal::Arithmetic),
+    | Binop::Mod => lhs.checked_rem(rhs).ok_or(Signal::Arithmetic),
+    | Binop::And => Ok(lhs & rhs),
+    | Binop::Or  => Ok(lhs | rhs),
+    | Binop::XOr => Ok(lhs ^ rhs),
+    }
+}
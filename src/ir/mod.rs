@@ -4,6 +4,11 @@ use simple_symbol::Symbol;
 use asm;
 use translate::Frame;
 use operand::*;
+use util::escape;
+
+mod verify;
+
+pub use self::verify::{verify, Form, Violation};
 
 #[derive(Debug)]
 pub struct Unit {
@@ -64,9 +69,13 @@ pub struct Data {
 
 impl Data {
     pub fn new(data: Symbol) -> Self {
+        Data::with_label(Label::from_str("STRING"), data)
+    }
+
+    pub fn with_label(label: Label, data: Symbol) -> Self {
         Data {
             id: DataID::next(),
-            label: Label::from_str("STRING"),
+            label,
             data,
         }
     }
@@ -78,9 +87,9 @@ pub enum Tree {
     Cx(Cond),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Exp {
-    Const(i32),
+    Const(i64),
     Name(Label),
     Temp(Temp),
     Binop(Box<Exp>, Binop, Box<Exp>),
@@ -109,6 +118,7 @@ impl From<Tree> for Exp {
                     gen_stm(t, f),
                     Stm::Label(f),
                     Stm::Move(Exp::Const(0), Exp::Temp(r)),
+                    Stm::Jump(Exp::Name(t), vec![t]),
                     Stm::Label(t),
                 ])),
                 Box::new(Exp::Temp(r)),
@@ -124,7 +134,7 @@ impl From<Exp> for Tree {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Stm {
     Move(Exp, Exp),
     Exp(Exp),
@@ -141,9 +151,13 @@ impl From<Tree> for Stm {
         | Tree::Nx(stm) => stm,
         | Tree::Ex(exp) => Stm::Exp(exp),
         | Tree::Cx(gen_stm) => {
-            let t = Label::from_str("TRUE_BRANCH");
-            let f = Label::from_str("FALSE_BRANCH");
-            gen_stm(t, f)
+
+            // Only the effects of the condition matter, so both branches rejoin after it
+            let join = Label::from_str("COND_JOIN");
+            Stm::Seq(vec![
+                gen_stm(join, join),
+                Stm::Label(join),
+            ])
         },
         }
     }
@@ -155,7 +169,7 @@ impl From<Stm> for Tree {
     }
 }
 
-pub type Cond = Box<Fn(Label, Label) -> Stm>;
+pub type Cond = Box<dyn Fn(Label, Label) -> Stm>;
 
 impl From<Tree> for Cond {
     fn from(tree: Tree) -> Self {
@@ -181,7 +195,7 @@ impl From<Cond> for Tree {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Binop {
     Add,
     Sub,
@@ -213,7 +227,7 @@ impl Binop {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Relop {
     Eq,
     Ne,
@@ -239,6 +253,14 @@ impl Relop {
 
 impl fmt::Display for Unit {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        for data in &self.data {
+            writeln!(fmt, "(DATA {} \"{}\")", data.label, escape(&data.data.to_string()))?;
+        }
+
+        if !self.data.is_empty() {
+            writeln!(fmt)?;
+        }
+
         for function in &self.functions {
             write!(fmt, "{}\n\n", function)?;
         }
@@ -249,7 +271,7 @@ impl fmt::Display for Unit {
 
 impl fmt::Display for Function {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(fmt, "{}", self.label)?;
+        write!(fmt, "{} (ESCAPES {})", self.label, self.escapes)?;
 
         for stm in &self.body {
             write!(fmt, "\n    {}", stm)?;
@@ -286,7 +308,7 @@ impl fmt::Display for Stm {
         | Stm::Jump(e, _)            => write!(fmt, "(JUMP {})", e),
         | Stm::CJump(l, op, r, t, f) => write!(fmt, "(CJUMP {} {} {} {} {})", l, op, r, t, f),
         | Stm::Label(l)              => write!(fmt, "(LABEL {})", l),
-        | Stm::Comment(c)            => write!(fmt, "(COMMENT {})", c),
+        | Stm::Comment(c)            => write!(fmt, "(COMMENT \"{}\")", escape(c)),
         | Stm::Seq(stms)                => {
             write!(fmt, "(SEQ").unwrap();
             for stm in stms {
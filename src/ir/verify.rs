@@ -0,0 +1,214 @@
+use std::fmt;
+
+use fnv::FnvHashSet;
+
+use ir::*;
+use operand::Label;
+
+/// Form that IR is in after a phase. Every jump target must be a label in the same function.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Form {
+
+    /// Straight out of translation.
+    Translated,
+
+    /// Function bodies are flat lists without [Seq] or [ESeq], and calls are only
+    /// made directly by a [Move] to a temporary or an [Exp] statement. Labels are
+    /// never reached by falling through, since basic blocks are split at jumps.
+    Canonical,
+
+    /// Canonical, except that labels can be reached by falling through, and every
+    /// [CJump] is immediately followed by its false label.
+    Reordered,
+}
+
+impl fmt::Display for Form {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+        | Form::Translated => write!(fmt, "translated"),
+        | Form::Canonical  => write!(fmt, "canonical"),
+        | Form::Reordered  => write!(fmt, "reordered"),
+        }
+    }
+}
+
+/// Statement that breaks an invariant of the form its function should be in.
+#[derive(Clone, Debug)]
+pub struct Violation {
+    pub function: Label,
+
+    /// Position in the function body of the top-level statement containing [stm].
+    pub index: usize,
+    pub stm: Stm,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}: {}: {}", self.function, self.reason, self.stm)
+    }
+}
+
+/// Checks that every function in [unit] is in [form].
+pub fn verify(unit: &Unit, form: Form) -> Result<(), Vec<Violation>> {
+    let mut violations = Vec::new();
+
+    for function in &unit.functions {
+        let mut labels = FnvHashSet::default();
+        for stm in &function.body {
+            collect_stm(stm, &mut labels);
+        }
+
+        let mut verifier = Verifier {
+            function: function.label,
+            index: 0,
+            form,
+            labels,
+            violations: &mut violations,
+        };
+
+        for (i, stm) in function.body.iter().enumerate() {
+            verifier.index = i;
+            verifier.verify_stm(stm);
+
+            match (form, stm) {
+            | (Form::Canonical, Stm::Label(_)) => match i.checked_sub(1).map(|i| &function.body[i]) {
+                | Some(Stm::Jump(..))
+                | Some(Stm::CJump(..)) => (),
+                | _ => verifier.report(stm, "label reached by falling through"),
+            },
+            | (Form::Reordered, Stm::CJump(_, _, _, _, f_label)) => match function.body.get(i + 1) {
+                | Some(Stm::Label(label)) if label == f_label => (),
+                | _ => verifier.report(stm, "false label does not follow conditional jump"),
+            },
+            | _ => (),
+            }
+        }
+    }
+
+    if violations.is_empty() { Ok(()) } else { Err(violations) }
+}
+
+fn collect_stm(stm: &Stm, labels: &mut FnvHashSet<Label>) {
+    match stm {
+    | Stm::Label(label) => { labels.insert(*label); },
+    | Stm::Seq(stms)    => for stm in stms { collect_stm(stm, labels) },
+    | Stm::Move(src, dst) => {
+        collect_exp(src, labels);
+        collect_exp(dst, labels);
+    },
+    | Stm::Exp(exp)
+    | Stm::Jump(exp, _) => collect_exp(exp, labels),
+    | Stm::CJump(l, _, r, _, _) => {
+        collect_exp(l, labels);
+        collect_exp(r, labels);
+    },
+    | Stm::Comment(_) => (),
+    }
+}
+
+fn collect_exp(exp: &Exp, labels: &mut FnvHashSet<Label>) {
+    match exp {
+    | Exp::Const(_)
+    | Exp::Name(_)
+    | Exp::Temp(_) => (),
+    | Exp::Binop(l, _, r) => {
+        collect_exp(l, labels);
+        collect_exp(r, labels);
+    },
+    | Exp::Mem(exp) => collect_exp(exp, labels),
+    | Exp::Call(name, args) => {
+        collect_exp(name, labels);
+        for arg in args { collect_exp(arg, labels) }
+    },
+    | Exp::ESeq(stm, exp) => {
+        collect_stm(stm, labels);
+        collect_exp(exp, labels);
+    },
+    }
+}
+
+struct Verifier<'a> {
+    function: Label,
+    index: usize,
+    form: Form,
+    labels: FnvHashSet<Label>,
+    violations: &'a mut Vec<Violation>,
+}
+
+impl <'a> Verifier<'a> {
+
+    fn report(&mut self, stm: &Stm, reason: &'static str) {
+        self.violations.push(Violation { function: self.function, index: self.index, stm: stm.clone(), reason });
+    }
+
+    fn canonical(&self) -> bool {
+        self.form >= Form::Canonical
+    }
+
+    fn verify_targets(&mut self, stm: &Stm, targets: &[Label]) {
+        if targets.iter().any(|target| !self.labels.contains(target)) {
+            self.report(stm, "jump to a label outside of the function");
+        }
+    }
+
+    fn verify_stm(&mut self, stm: &Stm) {
+        match stm {
+        | Stm::Move(Exp::Call(name, args), Exp::Temp(_))
+        | Stm::Exp(Exp::Call(name, args)) => {
+            self.verify_exp(stm, name);
+            for arg in args { self.verify_exp(stm, arg) }
+        },
+        | Stm::Move(src, dst) => {
+            match dst {
+            | Exp::Temp(_)
+            | Exp::Mem(_)
+            | Exp::ESeq(_, _) => (),
+            | _               => self.report(stm, "move to something other than a temporary or memory"),
+            }
+            self.verify_exp(stm, src);
+            self.verify_exp(stm, dst);
+        },
+        | Stm::Exp(exp) => self.verify_exp(stm, exp),
+        | Stm::Jump(exp, targets) => {
+            self.verify_exp(stm, exp);
+            self.verify_targets(stm, targets);
+        },
+        | Stm::CJump(l, _, r, t_label, f_label) => {
+            self.verify_exp(stm, l);
+            self.verify_exp(stm, r);
+            self.verify_targets(stm, &[*t_label, *f_label]);
+        },
+        | Stm::Seq(stms) => {
+            if self.canonical() { self.report(stm, "sequence in canonical IR") }
+            for stm in stms { self.verify_stm(stm) }
+        },
+        | Stm::Label(_)
+        | Stm::Comment(_) => (),
+        }
+    }
+
+    /// Checks [exp], which is part of [stm] but isn't itself a call that [stm] makes directly.
+    fn verify_exp(&mut self, stm: &Stm, exp: &Exp) {
+        match exp {
+        | Exp::Const(_)
+        | Exp::Name(_)
+        | Exp::Temp(_) => (),
+        | Exp::Binop(l, _, r) => {
+            self.verify_exp(stm, l);
+            self.verify_exp(stm, r);
+        },
+        | Exp::Mem(exp) => self.verify_exp(stm, exp),
+        | Exp::Call(name, args) => {
+            if self.canonical() { self.report(stm, "call that isn't moved to a temporary or discarded") }
+            self.verify_exp(stm, name);
+            for arg in args { self.verify_exp(stm, arg) }
+        },
+        | Exp::ESeq(inner, exp) => {
+            if self.canonical() { self.report(stm, "ESEQ in canonical IR") }
+            self.verify_stm(inner);
+            self.verify_exp(stm, exp);
+        },
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+/// Minimal JSON value, for exchanging structured data with other tools.
+///
+/// Object members keep their insertion order so that output is deterministic.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Arr(Vec<Json>),
+    Obj(Vec<(String, Json)>),
+}
+
+impl Json {
+
+    pub fn object(members: Vec<(&str, Json)>) -> Self {
+        Json::Obj(members.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+    }
+
+    /// Looks up member [key] of an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+        | Json::Obj(members) => members.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+        | _                  => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+        | Json::Bool(b) => Some(*b),
+        | _             => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+        | Json::Int(n) => Some(*n),
+        | _            => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+        | Json::Str(s) => Some(s),
+        | _            => None,
+        }
+    }
+
+    pub fn as_arr(&self) -> Option<&[Json]> {
+        match self {
+        | Json::Arr(values) => Some(values),
+        | _                 => None,
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self { Json::Bool(b) }
+}
+
+impl From<i64> for Json {
+    fn from(n: i64) -> Self { Json::Int(n) }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self { Json::Int(n as i64) }
+}
+
+impl <'a> From<&'a str> for Json {
+    fn from(s: &'a str) -> Self { Json::Str(s.to_string()) }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self { Json::Str(s) }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(values: Vec<Json>) -> Self { Json::Arr(values) }
+}
+
+impl <T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        match value {
+        | Some(value) => value.into(),
+        | None        => Json::Null,
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+        | Json::Null      => write!(fmt, "null"),
+        | Json::Bool(b)   => write!(fmt, "{}", b),
+        | Json::Int(n)    => write!(fmt, "{}", n),
+        | Json::Float(n)  => if n.is_finite() { write!(fmt, "{:?}", n) } else { write!(fmt, "null") },
+        | Json::Str(s)    => write_str(fmt, s),
+        | Json::Arr(values) => {
+            write!(fmt, "[")?;
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 { write!(fmt, ",")?; }
+                write!(fmt, "{}", value)?;
+            }
+            write!(fmt, "]")
+        },
+        | Json::Obj(members) => {
+            write!(fmt, "{{")?;
+            for (i, (key, value)) in members.iter().enumerate() {
+                if i > 0 { write!(fmt, ",")?; }
+                write_str(fmt, key)?;
+                write!(fmt, ":{}", value)?;
+            }
+            write!(fmt, "}}")
+        },
+        }
+    }
+}
+
+fn write_str(fmt: &mut fmt::Formatter, s: &str) -> Result<(), fmt::Error> {
+    write!(fmt, "\"")?;
+    for c in s.chars() {
+        match c {
+        | '"'  => write!(fmt, "\\\"")?,
+        | '\\' => write!(fmt, "\\\\")?,
+        | '\n' => write!(fmt, "\\n")?,
+        | '\r' => write!(fmt, "\\r")?,
+        | '\t' => write!(fmt, "\\t")?,
+        | c if (c as u32) < 0x20 => write!(fmt, "\\u{:04x}", c as u32)?,
+        | c    => write!(fmt, "{}", c)?,
+        }
+    }
+    write!(fmt, "\"")
+}
+
+impl FromStr for Json {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser(s.chars().peekable());
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        match parser.0.next() {
+        | None    => Ok(value),
+        | Some(c) => Err(format!("unexpected [{}] after JSON value", c)),
+        }
+    }
+}
+
+struct Parser<'s>(Peekable<Chars<'s>>);
+
+impl <'s> Parser<'s> {
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = self.0.peek() {
+            self.0.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.0.next() {
+        | Some(c) if c == expected => Ok(()),
+        | Some(c) => Err(format!("expected [{}] but found [{}]", expected, c)),
+        | None    => Err(format!("expected [{}] but found end of input", expected)),
+        }
+    }
+
+    fn expect_word(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for c in word.chars() { self.expect(c)?; }
+        Ok(value)
+    }
+
+    fn parse_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.0.peek().cloned() {
+        | Some('n') => self.expect_word("null", Json::Null),
+        | Some('t') => self.expect_word("true", Json::Bool(true)),
+        | Some('f') => self.expect_word("false", Json::Bool(false)),
+        | Some('"') => self.parse_str().map(Json::Str),
+        | Some('[') => self.parse_arr(),
+        | Some('{') => self.parse_obj(),
+        | Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+        | Some(c)   => Err(format!("unexpected [{}]", c)),
+        | None      => Err("unexpected end of input".to_string()),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Json, String> {
+        let mut number = String::new();
+        while let Some(&c) = self.0.peek() {
+            if !(c.is_ascii_digit() || c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E') { break }
+            number.push(c);
+            self.0.next();
+        }
+        if let Ok(n) = i64::from_str(&number) { return Ok(Json::Int(n)) }
+        f64::from_str(&number)
+            .map(Json::Float)
+            .map_err(|_| format!("invalid number [{}]", number))
+    }
+
+    fn parse_str(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.0.next() {
+            | Some('"')  => return Ok(string),
+            | Some('\\') => {
+                let c = match self.0.next() {
+                | Some('"')  => '"',
+                | Some('\\') => '\\',
+                | Some('/')  => '/',
+                | Some('b')  => '\u{8}',
+                | Some('f')  => '\u{c}',
+                | Some('n')  => '\n',
+                | Some('r')  => '\r',
+                | Some('t')  => '\t',
+                | Some('u')  => self.parse_unicode()?,
+                | Some(c)    => return Err(format!("invalid escape [\\{}]", c)),
+                | None       => return Err("unterminated string".to_string()),
+                };
+                string.push(c);
+            },
+            | Some(c) => string.push(c),
+            | None    => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn parse_hex(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.0.next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| "invalid unicode escape".to_string())?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    /// Decodes the digits of a `\u` escape, combining surrogate pairs.
+    fn parse_unicode(&mut self) -> Result<char, String> {
+        let high = self.parse_hex()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            self.expect('\\')?;
+            self.expect('u')?;
+            let low = self.parse_hex()?;
+            0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF)
+        } else {
+            high
+        };
+        Ok(std::char::from_u32(code).unwrap_or('\u{FFFD}'))
+    }
+
+    fn parse_arr(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if let Some(']') = self.0.peek() {
+            self.0.next();
+            return Ok(Json::Arr(values))
+        }
+        loop {
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.0.next() {
+            | Some(',') => continue,
+            | Some(']') => return Ok(Json::Arr(values)),
+            | _         => return Err("expected [,] or []] in array".to_string()),
+            }
+        }
+    }
+
+    fn parse_obj(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if let Some('}') = self.0.peek() {
+            self.0.next();
+            return Ok(Json::Obj(members))
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_str()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            members.push((key, self.parse_value()?));
+            self.skip_whitespace();
+            match self.0.next() {
+            | Some(',') => continue,
+            | Some('}') => return Ok(Json::Obj(members)),
+            | _         => return Err("expected [,] or [}] in object".to_string()),
+            }
+        }
+    }
+}
@@ -1,9 +1,11 @@
+use std::mem;
 use std::str::CharIndices;
 use std::str::FromStr;
 
 use codespan::{ByteIndex, ByteOffset, FileMap};
 use simple_symbol::store;
 
+use config::Config;
 use lex::Spanned;
 use token::Token;
 use error::{Error, LexError};
@@ -11,6 +13,9 @@ use span::Span;
 
 pub struct Lexer<'input> {
     mode: Mode,
+    config: Config,
+    comments: Vec<ByteIndex>,
+    closed: Vec<Span>,
     source: &'input FileMap,
     stream: CharIndices<'input>,
     next: Option<(usize, char)>,
@@ -53,10 +58,16 @@ fn is_whitespace(c: char) -> bool {
 
 impl <'input> Lexer<'input> {
 
-    pub fn new(source: &'input FileMap) -> Self {
+    pub fn new(source: &'input FileMap, config: Config) -> Self {
         let mut stream = source.src().char_indices();
         let next = stream.next();
-        Lexer { mode: Mode::Source, source, stream, next }
+        Lexer { mode: Mode::Source, config, comments: Vec::new(), closed: Vec::new(), source, stream, next }
+    }
+
+    /// Spans of the outermost comments lexed so far, which tools like
+    /// the formatter need since comments never become tokens.
+    pub fn take_comments(&mut self) -> Vec<Span> {
+        mem::take(&mut self.closed)
     }
 
     fn skip(&mut self) {
@@ -112,12 +123,108 @@ impl <'input> Lexer<'input> {
         self.take_while(start, is_digit)
     }
 
-    fn take_string(&mut self, start: ByteIndex) -> (ByteIndex, &'input str) {
-        if !self.test_peek(|c| c == '"') { return (start, "") }
+    fn take_string(&mut self, start: ByteIndex) -> Result<Spanned, Error> {
+
+        // Skip opening quotation mark
         self.skip();
-        let (end, _) = self.take_until(start, |c| c == '"');
+
+        // Each char of the string stands for a single byte
+        let mut string = String::new();
+
+        loop {
+            match self.peek() {
+            | None => {
+                let eof = self.source.span().end();
+                return Err(Error::lexical(start, eof, LexError::UnterminatedString))
+            },
+            | Some((end, '"')) => {
+                self.skip();
+                return Ok((start, Token::Str(string), end + ByteOffset(1)))
+            },
+            | Some((escape, '\\')) => {
+                self.skip();
+                if let Some(byte) = self.take_escape(escape)? {
+                    string.push(byte as char);
+                }
+            },
+            | Some((_, c)) => {
+                self.skip();
+                let mut buffer = [0; 4];
+                for byte in c.encode_utf8(&mut buffer).bytes() {
+                    string.push(byte as char);
+                }
+            },
+            }
+        }
+    }
+
+    /// Lexes the remainder of an escape sequence beginning with the backslash at [start],
+    /// returning the escaped byte, or [None] for an ignored `\f___f\` sequence.
+    fn take_escape(&mut self, start: ByteIndex) -> Result<Option<u8>, Error> {
+
+        let (index, c) = match self.peek() {
+        | None       => return Err(Error::lexical(start, start + ByteOffset(1), LexError::UnterminatedString)),
+        | Some(next) => next,
+        };
+
         self.skip();
-        (end + ByteOffset(1), self.slice(start, end))
+        let end = index + ByteOffset(c.len_utf8() as i64);
+
+        match c {
+        | 'n'  => Ok(Some(b'\n')),
+        | 't'  => Ok(Some(b'\t')),
+        | '"'  => Ok(Some(b'"')),
+        | '\\' => Ok(Some(b'\\')),
+        | '^'  => {
+
+            // Control characters are offset from their printable counterparts
+            let (end, control) = match self.peek() {
+            | Some((index, c)) => { self.skip(); (index + ByteOffset(c.len_utf8() as i64), c) },
+            | None             => (end, '\0'),
+            };
+
+            match control {
+            | '@' ..= '_' => Ok(Some(control as u8 - b'@')),
+            | 'a' ..= 'z' => Ok(Some(control as u8 - b'`')),
+            | '?'         => Ok(Some(0x7F)),
+            | _           => Err(Error::lexical(start, end, LexError::InvalidControl)),
+            }
+        },
+        | '0' ..= '9' => {
+
+            // Exactly three decimal digits are required
+            let mut value = c.to_digit(10).unwrap();
+            let mut end = end;
+
+            for _ in 0..2 {
+                match self.peek() {
+                | Some((index, c)) if is_digit(c) => {
+                    self.skip();
+                    value = value * 10 + c.to_digit(10).unwrap();
+                    end = index + ByteOffset(1);
+                },
+                | _ => return Err(Error::lexical(start, end, LexError::InvalidDecimal)),
+                }
+            }
+
+            if value > 255 {
+                return Err(Error::lexical(start, end, LexError::DecimalRange))
+            }
+
+            Ok(Some(value as u8))
+        },
+        | c if is_whitespace(c) => {
+
+            // Ignore whitespace up until the closing backslash
+            let (end, _) = self.take_while(end, is_whitespace);
+
+            match self.peek() {
+            | Some((_, '\\')) => { self.skip(); Ok(None) },
+            | _               => Err(Error::lexical(start, end, LexError::UnterminatedFormat)),
+            }
+        },
+        | _ => Err(Error::lexical(start, end, LexError::InvalidEscape)),
+        }
     }
 
 }
@@ -144,8 +251,6 @@ impl <'input> Iterator for Lexer<'input> {
 
     fn next(&mut self) -> Option<Self::Item> {
 
-        let mut comment_level = 0;
-
         while let Some((start, c)) = self.peek() {
             
             if is_whitespace(c) { self.skip(); continue }
@@ -175,7 +280,7 @@ impl <'input> Iterator for Lexer<'input> {
                     | ':' => if self.test_peek(|c| c == '=') { (true, Token::Assign) } else { (false, Token::Colon) },
                     | '>' => if self.test_peek(|c| c == '=') { (true, Token::Ge) } else { (false, Token::Gt) }
                     | '*' => if self.test_peek(|c| c == '/') { return error(start, start + ByteOffset(2), LexError::Comment) } else { (false, Token::Mul) },
-                    | '/' => if self.test_peek(|c| c == '*') { self.mode = Mode::Comment; self.skip(); continue } else { (false, Token::Div) },
+                    | '/' => if self.test_peek(|c| c == '*') { self.mode = Mode::Comment; self.comments.push(start); self.skip(); continue } else { (false, Token::Div) },
                     | '<' => {
                         if self.test_peek(|c| c == '=')      { (true, Token::Le) }
                         else if self.test_peek(|c| c == '>') { (true, Token::Neq) }
@@ -223,22 +328,17 @@ impl <'input> Iterator for Lexer<'input> {
                 | id => return success(start, end, Token::Ident(store(id))),
                 };
 
-                // Check for literal int
+                // Check for literal int, which may be one past the largest int if negated
                 match self.take_int(start) {
-                | (_, "")                              => (),
-                | (end, n) if i32::from_str(n).is_ok() => return success(start, end, Token::Int(i32::from_str(n).unwrap())),
-                | (end, _)                             => return error(start, end, LexError::Integer),
+                | (_, "")  => (),
+                | (end, n) => match u64::from_str(n) {
+                    | Ok(n) if n <= self.config.max_magnitude() => return success(start, end, Token::Int(n)),
+                    | _                                         => return error(start, end, self.config.int_error()),
+                    },
                 };
 
                 // Check for literal string
-                match self.take_string(start) {
-                | (_, "")  => (),
-                | (end, _) => {
-                    // Cut off literal quotation marks
-                    let string = String::from(self.slice(start + ByteOffset(1), end - ByteOffset(1)));
-                    return success(start, end, Token::Str(string));
-                },
-                };
+                if c == '"' { return Some(self.take_string(start)) }
 
                 // Failure to lex: consume until next whitespace and throw error
                 let (end, _) = self.take_until(start, is_whitespace);
@@ -249,14 +349,15 @@ impl <'input> Iterator for Lexer<'input> {
 
                 self.skip();
 
+                // Track the start of each nested comment
                 match c {
-                | '/' => if self.test_peek(|c| c == '*') { self.skip(); comment_level += 1 },
+                | '/' => if self.test_peek(|c| c == '*') { self.skip(); self.comments.push(start) },
                 | '*' => if self.test_peek(|c| c == '/') {
                             self.skip();
-                            if comment_level == 0 {
+                            let open = self.comments.pop();
+                            if self.comments.is_empty() {
                                 self.mode = Mode::Source;
-                            } else {
-                                comment_level -= 1
+                                self.closed.extend(open.map(|open| Span::new(open, start + ByteOffset(2))));
                             }
                          },
                 | _   => (),
@@ -265,6 +366,14 @@ impl <'input> Iterator for Lexer<'input> {
             };
         }
 
+        // Point at the outermost comment left open at end of file
+        if let Mode::Comment = self.mode {
+            self.mode = Mode::Source;
+            let start = self.comments[0];
+            self.comments.clear();
+            return error(start, start + ByteOffset(2), LexError::UnterminatedComment)
+        }
+
         None
     }
 }
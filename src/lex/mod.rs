@@ -6,13 +6,16 @@ use std::sync::Arc;
 use codespan::{ByteIndex, FileMap};
 
 use self::lexer::Lexer;
+use config::Config;
 use error::Error;
+use span::Span;
 use token::Token;
 
-pub fn lex(source: Arc<FileMap>) -> Result<TokenStream, Error> {
+pub fn lex(source: Arc<FileMap>, config: Config) -> Result<TokenStream, Error> {
     let mut tokens = Vec::new();
+    let comments;
     {
-        let mut lexer = Lexer::new(&*source);
+        let mut lexer = Lexer::new(&*source, config);
         loop {
             match lexer.next() {
             | Some(Ok(token)) => tokens.push(token),
@@ -20,14 +23,33 @@ pub fn lex(source: Arc<FileMap>) -> Result<TokenStream, Error> {
             | None            => break,
             };
         }
+        comments = lexer.take_comments();
     }
 
-    Ok(TokenStream(tokens, source))
+    Ok(TokenStream(tokens, source, comments))
 }
 
 pub type Spanned = (ByteIndex, Token, ByteIndex);
 
-pub struct TokenStream(Vec<Spanned>, Arc<FileMap>);
+pub struct TokenStream(Vec<Spanned>, Arc<FileMap>, Vec<Span>);
+
+impl TokenStream {
+
+    /// Index just past the end of the source.
+    pub fn eof(&self) -> ByteIndex {
+        self.1.span().end()
+    }
+
+    /// Every token along with its start and end, in order.
+    pub fn tokens(&self) -> &[Spanned] {
+        &self.0
+    }
+
+    /// Spans of every comment in the source, in order.
+    pub fn comments(&self) -> &[Span] {
+        &self.2
+    }
+}
 
 impl IntoIterator for TokenStream {
 
@@ -16,6 +16,8 @@ pub mod config;
 pub mod error;
 pub mod span;
 pub mod phase;
+pub mod json;
+pub mod emit;
 
 pub mod lex;
 pub mod parse;
@@ -24,6 +26,10 @@ pub mod translate;
 pub mod assemble;
 pub mod analyze;
 pub mod optimize;
+pub mod interpret;
+pub mod format;
+pub mod unparse;
+pub mod lsp;
 
 pub mod token;
 pub mod ast;
@@ -0,0 +1,219 @@
+use codespan::{ByteIndex, CodeMap, FileName};
+
+use check;
+use config::Config;
+use error::{Error, Kind, Severity};
+use json::Json;
+use lex;
+use parse;
+use span::Span;
+use ty::Ty;
+use lsp::resolve::{self, Definition, contains, resolve};
+
+/// An open source file along with everything known about it.
+pub struct Document {
+    uri: String,
+    text: String,
+    lines: Vec<usize>,
+    base: ByteIndex,
+    errors: Vec<Error>,
+    types: Vec<(Span, Ty)>,
+    definitions: Vec<Definition>,
+}
+
+impl Document {
+
+    /// Lexes, parses, and checks [text]. Types and names are only
+    /// available when it parses successfully.
+    pub fn new(uri: String, text: String, config: Config) -> Self {
+        let mut lines = vec![0];
+        lines.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+
+        let mut code = CodeMap::new();
+        let file = code.add_filemap(FileName::virtual_(uri.clone()), text.clone());
+
+        let mut document = Document {
+            uri,
+            text,
+            lines,
+            base: file.span().start(),
+            errors: Vec::new(),
+            types: Vec::new(),
+            definitions: Vec::new(),
+        };
+
+        let ast = lex::lex(file, config)
+            .map_err(|err| vec![err])
+            .and_then(|tokens| parse::parse(tokens, config));
+
+        match ast {
+        | Err(errors) => document.errors = errors,
+        | Ok(mut ast) => {
+            document.definitions = resolve(&ast);
+            let (types, errors) = check::analyze(&mut ast, config);
+            document.types = types;
+            document.errors = errors;
+        },
+        }
+
+        document
+    }
+
+    /// Converts an LSP position into a byte offset from the start of the text.
+    pub fn offset(&self, position: &Json) -> Option<usize> {
+        let line = position.get("line")?.as_int()? as usize;
+        let character = position.get("character")?.as_int()? as usize;
+        let start = match self.lines.get(line) {
+        | Some(start) => *start,
+        | None        => return Some(self.text.len()),
+        };
+
+        // Characters are counted in UTF-16 code units
+        let mut units = 0;
+        for (i, c) in self.text[start..].char_indices() {
+            if units >= character || c == '\n' { return Some(start + i) }
+            units += c.len_utf16();
+        }
+        Some(self.text.len())
+    }
+
+    fn position(&self, index: ByteIndex) -> Json {
+        let offset = (index.to_usize() - self.base.to_usize()).min(self.text.len());
+        let line = match self.lines.binary_search(&offset) {
+        | Ok(line)  => line,
+        | Err(line) => line - 1,
+        };
+        let start = self.lines[line];
+        let character = self.text[start..offset].chars()
+            .map(char::len_utf16)
+            .sum::<usize>();
+        Json::object(vec![
+            ("line", line.into()),
+            ("character", character.into()),
+        ])
+    }
+
+    fn range(&self, span: &Span) -> Json {
+        Json::object(vec![
+            ("start", self.position(span.start())),
+            ("end", self.position(span.end())),
+        ])
+    }
+
+    fn location(&self, span: &Span) -> Json {
+        Json::object(vec![
+            ("uri", self.uri.as_str().into()),
+            ("range", self.range(span)),
+        ])
+    }
+
+    /// Converts an offset into the text into a byte index of its spans.
+    fn index(&self, offset: usize) -> usize {
+        self.base.to_usize() + offset
+    }
+
+    /// Every error and warning, as LSP diagnostics.
+    pub fn diagnostics(&self) -> Json {
+        self.errors.iter().map(|error| {
+            let severity = match error.severity {
+            | Severity::Error   => 1,
+            | Severity::Warning => 2,
+            };
+
+            let related = match &error.kind {
+            | Kind::Semantic(err) => err.secondary(),
+            | _                   => Vec::new(),
+            };
+
+            let message: String = (&error.kind).into();
+            Json::object(vec![
+                ("range", self.range(&error.span)),
+                ("severity", Json::Int(severity)),
+                ("source", "tigerc".into()),
+                ("message", message.into()),
+                ("relatedInformation", related.into_iter().map(|(span, message)| {
+                    Json::object(vec![
+                        ("location", self.location(&span)),
+                        ("message", message.into()),
+                    ])
+                }).collect::<Vec<_>>().into()),
+            ])
+        }).collect::<Vec<_>>().into()
+    }
+
+    /// Type of the innermost expression at [offset].
+    pub fn hover(&self, offset: usize) -> Json {
+        let index = self.index(offset);
+        let innermost = self.types.iter()
+            .filter(|(span, ty)| contains(span, index) && !ty.is_error())
+            .min_by_key(|(span, _)| span.end().to_usize() - span.start().to_usize());
+
+        match innermost {
+        | None => Json::Null,
+        | Some((span, ty)) => Json::object(vec![
+            ("contents", Json::object(vec![
+                ("kind", "plaintext".into()),
+                ("value", ty.to_string().into()),
+            ])),
+            ("range", self.range(span)),
+        ]),
+        }
+    }
+
+    fn find(&self, offset: usize) -> Option<&Definition> {
+        let index = self.index(offset);
+        self.definitions.iter().rev().find(|definition| definition.mentions(index))
+    }
+
+    /// Declaration of the name at [offset].
+    pub fn definition(&self, offset: usize) -> Json {
+        match self.find(offset) {
+        | None             => Json::Null,
+        | Some(definition) => self.location(&definition.name_span),
+        }
+    }
+
+    /// Every use of the name at [offset], optionally including its declaration.
+    pub fn references(&self, offset: usize, declaration: bool) -> Json {
+        let definition = match self.find(offset) {
+        | None             => return Json::Null,
+        | Some(definition) => definition,
+        };
+
+        let declaration = if declaration { Some(&definition.name_span) } else { None };
+        declaration.into_iter()
+            .chain(&definition.references)
+            .map(|span| self.location(span))
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Outline of declarations, with declarations inside functions nested under them.
+    pub fn symbols(&self) -> Json {
+        self.children(None).into()
+    }
+
+    fn children(&self, parent: Option<usize>) -> Vec<Json> {
+        self.definitions.iter()
+            .enumerate()
+            .filter(|(_, definition)| definition.parent == parent)
+            .map(|(id, definition)| {
+
+                // SymbolKind values from the LSP specification
+                let kind = match definition.kind {
+                | resolve::Kind::Var  => 13,
+                | resolve::Kind::Fun  => 12,
+                | resolve::Kind::Type => 23,
+                };
+
+                Json::object(vec![
+                    ("name", definition.name.to_string().into()),
+                    ("kind", Json::Int(kind)),
+                    ("range", self.range(&definition.span)),
+                    ("selectionRange", self.range(&definition.name_span)),
+                    ("children", self.children(Some(id)).into()),
+                ])
+            })
+            .collect()
+    }
+}
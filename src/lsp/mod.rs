@@ -0,0 +1,5 @@
+mod document;
+mod resolve;
+mod server;
+
+pub use self::server::Server;
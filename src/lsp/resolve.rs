@@ -0,0 +1,216 @@
+use fnv::FnvHashMap;
+use simple_symbol::Symbol;
+
+use ast::*;
+use span::{Span, IntoSpan};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Kind {
+    Var,
+    Fun,
+    Type,
+}
+
+/// A declared name along with every place that refers to it.
+#[derive(Debug)]
+pub struct Definition {
+    pub name: Symbol,
+    pub kind: Kind,
+    pub name_span: Span,
+    pub span: Span,
+    pub parent: Option<usize>,
+    pub references: Vec<Span>,
+}
+
+impl Definition {
+    pub fn mentions(&self, index: usize) -> bool {
+        contains(&self.name_span, index) || self.references.iter().any(|span| contains(span, index))
+    }
+}
+
+/// Whether [span] covers byte [index], counting the position just past its end.
+pub fn contains(span: &Span, index: usize) -> bool {
+    span.start().to_usize() <= index && index <= span.end().to_usize()
+}
+
+/// Matches each use of a variable, function, or type in [ast] with its declaration.
+///
+/// Uses of undeclared names, including the standard library, are left out.
+pub fn resolve(ast: &Exp) -> Vec<Definition> {
+    let mut resolver = Resolver {
+        definitions: Vec::new(),
+        vars: vec![FnvHashMap::default()],
+        types: vec![FnvHashMap::default()],
+        parent: None,
+    };
+    resolver.resolve_exp(ast);
+    resolver.definitions
+}
+
+struct Resolver {
+    definitions: Vec<Definition>,
+    vars: Vec<FnvHashMap<Symbol, usize>>,
+    types: Vec<FnvHashMap<Symbol, usize>>,
+    parent: Option<usize>,
+}
+
+impl Resolver {
+
+    fn push(&mut self) {
+        self.vars.push(FnvHashMap::default());
+        self.types.push(FnvHashMap::default());
+    }
+
+    fn pop(&mut self) {
+        self.vars.pop().expect("Internal error: missing scope");
+        self.types.pop().expect("Internal error: missing scope");
+    }
+
+    fn define(&mut self, name: Symbol, kind: Kind, name_span: Span, span: Span) -> usize {
+        let id = self.definitions.len();
+        let scope = match kind {
+        | Kind::Var
+        | Kind::Fun  => &mut self.vars,
+        | Kind::Type => &mut self.types,
+        };
+        scope.last_mut()
+            .expect("Internal error: missing scope")
+            .insert(name, id);
+        self.definitions.push(Definition {
+            name, kind, name_span, span,
+            parent: self.parent,
+            references: Vec::new(),
+        });
+        id
+    }
+
+    fn refer_var(&mut self, name: &Symbol, span: Span) {
+        let id = self.vars.iter().rev().find_map(|scope| scope.get(name)).cloned();
+        if let Some(id) = id { self.definitions[id].references.push(span) }
+    }
+
+    fn refer_type(&mut self, name: &Symbol, span: Span) {
+        let id = self.types.iter().rev().find_map(|scope| scope.get(name)).cloned();
+        if let Some(id) = id { self.definitions[id].references.push(span) }
+    }
+
+    fn resolve_var(&mut self, var: &Var) {
+        match var {
+        | Var::Simple(name, span)     => self.refer_var(name, *span),
+        | Var::Field(rec, _, _, _)    => self.resolve_var(rec),
+        | Var::Index(arr, index, _)   => {
+            self.resolve_var(arr);
+            self.resolve_exp(index);
+        },
+        }
+    }
+
+    fn resolve_exp(&mut self, exp: &Exp) {
+        match exp {
+        | Exp::Break(_)
+        | Exp::Nil(_)
+        | Exp::Int(_, _)
+        | Exp::Str(_, _)          => (),
+        | Exp::Var(var, _)        => self.resolve_var(var),
+        | Exp::Neg(exp, _)        => self.resolve_exp(exp),
+        | Exp::Call{name, name_span, args, ..} => {
+            self.refer_var(name, *name_span);
+            for arg in args { self.resolve_exp(arg); }
+        },
+        | Exp::Bin{lhs, rhs, ..} => {
+            self.resolve_exp(lhs);
+            self.resolve_exp(rhs);
+        },
+        | Exp::Rec{name, name_span, fields, ..} => {
+            self.refer_type(name, *name_span);
+            for field in fields { self.resolve_exp(&field.exp); }
+        },
+        | Exp::Seq(exps, _) => {
+            for exp in exps { self.resolve_exp(exp); }
+        },
+        | Exp::Ass{name, exp, ..} => {
+            self.resolve_var(name);
+            self.resolve_exp(exp);
+        },
+        | Exp::If{guard, then, or, ..} => {
+            self.resolve_exp(guard);
+            self.resolve_exp(then);
+            if let Some(or) = or { self.resolve_exp(or); }
+        },
+        | Exp::While{guard, body, ..} => {
+            self.resolve_exp(guard);
+            self.resolve_exp(body);
+        },
+        | Exp::For{name, name_span, lo, hi, body, span, ..} => {
+            self.resolve_exp(lo);
+            self.resolve_exp(hi);
+            self.push();
+            self.define(*name, Kind::Var, *name_span, *span);
+            self.resolve_exp(body);
+            self.pop();
+        },
+        | Exp::Let{decs, body, ..} => {
+            self.push();
+            for dec in decs { self.resolve_dec(dec); }
+            self.resolve_exp(body);
+            self.pop();
+        },
+        | Exp::Arr{name, name_span, size, init, ..} => {
+            self.refer_type(name, *name_span);
+            self.resolve_exp(size);
+            self.resolve_exp(init);
+        },
+        }
+    }
+
+    fn resolve_dec(&mut self, dec: &Dec) {
+        match dec {
+        | Dec::Fun(funs, _) => {
+
+            // Functions in a group may call each other in any order
+            let ids = funs.iter()
+                .map(|fun| self.define(fun.name, Kind::Fun, fun.name_span, fun.span))
+                .collect::<Vec<_>>();
+
+            for (fun, id) in funs.iter().zip(ids) {
+                let parent = self.parent.replace(id);
+                self.push();
+                for arg in &fun.args {
+                    self.refer_type(&arg.ty, arg.ty_span);
+                    self.define(arg.name, Kind::Var, arg.name_span, arg.span);
+                }
+                if let (Some(rets), Some(rets_span)) = (fun.rets, fun.rets_span) {
+                    self.refer_type(&rets, rets_span);
+                }
+                self.resolve_exp(&fun.body);
+                self.pop();
+                self.parent = parent;
+            }
+        },
+        | Dec::Var{name, name_span, ty, ty_span, init, span, ..} => {
+            self.resolve_exp(init);
+            if let (Some(ty), Some(ty_span)) = (ty, ty_span) {
+                self.refer_type(ty, *ty_span);
+            }
+            self.define(*name, Kind::Var, *name_span, *span);
+        },
+        | Dec::Type(decs, _) => {
+
+            // Types in a group may refer to each other in any order
+            for dec in decs {
+                self.define(dec.name, Kind::Type, dec.name_span, dec.into_span());
+            }
+
+            for dec in decs {
+                match &dec.ty {
+                | Type::Name(name, span)     => self.refer_type(name, *span),
+                | Type::Arr(name, span, _)   => self.refer_type(name, *span),
+                | Type::Rec(fields, _)       => for field in fields {
+                    self.refer_type(&field.ty, field.ty_span);
+                },
+                }
+            }
+        },
+        }
+    }
+}
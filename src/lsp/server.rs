@@ -0,0 +1,223 @@
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use fnv::FnvHashMap;
+
+use config::Config;
+use json::Json;
+use lsp::document::Document;
+
+/// JSON-RPC error codes from the LSP specification.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Language server for Tiger, which keeps every open document checked.
+pub struct Server {
+    config: Config,
+    documents: FnvHashMap<String, Document>,
+    shutdown: bool,
+}
+
+impl Server {
+
+    pub fn new(config: Config) -> Self {
+        Server {
+            config,
+            documents: FnvHashMap::default(),
+            shutdown: false,
+        }
+    }
+
+    /// Serves requests from [input] until the client exits, returning the exit code.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: &mut R, output: &mut W) -> i32 {
+        loop {
+            let message = match read_message(input) {
+            | Ok(Some(message)) => message,
+            | Ok(None) | Err(_) => return 1,
+            };
+
+            let replies = match Json::from_str(&message) {
+            | Ok(message) => {
+                if message.get("method").and_then(Json::as_str) == Some("exit") {
+                    return if self.shutdown { 0 } else { 1 }
+                }
+                self.handle(&message)
+            },
+            | Err(err) => vec![failure(Json::Null, PARSE_ERROR, err)],
+            };
+
+            for reply in replies {
+                if write_message(output, &reply).is_err() { return 1 }
+            }
+        }
+    }
+
+    /// Handles a single request or notification, returning any messages to send back.
+    fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let params = message.get("params").unwrap_or(&Json::Null);
+
+        // Notifications have no id and never get a response
+        let id = match message.get("id") {
+        | Some(id) => id.clone(),
+        | None     => return self.notify(method, params),
+        };
+
+        let result = match method {
+        | "initialize"              => Some(capabilities()),
+        | "shutdown"                => { self.shutdown = true; Some(Json::Null) },
+        | "textDocument/hover"      => self.at(params, |document, offset| document.hover(offset)),
+        | "textDocument/definition" => self.at(params, |document, offset| document.definition(offset)),
+        | "textDocument/references" => {
+            let declaration = params.get("context")
+                .and_then(|context| context.get("includeDeclaration"))
+                .and_then(Json::as_bool)
+                .unwrap_or(true);
+            self.at(params, |document, offset| document.references(offset, declaration))
+        },
+        | "textDocument/documentSymbol" => {
+            uri(params).map(|uri| match self.documents.get(uri) {
+            | Some(document) => document.symbols(),
+            | None           => Json::Null,
+            })
+        },
+        | _ => {
+            let message = format!("Unhandled method [{}].", method);
+            return vec![failure(id, METHOD_NOT_FOUND, message)]
+        },
+        };
+
+        match result {
+        | Some(result) => vec![success(id, result)],
+        | None         => vec![failure(id, INVALID_PARAMS, format!("Invalid parameters for [{}].", method))],
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let uri = match uri(params) {
+        | Some(uri) => uri.to_string(),
+        | None      => return Vec::new(),
+        };
+
+        let text = match method {
+        | "textDocument/didOpen" => {
+            params.get("textDocument")
+                .and_then(|document| document.get("text"))
+                .and_then(Json::as_str)
+        },
+        | "textDocument/didChange" => {
+            // Documents are synchronized in full, so the last change has the whole text
+            params.get("contentChanges")
+                .and_then(Json::as_arr)
+                .and_then(|changes| changes.last())
+                .and_then(|change| change.get("text"))
+                .and_then(Json::as_str)
+        },
+        | "textDocument/didClose" => {
+            self.documents.remove(&uri);
+            return vec![publish(uri, Json::Arr(Vec::new()))]
+        },
+        | _ => return Vec::new(),
+        };
+
+        match text {
+        | None       => Vec::new(),
+        | Some(text) => {
+            let document = Document::new(uri.clone(), text.to_string(), self.config);
+            let diagnostics = document.diagnostics();
+            self.documents.insert(uri.clone(), document);
+            vec![publish(uri, diagnostics)]
+        },
+        }
+    }
+
+    /// Answers a request about the position in [params], or [None] if it is missing.
+    fn at<F: FnOnce(&Document, usize) -> Json>(&self, params: &Json, answer: F) -> Option<Json> {
+        let document = match self.documents.get(uri(params)?) {
+        | Some(document) => document,
+        | None           => return Some(Json::Null),
+        };
+        let offset = document.offset(params.get("position")?)?;
+        Some(answer(document, offset))
+    }
+}
+
+fn uri(params: &Json) -> Option<&str> {
+    params.get("textDocument")?.get("uri")?.as_str()
+}
+
+fn capabilities() -> Json {
+    Json::object(vec![
+        ("capabilities", Json::object(vec![
+            ("textDocumentSync", Json::Int(1)),
+            ("hoverProvider", true.into()),
+            ("definitionProvider", true.into()),
+            ("referencesProvider", true.into()),
+            ("documentSymbolProvider", true.into()),
+        ])),
+        ("serverInfo", Json::object(vec![
+            ("name", "tiger-lsp".into()),
+        ])),
+    ])
+}
+
+fn success(id: Json, result: Json) -> Json {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("id", id),
+        ("result", result),
+    ])
+}
+
+fn failure(id: Json, code: i64, message: String) -> Json {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("id", id),
+        ("error", Json::object(vec![
+            ("code", code.into()),
+            ("message", message.into()),
+        ])),
+    ])
+}
+
+fn publish(uri: String, diagnostics: Json) -> Json {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        ("params", Json::object(vec![
+            ("uri", uri.into()),
+            ("diagnostics", diagnostics),
+        ])),
+    ])
+}
+
+/// Reads the body of the next message, or [None] at the end of [input].
+fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 { return Ok(None) }
+        let header = header.trim_end();
+        if header.is_empty() { break }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = usize::from_str(value.trim()).ok();
+        }
+    }
+
+    let length = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn write_message<W: Write>(output: &mut W, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
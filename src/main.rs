@@ -4,11 +4,15 @@ extern crate codespan_reporting;
 extern crate tigerc;
 
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use codespan_reporting::emit;
 use codespan_reporting::termcolor::{StandardStream, ColorChoice};
 use structopt::StructOpt;
 
+use tigerc::config::{Config, Warnings};
+use tigerc::emit::Emit;
+use tigerc::ir::Form;
 use tigerc::phase::*;
 
 #[derive(Debug, StructOpt)]
@@ -35,6 +39,26 @@ struct Opt {
     #[structopt(long = "fold")]
     fold: bool,
 
+    /// Accept 64-bit integer literals, since `int` arithmetic is always 64-bit.
+    #[structopt(long = "int64")]
+    int64: bool,
+
+    /// Whether to ignore lints, report them as warnings, or treat them as errors.
+    #[structopt(
+        long = "warnings",
+        default_value = "warn",
+        raw(possible_values = r#"&["allow", "warn", "deny"]"#)
+    )]
+    warnings: Warnings,
+
+    /// Format of the files written for each phase.
+    #[structopt(
+        long = "emit-format",
+        default_value = "text",
+        raw(possible_values = r#"&["text", "json"]"#)
+    )]
+    emit_format: Emit,
+
     /// Disable constant folding.
     #[structopt(long = "o-no-cf")]
     disable_fold: bool,
@@ -59,31 +83,171 @@ struct Opt {
     #[structopt(long = "tile")]
     tile: bool,
 
+    /// Write liveness analysis diagnostics to file.
+    #[structopt(long = "liveness")]
+    liveness: bool,
+
+    /// Register allocator to use.
+    #[structopt(
+        long = "regalloc",
+        default_value = "trivial",
+        raw(possible_values = r#"&["trivial", "linear", "color"]"#)
+    )]
+    regalloc: Allocator,
+
+    /// Interpret the IR after the given stage instead of compiling.
+    #[structopt(
+        long = "interpret-ir",
+        raw(possible_values = r#"&["translate", "canonize", "fold", "reorder"]"#)
+    )]
+    interpret_ir: Option<Stage>,
+
+    /// Stage whose output is in [.ir] files, which are compiled from the next stage on.
+    #[structopt(
+        long = "ir-stage",
+        default_value = "translate",
+        raw(possible_values = r#"&["translate", "canonize", "fold", "reorder"]"#)
+    )]
+    ir_stage: Stage,
+
+    /// Emulate abstract or allocated assembly instead of compiling.
+    #[structopt(
+        long = "interpret-asm",
+        raw(possible_values = r#"&["abstract", "assembly"]"#)
+    )]
+    interpret_asm: Option<Code>,
+
     /// Files to compile.
     #[structopt(name = "FILE", parse(from_os_str))]
     files: Vec<PathBuf>,
 }
 
+#[derive(Debug)]
+enum Allocator {
+    Trivial,
+    Linear,
+    Color,
+}
+
+impl FromStr for Allocator {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+        | "trivial" => Ok(Allocator::Trivial),
+        | "linear"  => Ok(Allocator::Linear),
+        | "color"   => Ok(Allocator::Color),
+        | _         => Err(format!("unknown register allocator: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    Translate,
+    Canonize,
+    Fold,
+    Reorder,
+}
+
+impl FromStr for Stage {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+        | "translate" => Ok(Stage::Translate),
+        | "canonize"  => Ok(Stage::Canonize),
+        | "fold"      => Ok(Stage::Fold),
+        | "reorder"   => Ok(Stage::Reorder),
+        | _           => Err(format!("unknown IR stage: {}", s)),
+        }
+    }
+}
+
+impl Stage {
+    /// Form of the IR that this stage outputs.
+    fn form(&self) -> Form {
+        match self {
+        | Stage::Translate => Form::Translated,
+        | Stage::Canonize
+        | Stage::Fold      => Form::Canonical,
+        | Stage::Reorder   => Form::Reordered,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Code {
+    Abstract,
+    Assembly,
+}
+
+impl FromStr for Code {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+        | "abstract" => Ok(Code::Abstract),
+        | "assembly" => Ok(Code::Assembly),
+        | _          => Err(format!("unknown assembly kind: {}", s)),
+        }
+    }
+}
+
 fn main() {
     let opt = Opt::from_args();
     let mut stdout = StandardStream::stdout(ColorChoice::Auto);
+    let mut stderr = StandardStream::stderr(ColorChoice::Auto);
+    let mut exit = 0;
+    let interpret = |stage| InterpretIR::maybe(false, opt.interpret_ir != Some(stage));
+    let emulate = |code| InterpretAsm::maybe(false, opt.interpret_asm != Some(code));
 
     for file in &opt.files {
+
+        let allocate: Box<dyn Phase> = match opt.regalloc {
+        | Allocator::Trivial => Trivial::new(true),
+        | Allocator::Linear  => Linear::new(true),
+        | Allocator::Color   => Color::new(true),
+        };
+
+        // IR files skip every stage up to and including the one that produced them
+        let ir = file.extension().is_some_and(|extension| extension == "ir");
+        let skip = |stage| ir && opt.ir_stage >= stage;
+
         let mut compiler = Compiler::with_path(file)
-            .with_phase(Lex::new(opt.lex))
-            .with_phase(Parse::new(opt.parse))
-            .with_phase(Type::new(opt.type_check))
-            .with_phase(Canonize::new(opt.canonize))
-            .with_phase(Fold::maybe(opt.fold, opt.disable_fold))
-            .with_phase(Reorder::new(opt.reorder))
+            .with_config(Config { int64: opt.int64, warnings: opt.warnings })
+            .with_emit(opt.emit_format)
+            .with_phase(ReadIR::maybe(opt.ir_stage.form(), !ir))
+            .with_phase(Lex::maybe(opt.lex, ir))
+            .with_phase(Parse::maybe(opt.parse, ir))
+            .with_phase(Type::maybe(opt.type_check, ir))
+            .with_phase(interpret(Stage::Translate))
+            .with_phase(Canonize::maybe(opt.canonize, skip(Stage::Canonize)))
+            .with_phase(interpret(Stage::Canonize))
+            .with_phase(Fold::maybe(opt.fold, opt.disable_fold || skip(Stage::Fold)))
+            .with_phase(interpret(Stage::Fold))
+            .with_phase(Reorder::maybe(opt.reorder, skip(Stage::Reorder)))
+            .with_phase(interpret(Stage::Reorder))
             .with_phase(Tile::new(opt.tile))
             .with_phase(CoalesceAbstract::maybe(opt.coalesce_abstract, opt.disable_coalesce))
-            .with_phase(Trivial::new(true))
-            .with_phase(CoalesceAssembly::maybe(true, opt.disable_coalesce));
+            .with_phase(emulate(Code::Abstract))
+            .with_phase(Liveness::new(opt.liveness))
+            .with_phase(allocate)
+            .with_phase(CoalesceAssembly::maybe(true, opt.disable_coalesce))
+            .with_phase(emulate(Code::Assembly));
+
+        let result = compiler.run();
 
-        match compiler.run() {
-        | Err(err) => emit(&mut stdout, compiler.code(), &err.into()).expect("Internal error: IO"),
+        // Keep warnings apart from the output of interpreted programs
+        for warning in compiler.warnings() {
+            emit(&mut stderr, compiler.code(), &warning.into()).expect("Internal error: IO")
+        }
+
+        match result {
+        | Err(errs) => for err in errs {
+            emit(&mut stdout, compiler.code(), &err.into()).expect("Internal error: IO")
+        },
+        | Ok(Item::Exit(code)) => exit = code,
         | _ => (),
         }
     }
+
+    std::process::exit(exit);
 }
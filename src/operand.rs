@@ -8,10 +8,25 @@ generate_counter!(TempID, usize);
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub enum Imm {
-    Int(i32),    
+    Int(i64),
     Label(Label),
 }
 
+impl Imm {
+    /// Whether this fits in the sign-extended 32-bit immediate most instructions take.
+    pub fn is_imm32(&self) -> bool {
+        match self {
+        | Imm::Int(n)   => is_imm32(*n),
+        | Imm::Label(_) => true,
+        }
+    }
+}
+
+/// Whether [n] fits in the sign-extended 32-bit immediate most instructions take.
+pub fn is_imm32(n: i64) -> bool {
+    n == n as i32 as i64
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Label {
     Fixed(Symbol),
@@ -44,7 +59,7 @@ impl Into<Symbol> for Label {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Temp {
     Reg(Reg),
     Temp {
@@ -61,12 +76,22 @@ impl Temp {
         }
     }
 
+    pub fn from_symbol(name: Symbol) -> Self {
+        Temp::Temp { id: TempID::next(), name }
+    }
+
     pub fn from_reg(reg: Reg) -> Self {
         Temp::Reg(reg)
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+impl From<Reg> for Temp {
+    fn from(reg: Reg) -> Self {
+        Temp::Reg(reg)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Reg {
     RAX,
     RBX,
@@ -87,6 +112,29 @@ pub enum Reg {
 }
 
 impl Reg {
+    /// Caller-saved registers, which any call may overwrite.
+    pub const CALLER_SAVED: [Reg; 9] = [
+        Reg::RAX,
+        Reg::RCX,
+        Reg::RDX,
+        Reg::RSI,
+        Reg::RDI,
+        Reg::R8,
+        Reg::R9,
+        Reg::R10,
+        Reg::R11,
+    ];
+
+    /// Callee-saved registers that functions save in their prologue and
+    /// restore in their epilogue. RBP and RSP are restored by the frame itself.
+    pub const CALLEE_SAVED: [Reg; 5] = [
+        Reg::RBX,
+        Reg::R12,
+        Reg::R13,
+        Reg::R14,
+        Reg::R15,
+    ];
+
     pub fn is_callee_saved(&self) -> bool {
         match self {
         | Reg::R12
@@ -131,6 +179,22 @@ pub enum Mem<T: Operand> {
     RO(T, i32),
 }
 
+impl <T: Operand> Mem<T> {
+    pub fn base(&self) -> T {
+        match self {
+        | Mem::R(reg)
+        | Mem::RO(reg, _) => *reg,
+        }
+    }
+
+    pub fn map<U: Operand, F: FnMut(T) -> U>(&self, f: &mut F) -> Mem<U> {
+        match self {
+        | Mem::R(reg)          => Mem::R(f(*reg)),
+        | Mem::RO(reg, offset) => Mem::RO(f(*reg), *offset),
+        }
+    }
+}
+
 impl fmt::Display for Temp {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match self {
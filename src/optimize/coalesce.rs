@@ -1,7 +1,8 @@
+use analyze::liveness::{Liveness, is_tracked};
 use asm::*;
 use operand::*;
 
-pub fn coalesce<T: Operand>(unit: Unit<T>) -> Unit<T> {
+pub fn coalesce<T: Operand + Into<Temp>>(unit: Unit<T>) -> Unit<T> {
     Unit {
         data: unit.data,
         functions: unit.functions.into_iter()
@@ -10,10 +11,22 @@ pub fn coalesce<T: Operand>(unit: Unit<T>) -> Unit<T> {
     }
 }
 
-pub fn coalesce_function<T: Operand>(asm: Function<T>) -> Function<T> {
+pub fn coalesce_function<T: Operand + Into<Temp>>(asm: Function<T>) -> Function<T> {
 
     use self::Binary::*;
 
+    let body = asm.body.iter()
+        .map(|stm| stm.map(&mut |operand: T| operand.into()))
+        .collect::<Vec<_>>();
+
+    // Intermediate register must not be read after the pair of moves,
+    // and registers that liveness doesn't track are always assumed live
+    let liveness = Liveness::new(&body);
+    let dead = |i: usize, reg: T| {
+        let temp = reg.into();
+        is_tracked(&temp) && !liveness.live_out(i + 1).contains(&temp)
+    };
+
     let mut coalesced = Vec::new();
     let mut i = 0;
     let len = asm.body.len();
@@ -26,25 +39,29 @@ pub fn coalesce_function<T: Operand>(asm: Function<T>) -> Function<T> {
         }
 
         match (asm.body[i], asm.body[i + 1]) {
-        | (Asm::Mov(IR(imm, reg_a)), Asm::Mov(RM(reg_b, mem))) if reg_a == reg_b => {
+        // Only moves into registers can take 64-bit immediates
+        | (Asm::Mov(IR(imm, reg_a)), Asm::Mov(RM(reg_b, mem))) if reg_a == reg_b && imm.is_imm32() && dead(i, reg_a) => {
             coalesced.push(Asm::Mov(IM(imm, mem)));
         }
-        | (Asm::Mov(IR(imm, reg_a)), Asm::Mov(RR(reg_b, reg_c))) if reg_a == reg_b => {
+        | (Asm::Mov(IR(imm, reg_a)), Asm::Mov(RR(reg_b, reg_c))) if reg_a == reg_b && dead(i, reg_a) => {
             coalesced.push(Asm::Mov(IR(imm, reg_c)));
         }
+        // Memory may be read again later, so keep the store and forward its value
         | (Asm::Mov(IM(imm, mem_a)), Asm::Mov(MR(mem_b, reg))) if mem_a == mem_b => {
+            coalesced.push(Asm::Mov(IM(imm, mem_a)));
             coalesced.push(Asm::Mov(IR(imm, reg)));
         }
-        | (Asm::Mov(MR(mem, reg_a)), Asm::Mov(RR(reg_b, reg_c))) if reg_a == reg_b => {
+        | (Asm::Mov(MR(mem, reg_a)), Asm::Mov(RR(reg_b, reg_c))) if reg_a == reg_b && dead(i, reg_a) => {
             coalesced.push(Asm::Mov(MR(mem, reg_c)));
         }
         | (Asm::Mov(RM(reg_a, mem_a)), Asm::Mov(MR(mem_b, reg_b))) if mem_a == mem_b => {
+            coalesced.push(Asm::Mov(RM(reg_a, mem_a)));
             coalesced.push(Asm::Mov(RR(reg_a, reg_b)));
         }
-        | (Asm::Mov(RR(reg_a, reg_b)), Asm::Mov(RR(reg_c, reg_d))) if reg_b == reg_c => {
+        | (Asm::Mov(RR(reg_a, reg_b)), Asm::Mov(RR(reg_c, reg_d))) if reg_b == reg_c && dead(i, reg_b) => {
             coalesced.push(Asm::Mov(RR(reg_a, reg_d)));
         }
-        | (Asm::Mov(RR(reg_a, reg_b)), Asm::Mov(RM(reg_c, mem))) if reg_b == reg_c => {
+        | (Asm::Mov(RR(reg_a, reg_b)), Asm::Mov(RM(reg_c, mem))) if reg_b == reg_c && dead(i, reg_b) => {
             coalesced.push(Asm::Mov(RM(reg_a, mem)));
         }
         | _ => {
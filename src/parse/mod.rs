@@ -1,10 +1,36 @@
+#[allow(clippy::all, unused_parens)]
 mod grammar;
 
+use codespan::ByteIndex;
+
 use ast::Exp;
+use config::Config;
 use lex::TokenStream;
-use error::Error;
+use error::{Error, Kind, ParseError};
+use span::Span;
 
-pub fn parse(input: TokenStream) -> Result<Exp, Error> {
+/// Parses [input], recovering from syntax errors so that all of them can be reported.
+pub fn parse(input: TokenStream, config: Config) -> Result<Exp, Vec<Error>> {
     let parser = grammar::ProgramParser::new();
-    parser.parse(input).map_err(|err| err.into())
+    let eof = input.eof();
+    let mut recovered = Vec::new();
+    let result = parser.parse(config, &mut recovered, input);
+
+    let mut errors = recovered.into_iter()
+        .map(|recovery| locate(recovery.error.into(), eof))
+        .collect::<Vec<_>>();
+
+    match result {
+    | Ok(ast) if errors.is_empty() => Ok(ast),
+    | Ok(_)                        => Err(errors),
+    | Err(err)                     => { errors.push(locate(err.into(), eof)); Err(errors) },
+    }
+}
+
+/// Points errors about a missing token at the end of the source.
+fn locate(mut error: Error, eof: ByteIndex) -> Error {
+    if let Kind::Syntactic(ParseError::EOF(_)) = error.kind {
+        error.span = Span::new(eof, eof);
+    }
+    error
 }
@@ -1,3 +1,4 @@
+use std::cell::RefCell;
 use std::fmt;
 use std::fs::File;
 use std::io::Write;
@@ -5,7 +6,7 @@ use std::mem;
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use codespan::{CodeMap, FileMap};
+use codespan::{ByteIndex, CodeMap, FileMap};
 
 use ast;
 use ir;
@@ -17,7 +18,11 @@ use check;
 use translate;
 use assemble;
 use optimize;
+use analyze;
+use interpret;
 
+use config::Config;
+use emit::{Emit, Emitter};
 use error::Error;
 use operand::{Temp, Reg};
 
@@ -29,6 +34,7 @@ pub enum Item {
     Intermediate(ir::Unit),
     Abstract(asm::Unit<Temp>),
     Assembly(asm::Unit<Reg>),
+    Exit(i32),
 }
 
 impl fmt::Display for Item {
@@ -41,18 +47,29 @@ impl fmt::Display for Item {
         | Item::Intermediate(unit) => write!(fmt, "{}\n\n", unit),
         | Item::Abstract(unit) => write!(fmt, "{}\n\n", unit),
         | Item::Assembly(unit) => write!(fmt, "{}\n\n", unit),
+        | Item::Exit(code) => write!(fmt, "Exited with code {}", code),
         }
     }
 }
 
 pub trait Phase {
-    fn process(&self, compiler: &Compiler, input: Item) -> Result<Item, Error>;
+    fn process(&self, compiler: &Compiler, input: Item) -> Result<Item, Vec<Error>>;
+
+    /// Form of the IR this phase outputs, if any, which is verified in debug builds.
+    /// Disabled phases pass their input along and promise nothing about it.
+    fn form(&self) -> Option<ir::Form> {
+        None
+    }
 }
 
 pub struct Compiler {
-    phases: Vec<Box<Phase>>, 
+    phases: Vec<Box<dyn Phase>>, 
     code: CodeMap,
     path: PathBuf,
+    base: ByteIndex,
+    config: Config,
+    emit: Emit,
+    warnings: RefCell<Vec<Error>>,
 }
 
 impl Compiler {
@@ -62,18 +79,34 @@ impl Compiler {
             phases: Vec::new(),
             code: CodeMap::default(),
             path: path.into(),
+            base: ByteIndex(0),
+            config: Config::default(),
+            emit: Emit::default(),
+            warnings: RefCell::new(Vec::new()),
         }
     }
 
-    pub fn with_phase(mut self, phase: Box<Phase>) -> Self {
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn with_emit(mut self, emit: Emit) -> Self {
+        self.emit = emit;
+        self
+    }
+
+    pub fn with_phase(mut self, phase: Box<dyn Phase>) -> Self {
         self.phases.push(phase);
         self
     }
 
-    pub fn run(&mut self) -> Result<Item, Error> {
+    pub fn run(&mut self) -> Result<Item, Vec<Error>> {
         let map = self.code.add_filemap_from_disk(&self.path)
             .expect("Internal error: IO")
             .clone();
+
+        self.base = map.span().start();
         
         let phases = mem::replace(
             &mut self.phases,
@@ -82,7 +115,14 @@ impl Compiler {
 
         phases.into_iter()
             .try_fold(Item::Source(map), |item, phase| {
-                phase.process(&self, item)
+                match item {
+                | Item::Exit(_) => Ok(item),
+                | _             => {
+                    let item = phase.process(&self, item)?;
+                    if cfg!(debug_assertions) { verify(&*phase, &item); }
+                    Ok(item)
+                },
+                }
             })
     }
 
@@ -90,22 +130,84 @@ impl Compiler {
         &self.code
     }
 
-    fn write(&self, ext: &'static str, item: &Result<Item, Error>) {
+    pub fn config(&self) -> Config {
+        self.config
+    }
+
+    /// Diagnostics that didn't stop compilation.
+    pub fn warnings(&self) -> Vec<Error> {
+        self.warnings.borrow().clone()
+    }
+
+    fn warn(&self, warnings: Vec<Error>) {
+        self.warnings.borrow_mut().extend(warnings);
+    }
+
+    fn write(&self, ext: &'static str, item: &Result<Item, Vec<Error>>) {
+        self.write_with_warnings(ext, item, &[]);
+    }
+
+    /// Writes [item] to the file with extension [ext], or to `[ext].json` when emitting JSON.
+    /// Assembly is always written as text too, since it is the output of compilation.
+    fn write_with_warnings(&self, ext: &'static str, item: &Result<Item, Vec<Error>>, warnings: &[Error]) {
+        if self.emit == Emit::Json {
+            let output = self.path.with_extension(format!("{}.json", ext));
+            let mut outfile = File::create(output)
+                .expect("Internal error: IO");
+            let json = Emitter::new(self.base).output(item, warnings);
+            writeln!(outfile, "{}", json).expect("Internal error: IO");
+        }
+
+        if self.emit == Emit::Text || ext == "s" {
+            self.write_text(ext, item, warnings);
+        }
+    }
+
+    fn write_text<T: fmt::Display>(&self, ext: &'static str, item: &Result<T, Vec<Error>>, warnings: &[Error]) {
         let output = self.path.with_extension(ext);
         let mut outfile = File::create(output)
             .expect("Internal error: IO");
 
         match item {
-        | Ok(item) => write!(outfile, "{}", item).expect("Internal error: IO"),
-        | Err(err) => write!(outfile, "{}", err.to_debug(&self.code)).expect("Internal error: IO"),
+        | Ok(item) => {
+            write!(outfile, "{}", item).expect("Internal error: IO");
+            for warning in warnings {
+                write!(outfile, "\n{}", warning.to_debug(&self.code)).expect("Internal error: IO");
+            }
+        },
+        | Err(errs) => {
+            let errs = errs.iter()
+                .map(|err| err.to_debug(&self.code))
+                .collect::<Vec<_>>()
+                .join("\n");
+            write!(outfile, "{}", errs).expect("Internal error: IO")
+        },
         };
     }
 }
 
+/// Checks that IR output by [phase] is in the form it promises.
+fn verify(phase: &dyn Phase, item: &Item) {
+    let (unit, form) = match (item, phase.form()) {
+    | (Item::Typed(unit), Some(form))
+    | (Item::Intermediate(unit), Some(form)) => (unit, form),
+    | _ => return,
+    };
+
+    if let Err(violations) = ir::verify(unit, form) {
+        let violations = violations.iter()
+            .map(|violation| violation.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        panic!("Internal error: IR is not {}\n{}", form, violations);
+    }
+}
+
 macro_rules! impl_phase {
-    ($phase:ident, $ext:expr, $item:pat => $result:expr) => {
+    (@impl $phase:ident, $ext:expr, $form:expr, |$compiler:ident| $item:pat => $result:expr) => {
         impl Phase for $phase {
-            fn process(&self, compiler: &Compiler, input: Item) -> Result<Item, Error> {
+            fn process(&self, $compiler: &Compiler, input: Item) -> Result<Item, Vec<Error>> {
+                let compiler = $compiler;
                 if self.1 { return Ok(input) }
 
                 match input {
@@ -117,6 +219,10 @@ macro_rules! impl_phase {
                 | _ => panic!("Internal error: incorrect phase input"),
                 }
             }
+
+            fn form(&self) -> Option<ir::Form> {
+                if self.1 { None } else { $form }
+            }
         }
 
         impl $phase {
@@ -128,58 +234,211 @@ macro_rules! impl_phase {
                 Box::new($phase(diagnostics, disable))
             }
         }
-    }
+    };
+    ($phase:ident, $ext:expr, $item:pat => $result:expr) => {
+        impl_phase!(@impl $phase, $ext, None, |_compiler| $item => $result);
+    };
+    ($phase:ident, $ext:expr, |$compiler:ident| $item:pat => $result:expr) => {
+        impl_phase!(@impl $phase, $ext, None, |$compiler| $item => $result);
+    };
+    ($phase:ident, $ext:expr, $form:expr, $item:pat => $result:expr) => {
+        impl_phase!(@impl $phase, $ext, Some($form), |_compiler| $item => $result);
+    };
 }
 
 pub struct Lex(pub bool, pub bool);
 
-impl_phase! (Lex, "lexed", Item::Source(source) => {
-    lex::lex(source).map(|tokens| Item::Tokens(tokens)) 
+impl_phase! (Lex, "lexed", |compiler| Item::Source(source) => {
+    lex::lex(source, compiler.config())
+        .map(|tokens| Item::Tokens(tokens))
+        .map_err(|err| vec![err])
 });
 
 pub struct Parse(pub bool, pub bool);
 
-impl_phase! (Parse, "parsed", Item::Tokens(tokens) => {
-    parse::parse(tokens).map(|ast| Item::Syntax(ast))
+impl_phase! (Parse, "parsed", |compiler| Item::Tokens(tokens) => {
+    parse::parse(tokens, compiler.config()).map(|ast| Item::Syntax(ast))
 });
 
 
 pub struct Type(pub bool, pub bool);
 
-impl_phase! (Type, "typed", Item::Syntax(ast) => {
-    check::check(ast).map(|unit| Item::Typed(unit))
-});
+impl Phase for Type {
+    fn process(&self, compiler: &Compiler, input: Item) -> Result<Item, Vec<Error>> {
+        if self.1 { return Ok(input) }
+
+        match input {
+        | Item::Syntax(ast) => {
+            let (result, warnings) = match check::check(ast, compiler.config()) {
+            | Ok((unit, warnings)) => (Ok(Item::Typed(unit)), warnings),
+            | Err(errs)            => (Err(errs), Vec::new()),
+            };
+            if self.0 { compiler.write_with_warnings("typed", &result, &warnings); }
+            compiler.warn(warnings);
+            result
+        }
+        | _ => panic!("Internal error: incorrect phase input"),
+        }
+    }
+
+    fn form(&self) -> Option<ir::Form> {
+        if self.1 { None } else { Some(ir::Form::Translated) }
+    }
+}
+
+impl Type {
+    pub fn new(diagnostics: bool) -> Box<Self> {
+        Box::new(Type(diagnostics, false))
+    }
+
+    pub fn maybe(diagnostics: bool, disable: bool) -> Box<Self> {
+        Box::new(Type(diagnostics, disable))
+    }
+}
+
+/// Reads IR in place of lexing, parsing, and type checking source, expecting it
+/// to be in the form given by the first field so that earlier stages can be skipped.
+pub struct ReadIR(pub ir::Form, pub bool);
+
+impl Phase for ReadIR {
+    fn process(&self, _compiler: &Compiler, input: Item) -> Result<Item, Vec<Error>> {
+        if self.1 { return Ok(input) }
+
+        match input {
+        | Item::Source(source) => {
+            let unit = translate::read_verified(source, self.0)?;
+            if self.0 == ir::Form::Translated { Ok(Item::Typed(unit)) } else { Ok(Item::Intermediate(unit)) }
+        }
+        | _ => panic!("Internal error: incorrect phase input"),
+        }
+    }
+
+    fn form(&self) -> Option<ir::Form> {
+        if self.1 { None } else { Some(self.0) }
+    }
+}
+
+impl ReadIR {
+    pub fn maybe(form: ir::Form, disable: bool) -> Box<Self> {
+        Box::new(ReadIR(form, disable))
+    }
+}
 
 pub struct Canonize(pub bool, pub bool);
 
-impl_phase! (Canonize, "canonized", Item::Typed(unit) => {
+impl_phase! (Canonize, "canonized", ir::Form::Canonical, Item::Typed(unit) => {
     Ok(Item::Intermediate(translate::canonize(unit)))
 });
 
 pub struct Fold(pub bool, pub bool);
 
-impl_phase! (Fold, "folded", Item::Intermediate(unit) => {
+impl_phase! (Fold, "folded", ir::Form::Canonical, Item::Intermediate(unit) => {
     Ok(Item::Intermediate(translate::fold(unit)))
 });
 
 pub struct Reorder(pub bool, pub bool);
 
-impl_phase! (Reorder, "reordered", Item::Intermediate(unit) => {
+impl_phase! (Reorder, "reordered", ir::Form::Reordered, Item::Intermediate(unit) => {
     Ok(Item::Intermediate(translate::reorder(unit)))
 });
 
+pub struct InterpretIR(pub bool, pub bool);
+
+impl Phase for InterpretIR {
+    fn process(&self, compiler: &Compiler, input: Item) -> Result<Item, Vec<Error>> {
+        if self.1 { return Ok(input) }
+
+        match input {
+        | Item::Typed(unit)
+        | Item::Intermediate(unit) => {
+            let result = Ok(Item::Exit(interpret::interpret_ir(&unit)));
+            if self.0 { compiler.write("interpreted", &result); }
+            result
+        }
+        | _ => panic!("Internal error: incorrect phase input"),
+        }
+    }
+}
+
+impl InterpretIR {
+    pub fn maybe(diagnostics: bool, disable: bool) -> Box<Self> {
+        Box::new(InterpretIR(diagnostics, disable))
+    }
+}
+
+pub struct InterpretAsm(pub bool, pub bool);
+
+impl Phase for InterpretAsm {
+    fn process(&self, compiler: &Compiler, input: Item) -> Result<Item, Vec<Error>> {
+        if self.1 { return Ok(input) }
+
+        let result = match input {
+        | Item::Abstract(unit) => Ok(Item::Exit(interpret::interpret_asm(&unit))),
+        | Item::Assembly(unit) => Ok(Item::Exit(interpret::interpret_asm(&unit))),
+        | _ => panic!("Internal error: incorrect phase input"),
+        };
+
+        if self.0 { compiler.write("interpreted", &result); }
+        result
+    }
+}
+
+impl InterpretAsm {
+    pub fn maybe(diagnostics: bool, disable: bool) -> Box<Self> {
+        Box::new(InterpretAsm(diagnostics, disable))
+    }
+}
+
 pub struct Tile(pub bool, pub bool);
 
 impl_phase! (Tile, "tiled", Item::Intermediate(unit) => {
     Ok(Item::Abstract(assemble::tile(unit)))
 });
 
+pub struct Liveness(pub bool, pub bool);
+
+impl Phase for Liveness {
+    fn process(&self, compiler: &Compiler, input: Item) -> Result<Item, Vec<Error>> {
+        if self.1 || !self.0 { return Ok(input) }
+
+        match input {
+        | Item::Abstract(unit) => {
+            let annotated = unit.functions.iter()
+                .map(|function| analyze::liveness::Liveness::new(&function.body).annotate(&function.body))
+                .collect::<Vec<_>>()
+                .join("\n");
+            compiler.write_text("live", &Ok(annotated), &[]);
+            Ok(Item::Abstract(unit))
+        }
+        | _ => panic!("Internal error: incorrect phase input"),
+        }
+    }
+}
+
+impl Liveness {
+    pub fn new(diagnostics: bool) -> Box<Self> {
+        Box::new(Liveness(diagnostics, false))
+    }
+}
+
 pub struct Trivial(pub bool, pub bool);
 
 impl_phase! (Trivial, "s", Item::Abstract(unit) => {
     Ok(Item::Assembly(assemble::allocate::<assemble::Trivial>(unit)))
 });
 
+pub struct Color(pub bool, pub bool);
+
+impl_phase! (Color, "s", Item::Abstract(unit) => {
+    Ok(Item::Assembly(assemble::allocate::<assemble::Color>(unit)))
+});
+
+pub struct Linear(pub bool, pub bool);
+
+impl_phase! (Linear, "s", Item::Abstract(unit) => {
+    Ok(Item::Assembly(assemble::allocate::<assemble::Linear>(unit)))
+});
+
 pub struct CoalesceAbstract(pub bool, pub bool);
 
 impl_phase! (CoalesceAbstract, "coalesced", Item::Abstract(unit) => {
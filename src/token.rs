@@ -2,8 +2,10 @@ use std::fmt;
 
 use simple_symbol::Symbol;
 
+use util::escape;
+
 /// Represents all valid lexical tokens in the Tiger language.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Token {
 
     // Keywords
@@ -137,7 +139,7 @@ pub enum Token {
 
     // Literals
     
-    Int(i32),
+    Int(u64),
 
     Str(String),
 
@@ -190,7 +192,7 @@ impl fmt::Display for Token {
         | Token::Colon         => write!(fmt, "SYMBOL :"),
         | Token::Comma         => write!(fmt, "SYMBOL ,"),
         | Token::Int(n)        => write!(fmt, "INTEGER {}", n),
-        | Token::Str(s)        => write!(fmt, "STRING \"{}\"", s),
+        | Token::Str(s)        => write!(fmt, "STRING \"{}\"", escape(s)),
         | Token::Ident(i)      => write!(fmt, "IDENTIFIER {}", i),
         }
     }
@@ -1,3 +1,5 @@
+use fnv::FnvHashSet;
+
 use ir::*;
 use operand::Temp;
 
@@ -5,288 +7,275 @@ pub fn canonize(unit: Unit) -> Unit {
     unit.map(|function| {
         function.map(|body| {
             body.into_iter()
-                .map(|stm| canonize_stm(stm))
-                .flat_map(|(_, stms)| stms.into_iter())
+                .flat_map(|stm| canonize_stm(stm).into_iter())
                 .collect()
         })
     })
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-enum Purity {
-    Pure,
-    Memory,
-    Impure,
+/// What running a list of statements or evaluating an expression can observe or change.
+#[derive(Debug, Default)]
+struct Effect {
+
+    /// Temporaries that are read.
+    reads: FnvHashSet<Temp>,
+
+    /// Temporaries that are written.
+    writes: FnvHashSet<Temp>,
+
+    /// Whether any memory is read.
+    loads: bool,
+
+    /// Whether any memory is written.
+    stores: bool,
+
+    /// Whether a call is made, which can also read or write any memory
+    /// and clobbers the caller-saved registers.
+    calls: bool,
 }
 
-impl Purity {
-    fn and(self, other: Purity) -> Purity {
-        match (self, other) {
-        | (Purity::Pure, Purity::Pure)     => Purity::Pure,
-        | (Purity::Memory, Purity::Pure)   => Purity::Memory,     
-        | (Purity::Pure, Purity::Memory)   => Purity::Memory,
-        | (Purity::Memory, Purity::Memory) => Purity::Memory,
-        | _                                => Purity::Impure,
+impl Effect {
+
+    fn of_stms(stms: &[Stm]) -> Self {
+        let mut effect = Effect::default();
+        for stm in stms { effect.stm(stm); }
+        effect
+    }
+
+    fn of_exp(exp: &Exp) -> Self {
+        let mut effect = Effect::default();
+        effect.exp(exp);
+        effect
+    }
+
+    fn stm(&mut self, stm: &Stm) {
+        match stm {
+        | Stm::Move(src_exp, Exp::Temp(temp)) => {
+            self.exp(src_exp);
+            self.writes.insert(*temp);
+        },
+        | Stm::Move(src_exp, Exp::Mem(addr_exp)) => {
+            self.exp(src_exp);
+            self.exp(addr_exp);
+            self.stores = true;
+        },
+        | Stm::Move(src_exp, dst_exp) => {
+            self.exp(src_exp);
+            self.exp(dst_exp);
+        },
+        | Stm::Exp(exp)
+        | Stm::Jump(exp, _) => self.exp(exp),
+        | Stm::CJump(lhs_exp, _, rhs_exp, _, _) => {
+            self.exp(lhs_exp);
+            self.exp(rhs_exp);
+        },
+        | Stm::Seq(stms) => for stm in stms { self.stm(stm) },
+        | Stm::Label(_)
+        | Stm::Comment(_) => (),
         }
     }
 
-    fn is_affected_by(self, other: Purity) -> bool {
-        match (self, other) {
-        | (Purity::Impure, Purity::Impure)
-        | (Purity::Memory, Purity::Impure) => true,
-        | _                                => false,
+    fn exp(&mut self, exp: &Exp) {
+        match exp {
+        | Exp::Const(_)
+        | Exp::Name(_) => (),
+        | Exp::Temp(temp) => { self.reads.insert(*temp); },
+        | Exp::Binop(lhs_exp, _, rhs_exp) => {
+            self.exp(lhs_exp);
+            self.exp(rhs_exp);
+        },
+        | Exp::Mem(addr_exp) => {
+            self.exp(addr_exp);
+            self.loads = true;
+        },
+        | Exp::Call(name_exp, arg_exps) => {
+            self.exp(name_exp);
+            for arg_exp in arg_exps { self.exp(arg_exp); }
+            self.loads = true;
+            self.stores = true;
+            self.calls = true;
+        },
+        | Exp::ESeq(stm, exp) => {
+            self.stm(stm);
+            self.exp(exp);
+        },
         }
     }
+
+    /// Whether a call made by [self] could change or observe any temporary used by [other].
+    fn clobbers(&self, other: &Effect) -> bool {
+        self.calls && other.reads.iter()
+            .chain(other.writes.iter())
+            .any(|temp| match temp {
+            | Temp::Reg(reg) => reg.is_caller_saved(),
+            | Temp::Temp{..} => false,
+            })
+    }
+
+    /// Whether [self] and [other] can run in either order with the same results.
+    fn commutes(&self, other: &Effect) -> bool {
+        self.writes.is_disjoint(&other.reads)
+            && self.writes.is_disjoint(&other.writes)
+            && other.writes.is_disjoint(&self.reads)
+            && !(self.stores && (other.loads || other.stores))
+            && !(other.stores && self.loads)
+            && !self.clobbers(other)
+            && !other.clobbers(self)
+    }
 }
 
-fn canonize_exp(exp: Exp) -> (Purity, Exp, Vec<Stm>) {
+/// Orders [exp], which runs after its statements [stms], before [later] statements
+/// that should run after it. When [later] could change the value of [exp],
+/// it is saved to a fresh temporary named [name] first.
+fn hoist(exp: Exp, mut stms: Vec<Stm>, mut later: Vec<Stm>, name: &'static str) -> (Exp, Vec<Stm>) {
+
+    // Canonized expressions have no effects of their own, so only their reads matter
+    let commutes = match exp {
+    | Exp::Const(_)
+    | Exp::Name(_) => true,
+    | _            => later.is_empty() || Effect::of_stms(&later).commutes(&Effect::of_exp(&exp)),
+    };
+
+    if commutes {
+        stms.append(&mut later);
+        (exp, stms)
+    } else {
+        let protect = Temp::from_str(name);
+        stms.push(Stm::Move(exp, Exp::Temp(protect)));
+        stms.append(&mut later);
+        (Exp::Temp(protect), stms)
+    }
+}
+
+/// Returns an expression without [ESeq] or [Call], and the statements that must run before it.
+fn canonize_exp(exp: Exp) -> (Exp, Vec<Stm>) {
 
     match exp {
     | Exp::Const(_)
     | Exp::Name(_)
-    | Exp::Temp(_) => (Purity::Pure, exp, vec![]),
+    | Exp::Temp(_) => (exp, vec![]),
     | Exp::Binop(lhs_exp, op, rhs_exp) => {
 
-        let (lhs_purity, lhs_exp, mut lhs_stms) = canonize_exp(*lhs_exp);
-        let (rhs_purity, rhs_exp, mut rhs_stms) = canonize_exp(*rhs_exp);
+        let (lhs_exp, lhs_stms) = canonize_exp(*lhs_exp);
+        let (rhs_exp, rhs_stms) = canonize_exp(*rhs_exp);
 
         // Evaluation of RHS might affect evaluation of LHS
-        if lhs_purity.is_affected_by(rhs_purity) {
-
-            let protect = Temp::from_str("CANONIZE_BINOP_LHS");
-
-            // Move LHS result into temp before evaluating RHS
-            lhs_stms.push(Stm::Move(
-                lhs_exp,
-                Exp::Temp(protect),
-            ));
-
-            lhs_stms.append(&mut rhs_stms);
-
-            let canonized = Exp::Binop(
-                Box::new(Exp::Temp(protect)),
-                op,
-                Box::new(rhs_exp),
-            );
-
-            (Purity::Impure, canonized, lhs_stms)
-        }
+        let (lhs_exp, stms) = hoist(lhs_exp, lhs_stms, rhs_stms, "CANONIZE_BINOP_LHS");
 
-        // Evaluation of RHS doesn't affect evaluation of LHS
-        else {
-
-            // Safe to evaluate all statements before expression
-            lhs_stms.append(&mut rhs_stms);
-
-            let canonized = Exp::Binop(
-                Box::new(lhs_exp),
-                op,
-                Box::new(rhs_exp),
-            );
+        let canonized = Exp::Binop(
+            Box::new(lhs_exp),
+            op,
+            Box::new(rhs_exp),
+        );
 
-            (Purity::Pure, canonized, lhs_stms)
-        }
+        (canonized, stms)
     }
     | Exp::Mem(addr_exp) => {
 
-        let (purity, exp, stms) = canonize_exp(*addr_exp);
+        let (addr_exp, addr_stms) = canonize_exp(*addr_exp);
 
         let canonized = Exp::Mem(
-            Box::new(exp)
+            Box::new(addr_exp)
         );
 
-        // Memory access is pure if address expression is pure
-        let memory_purity = match purity {
-        | Purity::Impure => Purity::Impure, 
-        | _              => Purity::Memory,
-        };
-
-        (memory_purity, canonized, stms)
-
+        (canonized, addr_stms)
     },
     | Exp::Call(name_exp, arg_exps) => {
 
-        let mut all_purity = Purity::Pure;
         let mut all_stms = vec![];
         let mut all_exps = vec![];
 
-        // Impure args may affect all args before them
+        // Later args may affect all args before them
         for arg_exp in arg_exps.into_iter().rev() {
-
-            let (arg_purity, arg_exp, mut arg_stms) = canonize_exp(arg_exp);
-
-            // Arg possibly conflicts with later arg
-            if arg_purity.is_affected_by(all_purity) {
-
-                // Move arg into temp
-                let arg_temp = Temp::from_str("CANONIZE_CALL_ARG");
-                arg_stms.push(Stm::Move(arg_exp, Exp::Temp(arg_temp)));
-
-                // Append later arg statements
-                arg_stms.append(&mut all_stms);
-                all_stms = arg_stms;
-                all_exps.insert(0, Exp::Temp(arg_temp));
-                all_purity = Purity::Impure;
-
-            } else {
-                arg_stms.append(&mut all_stms);
-                all_stms = arg_stms;
-                all_exps.insert(0, arg_exp);
-            }
+            let (arg_exp, arg_stms) = canonize_exp(arg_exp);
+            let (arg_exp, arg_stms) = hoist(arg_exp, arg_stms, all_stms, "CANONIZE_CALL_ARG");
+            all_stms = arg_stms;
+            all_exps.insert(0, arg_exp);
         }
 
-        let (name_purity, name_exp, mut name_stms) = canonize_exp(*name_exp);
-
-        if name_purity.is_affected_by(all_purity) {
-
-            let name_temp = Temp::from_str("CANONIZE_CALL_NAME");
-            let call_temp = Temp::from_str("CANONIZE_CALL");
-
-            name_stms.push(Stm::Move(
-                name_exp,
-                Exp::Temp(name_temp)
-            ));
-
-            name_stms.append(&mut all_stms);
-
-            // Move result of call into temp to prevent clobbering
-            name_stms.push(Stm::Move(
-                Exp::Call(
-                    Box::new(Exp::Temp(name_temp)),
-                    all_exps,
-                ),
-                Exp::Temp(call_temp),
-            ));
-
-            (Purity::Impure, Exp::Temp(call_temp), name_stms)
+        let (name_exp, name_stms) = canonize_exp(*name_exp);
+        let (name_exp, mut name_stms) = hoist(name_exp, name_stms, all_stms, "CANONIZE_CALL_NAME");
 
-        } else {
+        // Move result of call into temp to prevent clobbering
+        let call_temp = Temp::from_str("CANONIZE_CALL");
 
-            let call_temp = Temp::from_str("CANONIZE_CALL");
-
-            name_stms.append(&mut all_stms);
-
-            // Move result of call into temp to prevent clobbering
-            name_stms.push(Stm::Move(
-                Exp::Call(
-                    Box::new(name_exp),
-                    all_exps,
-                ),
-                Exp::Temp(call_temp),
-            ));
-
-            (Purity::Impure, Exp::Temp(call_temp), name_stms)
+        name_stms.push(Stm::Move(
+            Exp::Call(
+                Box::new(name_exp),
+                all_exps,
+            ),
+            Exp::Temp(call_temp),
+        ));
 
-        }
+        (Exp::Temp(call_temp), name_stms)
     },
     | Exp::ESeq(stm, exp) => {
 
-        let (exp_purity, exp_exp, mut exp_stms) = canonize_exp(*exp);
-        let (stm_purity, mut stm_stms) = canonize_stm(*stm);
+        let mut stm_stms = canonize_stm(*stm);
+        let (exp, mut exp_stms) = canonize_exp(*exp);
         stm_stms.append(&mut exp_stms);
-
-        // ESeq is pure if both statement and expression are pure
-        (exp_purity.and(stm_purity), exp_exp, stm_stms)
+        (exp, stm_stms)
 
     },
     }
 }
 
-fn canonize_stm(stm: Stm) -> (Purity, Vec<Stm>) {
+fn canonize_stm(stm: Stm) -> Vec<Stm> {
 
     match stm {
     | Stm::Label(_)
-    | Stm::Comment(_) => (Purity::Pure, vec![stm]),
+    | Stm::Comment(_) => vec![stm],
     | Stm::Move(src_exp, dst_exp) => {
 
-        let (src_purity, src_exp, mut src_stms) = canonize_exp(src_exp);
-        let (dst_purity, dst_exp, mut dst_stms) = canonize_exp(dst_exp);
-
-        if src_purity.is_affected_by(dst_purity) {
-
-            let src_temp = Temp::from_str("CANONIZE_MOVE_SRC");
-
-            src_stms.push(Stm::Move(
-                src_exp,
-                Exp::Temp(src_temp),
-            ));
+        let (src_exp, src_stms) = canonize_exp(src_exp);
+        let (dst_exp, dst_stms) = canonize_exp(dst_exp);
 
-            src_stms.append(&mut dst_stms);
+        // Evaluation of the destination might affect evaluation of the source
+        let (src_exp, mut stms) = hoist(src_exp, src_stms, dst_stms, "CANONIZE_MOVE_SRC");
 
-            src_stms.push(Stm::Move(
-                Exp::Temp(src_temp),
-                dst_exp
-            ));
-
-        } else {
-
-            src_stms.append(&mut dst_stms);
-
-            src_stms.push(Stm::Move(
-                src_exp,
-                dst_exp,
-            ));
-
-        }
+        stms.push(Stm::Move(
+            src_exp,
+            dst_exp,
+        ));
 
-        (Purity::Impure, src_stms)
+        stms
 
     },
     | Stm::Exp(exp) => {
 
-        let (exp_purity, _, exp_stms) = canonize_exp(exp);
-        (exp_purity, exp_stms)
+        let (_, exp_stms) = canonize_exp(exp);
+        exp_stms
 
     },
     | Stm::Jump(addr_exp, labels) => {
 
-        let (_, addr_exp, mut addr_stms) = canonize_exp(addr_exp);
+        let (addr_exp, mut addr_stms) = canonize_exp(addr_exp);
 
         addr_stms.push(Stm::Jump(
             addr_exp,
             labels
         ));
 
-        (Purity::Impure, addr_stms)
+        addr_stms
 
     },
     | Stm::CJump(lhs_exp, op, rhs_exp, t, f) => {
 
-        let (lhs_purity, lhs_exp, mut lhs_stms) = canonize_exp(lhs_exp);
-        let (rhs_purity, rhs_exp, mut rhs_stms) = canonize_exp(rhs_exp);
-
-        if lhs_purity.is_affected_by(rhs_purity) {
-
-            let lhs_temp = Temp::from_str("CANONIZE_CJUMP_LHS");
-
-            lhs_stms.push(Stm::Move(
-                lhs_exp,
-                Exp::Temp(lhs_temp),
-            ));
-
-            lhs_stms.append(&mut rhs_stms);
-
-            lhs_stms.push(Stm::CJump(Exp::Temp(lhs_temp), op, rhs_exp, t, f));
-
-        } else {
-
-            lhs_stms.append(&mut rhs_stms);
-            lhs_stms.push(Stm::CJump(lhs_exp, op, rhs_exp, t, f));
-
-        }
+        let (lhs_exp, lhs_stms) = canonize_exp(lhs_exp);
+        let (rhs_exp, rhs_stms) = canonize_exp(rhs_exp);
 
+        // Evaluation of RHS might affect evaluation of LHS
+        let (lhs_exp, mut stms) = hoist(lhs_exp, lhs_stms, rhs_stms, "CANONIZE_CJUMP_LHS");
 
-        (Purity::Impure, lhs_stms)
+        stms.push(Stm::CJump(lhs_exp, op, rhs_exp, t, f));
+        stms
 
     },
     | Stm::Seq(stms) => {
 
         stms.into_iter()
-            .map(|stm| canonize_stm(stm))
-            .fold((Purity::Pure, vec![]), |(all_purity, mut all_stms), (stm_purity, mut stm_stms)| {
-                all_stms.append(&mut stm_stms);
-                (stm_purity.and(all_purity), all_stms)
-            })
+            .flat_map(|stm| canonize_stm(stm).into_iter())
+            .collect()
 
     },
     }
@@ -1,16 +1,112 @@
+use fnv::FnvHashMap;
+
+use analyze::constant::{assign, constant_propagation, Constant};
+use analyze::flow::Flow;
 use ir::*;
-use operand::Label;
+use operand::{Label, Temp};
 
 pub fn fold(unit: Unit) -> Unit {
     unit.map(|function| {
+        let start = function.label;
         function.map(|body| {
-            body.into_iter()
+            propagate(start, body).into_iter()
                 .map(|stm| fold_stm(&stm))
                 .collect()
         })
     })
 }
 
+/// Replaces temporaries that hold the same constant along every path reaching them.
+fn propagate(start: Label, body: Vec<Stm>) -> Vec<Stm> {
+
+    let flow = Flow::new(start, body.clone());
+    let solution = constant_propagation(&flow);
+    let mut constants = solution.input(start).clone();
+    let mut propagated = Vec::with_capacity(body.len());
+
+    for stm in body {
+
+        // Facts are only known at the start of reachable blocks
+        match stm {
+        | Stm::Label(label) if flow.graph().contains_node(label) => {
+            constants = solution.input(label).clone();
+        },
+        | Stm::Label(_) => constants.clear(),
+        | _ => (),
+        }
+
+        let stm = propagate_stm(stm, &constants);
+        assign(&stm, &mut constants);
+
+        if let Stm::Jump(..) | Stm::CJump(..) = stm { constants.clear() }
+        propagated.push(stm);
+    }
+
+    propagated
+}
+
+fn propagate_stm(stm: Stm, constants: &FnvHashMap<Temp, Constant>) -> Stm {
+    let propagate = |exp| propagate_exp(exp, constants);
+    match stm {
+    | Stm::Move(src_exp, Exp::Mem(addr_exp)) => {
+        Stm::Move(
+            propagate(src_exp),
+            Exp::Mem(Box::new(propagate(*addr_exp))),
+        )
+    },
+    | Stm::Move(src_exp, dst_exp) => Stm::Move(propagate(src_exp), dst_exp),
+    | Stm::Exp(exp) => Stm::Exp(propagate(exp)),
+    | Stm::Jump(dst_exp, labels) => Stm::Jump(propagate(dst_exp), labels),
+    | Stm::CJump(lhs_exp, op, rhs_exp, t, f) => {
+        Stm::CJump(propagate(lhs_exp), op, propagate(rhs_exp), t, f)
+    },
+    // Canonical IR has no nested statements
+    | Stm::Seq(_)
+    | Stm::Label(_)
+    | Stm::Comment(_) => stm,
+    }
+}
+
+fn propagate_exp(exp: Exp, constants: &FnvHashMap<Temp, Constant>) -> Exp {
+    match exp {
+    // Registers can be clobbered by calls, so only temporaries are replaced
+    | Exp::Temp(temp @ Temp::Temp { .. }) => match constants.get(&temp) {
+        | Some(Constant::Const(n)) => Exp::Const(*n),
+        | _                        => exp,
+    },
+    | Exp::Binop(lhs_exp, op, rhs_exp) => {
+        Exp::Binop(
+            Box::new(propagate_exp(*lhs_exp, constants)),
+            op,
+            Box::new(propagate_exp(*rhs_exp, constants)),
+        )
+    },
+    | Exp::Mem(addr_exp) => Exp::Mem(Box::new(propagate_exp(*addr_exp, constants))),
+    | Exp::Call(name_exp, arg_exps) => {
+        Exp::Call(
+            Box::new(propagate_exp(*name_exp, constants)),
+            arg_exps.into_iter()
+                .map(|arg_exp| propagate_exp(arg_exp, constants))
+                .collect(),
+        )
+    },
+    | Exp::Const(_)
+    | Exp::Name(_)
+    | Exp::Temp(_)
+    | Exp::ESeq(_, _) => exp,
+    }
+}
+
+/// Evaluates a condition that folds down to a constant, if any.
+pub fn fold_cond(cond: &Cond) -> Option<bool> {
+    let t = Label::from_fixed("TRUE");
+    let f = Label::from_fixed("FALSE");
+    match fold_stm(&cond(t, f)) {
+    | Stm::Jump(_, targets) => Some(targets[0] == t),
+    | _                     => None,
+    }
+}
+
 fn fold_exp(exp: &Exp) -> Exp {
     match exp {
     | Exp::Const(_)
@@ -58,18 +154,22 @@ fn fold_binop(lhs_exp: &Exp, op: &Binop, rhs_exp: &Exp) -> Exp {
     | (_            ,   Binop::And,     Exp::Const(0)) => Exp::Const(0),
     | (Exp::Const(lhs), op,             Exp::Const(rhs)) => {
 
+        // Arithmetic wraps like the machine, but division that would trap is left for run time
         let result = match op {
-        | Binop::Add => lhs + rhs,
-        | Binop::Sub => lhs - rhs,
-        | Binop::Mul => lhs * rhs,
-        | Binop::Div => lhs / rhs,
-        | Binop::Mod => lhs % rhs,
-        | Binop::And => lhs & rhs,
-        | Binop::Or  => lhs | rhs,
-        | Binop::XOr => lhs ^ rhs,
+        | Binop::Add => Some(lhs.wrapping_add(rhs)),
+        | Binop::Sub => Some(lhs.wrapping_sub(rhs)),
+        | Binop::Mul => Some(lhs.wrapping_mul(rhs)),
+        | Binop::Div => lhs.checked_div(rhs),
+        | Binop::Mod => lhs.checked_rem(rhs),
+        | Binop::And => Some(lhs & rhs),
+        | Binop::Or  => Some(lhs | rhs),
+        | Binop::XOr => Some(lhs ^ rhs),
         };
 
-        Exp::Const(result)
+        match result {
+        | Some(result) => Exp::Const(result),
+        | None         => Exp::Binop(Box::new(Exp::Const(lhs)), *op, Box::new(Exp::Const(rhs))),
+        }
     },
     | (lhs_exp, op, rhs_exp) => Exp::Binop(Box::new(lhs_exp), *op, Box::new(rhs_exp)),
     }
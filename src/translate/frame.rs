@@ -18,7 +18,7 @@ impl Access {
         | Access::Frame(n) => {
 
             let offset = ir::Exp::Const(
-                n as i32 * WORD_SIZE
+                n as i64 * WORD_SIZE as i64
             );
 
             ir::Exp::Mem(
@@ -111,7 +111,7 @@ impl Frame {
             );
 
             let offset = ir::Exp::Const(
-                (i - 4) as i32 * WORD_SIZE
+                (i - 4) as i64 * WORD_SIZE as i64
             );
 
             ir::Exp::Mem(
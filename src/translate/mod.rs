@@ -1,11 +1,13 @@
 mod canonize;
 mod fold;
 mod frame;
+mod read;
 mod reorder;
 mod translate;
 
 pub use self::translate::*;
 pub use self::frame::Frame;
-pub use self::fold::fold;
+pub use self::fold::{fold, fold_cond};
 pub use self::canonize::canonize;
 pub use self::reorder::reorder;
+pub use self::read::{read, read_verified};
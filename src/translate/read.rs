@@ -0,0 +1,422 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use codespan::{ByteIndex, ByteOffset, FileMap};
+use simple_symbol::store;
+
+use error::{Error, LexError, ParseError};
+use ir::*;
+use operand::{Label, LabelID, Reg, Temp, TempID};
+use span::Span;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    LParen,
+    RParen,
+    Atom(String),
+    Str(String),
+}
+
+type Spanned = (ByteIndex, Token, ByteIndex);
+
+/// Reads a unit in the format written by its [Display] implementation,
+/// so that hand-written IR can be fed to the later phases of the compiler.
+///
+/// Labels and temporaries ending in `_[id]` are read as generated ones, and the
+/// counters are advanced past them so that later phases don't reuse their ids.
+pub fn read(source: Arc<FileMap>) -> Result<Unit, Error> {
+    read_spanned(source).map(|(unit, _)| unit)
+}
+
+/// Reads a unit like [read], reporting every statement that breaks the invariants
+/// of [form] as an error, so that later phases never see malformed IR.
+pub fn read_verified(source: Arc<FileMap>, form: Form) -> Result<Unit, Vec<Error>> {
+    let (unit, spans) = read_spanned(source).map_err(|err| vec![err])?;
+
+    let violations = match verify(&unit, form) {
+    | Ok(())          => return Ok(unit),
+    | Err(violations) => violations,
+    };
+
+    Err(violations.into_iter()
+        .map(|violation| {
+            let function = unit.functions.iter()
+                .position(|function| function.label == violation.function)
+                .expect("Internal error: violation in missing function");
+            let span = spans[function][violation.index];
+            Error::syntactic(span.start(), span.end(), ParseError::Malformed(violation.reason))
+        })
+        .collect())
+}
+
+/// Reads a unit along with the span of each statement in each function body.
+fn read_spanned(source: Arc<FileMap>) -> Result<(Unit, Vec<Vec<Span>>), Error> {
+    let tokens = tokenize(&source)?;
+    let mut reader = Reader {
+        tokens,
+        next: 0,
+        eof: source.span().end(),
+        labels: 0,
+        temps: 0,
+        spans: Vec::new(),
+    };
+
+    let unit = reader.unit()?;
+    let next = LabelID::next();
+    LabelID::set(usize::max(next, reader.labels));
+    let next = TempID::next();
+    TempID::set(usize::max(next, reader.temps));
+    Ok((unit, reader.spans))
+}
+
+fn tokenize(source: &FileMap) -> Result<Vec<Spanned>, Error> {
+    let base = source.span().start();
+    let text = source.src();
+    let at = |index: usize| base + ByteOffset(index as i64);
+    let mut chars = text.char_indices().peekable();
+    let mut tokens = Vec::new();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+        | '(' => tokens.push((at(start), Token::LParen, at(start + 1))),
+        | ')' => tokens.push((at(start), Token::RParen, at(start + 1))),
+        | '"' => {
+
+            // Strings are escaped as in Tiger source, with one char per byte
+            let mut string = String::new();
+            let end = loop {
+                match chars.next() {
+                | None             => return Err(Error::lexical(at(start), at(text.len()), LexError::UnterminatedString)),
+                | Some((end, '"')) => break end + 1,
+                | Some((escape, '\\')) => match chars.next() {
+                    | Some((_, 'n'))  => string.push('\n'),
+                    | Some((_, 't'))  => string.push('\t'),
+                    | Some((_, '"'))  => string.push('"'),
+                    | Some((_, '\\')) => string.push('\\'),
+                    | Some((_, c)) if c.is_ascii_digit() => {
+                        let mut value = c.to_digit(10).unwrap();
+                        for _ in 0..2 {
+                            match chars.next() {
+                            | Some((_, c)) if c.is_ascii_digit() => value = value * 10 + c.to_digit(10).unwrap(),
+                            | _ => return Err(Error::lexical(at(escape), at(escape + 2), LexError::InvalidDecimal)),
+                            }
+                        }
+                        if value > 255 {
+                            return Err(Error::lexical(at(escape), at(escape + 4), LexError::DecimalRange))
+                        }
+                        string.push(value as u8 as char);
+                    },
+                    | _ => return Err(Error::lexical(at(escape), at(escape + 1), LexError::InvalidEscape)),
+                },
+                | Some((_, c)) => {
+                    let mut buffer = [0; 4];
+                    for byte in c.encode_utf8(&mut buffer).bytes() {
+                        string.push(byte as char);
+                    }
+                },
+                }
+            };
+            tokens.push((at(start), Token::Str(string), at(end)));
+        },
+        | c if c.is_whitespace() => (),
+        | _ => {
+            let mut end = start + c.len_utf8();
+            while let Some(&(index, c)) = chars.peek() {
+                if c.is_whitespace() || c == '(' || c == ')' || c == '"' { break }
+                end = index + c.len_utf8();
+                chars.next();
+            }
+            tokens.push((at(start), Token::Atom(text[start..end].to_string()), at(end)));
+        },
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Splits a generated name into its prefix and id, as in `STRING_0`.
+fn split_id(name: &str) -> Option<(&str, usize)> {
+    let index = name.rfind('_')?;
+    let (prefix, id) = (&name[..index], &name[index + 1..]);
+    if prefix.is_empty() || id.is_empty() || !id.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None
+    }
+    id.parse().ok().map(|id| (prefix, id))
+}
+
+fn reg(name: &str) -> Option<Reg> {
+    match name {
+    | "RAX" => Some(Reg::RAX),
+    | "RBX" => Some(Reg::RBX),
+    | "RCX" => Some(Reg::RCX),
+    | "RDX" => Some(Reg::RDX),
+    | "RBP" => Some(Reg::RBP),
+    | "RSP" => Some(Reg::RSP),
+    | "RSI" => Some(Reg::RSI),
+    | "RDI" => Some(Reg::RDI),
+    | "R8"  => Some(Reg::R8),
+    | "R9"  => Some(Reg::R9),
+    | "R10" => Some(Reg::R10),
+    | "R11" => Some(Reg::R11),
+    | "R12" => Some(Reg::R12),
+    | "R13" => Some(Reg::R13),
+    | "R14" => Some(Reg::R14),
+    | "R15" => Some(Reg::R15),
+    | _     => None,
+    }
+}
+
+struct Reader {
+    tokens: Vec<Spanned>,
+    next: usize,
+    eof: ByteIndex,
+
+    /// One more than the largest label id read
+    labels: usize,
+
+    /// One more than the largest temporary id read
+    temps: usize,
+
+    /// Spans of the statements in each function body read
+    spans: Vec<Vec<Span>>,
+}
+
+impl Reader {
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token, _)| token)
+    }
+
+    /// Keyword of the form starting at the next token, if any.
+    fn peek_form(&self) -> Option<&str> {
+        match (self.peek(), self.tokens.get(self.next + 1)) {
+        | (Some(Token::LParen), Some((_, Token::Atom(keyword), _))) => Some(keyword),
+        | _ => None,
+        }
+    }
+
+    fn error(&self, expected: &[&str]) -> Error {
+        let expected = expected.iter().map(|expected| expected.to_string()).collect();
+        match self.tokens.get(self.next) {
+        | Some((start, _, end)) => Error::syntactic(*start, *end, ParseError::Unexpected(expected)),
+        | None                  => Error::syntactic(self.eof, self.eof, ParseError::EOF(expected)),
+        }
+    }
+
+    fn expect(&mut self, token: Token, expected: &str) -> Result<(), Error> {
+        if self.peek() != Some(&token) { return Err(self.error(&[expected])) }
+        self.next += 1;
+        Ok(())
+    }
+
+    fn atom(&mut self, expected: &str) -> Result<String, Error> {
+        match self.peek() {
+        | Some(Token::Atom(atom)) => {
+            let atom = atom.clone();
+            self.next += 1;
+            Ok(atom)
+        },
+        | _ => Err(self.error(&[expected])),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        match self.peek() {
+        | Some(Token::Str(string)) => {
+            let string = string.clone();
+            self.next += 1;
+            Ok(string)
+        },
+        | _ => Err(self.error(&["string"])),
+        }
+    }
+
+    fn int<T: FromStr>(&mut self) -> Result<T, Error> {
+        match self.peek() {
+        | Some(Token::Atom(atom)) => match atom.parse() {
+            | Ok(int) => { self.next += 1; Ok(int) },
+            | Err(_)  => Err(self.error(&["integer"])),
+        },
+        | _ => Err(self.error(&["integer"])),
+        }
+    }
+
+    /// Opens a form, returning its keyword if it is one of [keywords].
+    fn open(&mut self, keywords: &[&str]) -> Result<String, Error> {
+        self.expect(Token::LParen, "(")?;
+        match self.peek() {
+        | Some(Token::Atom(keyword)) if keywords.contains(&keyword.as_str()) => {
+            let keyword = keyword.clone();
+            self.next += 1;
+            Ok(keyword)
+        },
+        | _ => Err(self.error(keywords)),
+        }
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.expect(Token::RParen, ")")
+    }
+
+    fn label(&mut self) -> Result<Label, Error> {
+        let name = self.atom("label")?;
+        match split_id(&name) {
+        | Some((name, id)) => {
+            self.labels = usize::max(self.labels, id + 1);
+            Ok(Label::Unfixed { id, name: store(name) })
+        },
+        | None => Ok(Label::Fixed(store(&name))),
+        }
+    }
+
+    fn temp(&mut self) -> Result<Temp, Error> {
+        let temp = match self.peek() {
+        | Some(Token::Atom(atom)) if atom.starts_with("TEMP_") => atom["TEMP_".len()..].to_string(),
+        | _ => return Err(self.error(&["temporary"])),
+        };
+
+        let temp = match (reg(&temp), split_id(&temp)) {
+        | (Some(reg), _)     => Temp::Reg(reg),
+        | (_, Some((name, id))) => {
+            self.temps = usize::max(self.temps, id + 1);
+            Temp::Temp { id, name: store(name) }
+        },
+        | (None, None) => return Err(self.error(&["temporary"])),
+        };
+
+        self.next += 1;
+        Ok(temp)
+    }
+
+    fn unit(&mut self) -> Result<Unit, Error> {
+        let mut data = Vec::new();
+        while self.peek_form() == Some("DATA") {
+            self.open(&["DATA"])?;
+            let label = self.label()?;
+            let string = self.string()?;
+            self.close()?;
+            data.push(Data::with_label(label, store(&string)));
+        }
+
+        let mut functions = Vec::new();
+        while self.peek().is_some() {
+            functions.push(self.function()?);
+        }
+
+        Ok(Unit { data, functions })
+    }
+
+    fn function(&mut self) -> Result<Function, Error> {
+        let label = self.label()?;
+        let mut escapes = 0;
+        if self.peek_form() == Some("ESCAPES") {
+            self.open(&["ESCAPES"])?;
+            escapes = self.int()?;
+            self.close()?;
+        }
+
+        let mut body = Vec::new();
+        let mut spans = Vec::new();
+        while self.peek() == Some(&Token::LParen) {
+            let (start, _, _) = self.tokens[self.next];
+            body.push(self.stm()?);
+            let (_, _, end) = self.tokens[self.next - 1];
+            spans.push(Span::new(start, end));
+        }
+
+        self.spans.push(spans);
+        Ok(Function { label, body, escapes })
+    }
+
+    fn stm(&mut self) -> Result<Stm, Error> {
+        let keyword = self.open(&["MOVE", "EXP", "JUMP", "CJUMP", "SEQ", "LABEL", "COMMENT"])?;
+        let stm = match keyword.as_str() {
+        | "MOVE"    => Stm::Move(self.exp()?, self.exp()?),
+        | "EXP"     => Stm::Exp(self.exp()?),
+        | "JUMP"    => {
+            let target = self.exp()?;
+            let labels = match &target {
+            | Exp::Name(label) => vec![*label],
+            | _                => Vec::new(),
+            };
+            Stm::Jump(target, labels)
+        },
+        | "CJUMP"   => Stm::CJump(self.exp()?, self.relop()?, self.exp()?, self.label()?, self.label()?),
+        | "SEQ"     => {
+            let mut stms = Vec::new();
+            while self.peek() == Some(&Token::LParen) {
+                stms.push(self.stm()?);
+            }
+            Stm::Seq(stms)
+        },
+        | "LABEL"   => Stm::Label(self.label()?),
+        | "COMMENT" => Stm::Comment(self.string()?),
+        | _         => unreachable!(),
+        };
+        self.close()?;
+        Ok(stm)
+    }
+
+    fn exp(&mut self) -> Result<Exp, Error> {
+        let keyword = self.open(&["CONST", "NAME", "TEMP", "BINOP", "MEM", "CALL", "ESEQ"])?;
+        let exp = match keyword.as_str() {
+        | "CONST" => Exp::Const(self.int()?),
+        | "NAME"  => Exp::Name(self.label()?),
+        | "TEMP"  => Exp::Temp(self.temp()?),
+        | "BINOP" => {
+            let lhs = self.exp()?;
+            let op = self.binop()?;
+            Exp::Binop(Box::new(lhs), op, Box::new(self.exp()?))
+        },
+        | "MEM"   => Exp::Mem(Box::new(self.exp()?)),
+        | "CALL"  => {
+            let name = self.exp()?;
+            let mut args = Vec::new();
+            while self.peek() == Some(&Token::LParen) {
+                args.push(self.exp()?);
+            }
+            Exp::Call(Box::new(name), args)
+        },
+        | "ESEQ"  => Exp::ESeq(Box::new(self.stm()?), Box::new(self.exp()?)),
+        | _       => unreachable!(),
+        };
+        self.close()?;
+        Ok(exp)
+    }
+
+    fn binop(&mut self) -> Result<Binop, Error> {
+        let op = match self.peek() {
+        | Some(Token::Atom(op)) => match op.as_str() {
+            | "ADD"  => Binop::Add,
+            | "SUB"  => Binop::Sub,
+            | "MUL"  => Binop::Mul,
+            | "DIV"  => Binop::Div,
+            | "MOD"  => Binop::Mod,
+            | "LAND" => Binop::And,
+            | "LOR"  => Binop::Or,
+            | "XOR"  => Binop::XOr,
+            | _      => return Err(self.error(&["binary operator"])),
+        },
+        | _ => return Err(self.error(&["binary operator"])),
+        };
+        self.next += 1;
+        Ok(op)
+    }
+
+    fn relop(&mut self) -> Result<Relop, Error> {
+        let op = match self.peek() {
+        | Some(Token::Atom(op)) => match op.as_str() {
+            | "EQ" => Relop::Eq,
+            | "NE" => Relop::Ne,
+            | "LT" => Relop::Lt,
+            | "GT" => Relop::Gt,
+            | "LE" => Relop::Le,
+            | "GE" => Relop::Ge,
+            | _    => return Err(self.error(&["relational operator"])),
+        },
+        | _ => return Err(self.error(&["relational operator"])),
+        };
+        self.next += 1;
+        Ok(op)
+    }
+}
@@ -61,7 +61,10 @@ fn clean(function: Function) -> Function {
     for stm in &function.body {
         match stm {
         | Stm::Jump(Exp::Name(label), _) => { used.insert(*label); },
-        | Stm::CJump(_, _, _, label, _)  => { used.insert(*label); },
+        | Stm::CJump(_, _, _, t_label, f_label) => {
+            used.insert(*t_label);
+            used.insert(*f_label);
+        },
         | _ => (),
         }
     }
@@ -54,7 +54,7 @@ pub fn translate_field_var(rec_exp: ir::Tree, index: usize) -> ir::Tree {
             ir::Exp::Binop(
                 Box::new(rec_exp.into()),
                 ir::Binop::Add,
-                Box::new(ir::Exp::Const(index as i32 * WORD_SIZE)),
+                Box::new(ir::Exp::Const(index as i64 * WORD_SIZE as i64)),
             )
         )
     ).into()
@@ -67,7 +67,7 @@ pub fn translate_index_var(array_exp: ir::Tree, index_exp: ir::Tree) -> ir::Tree
     let offset_exp = ir::Exp::Binop(
         Box::new(index_exp.into()),
         ir::Binop::Mul,
-        Box::new(ir::Exp::Const(WORD_SIZE)),
+        Box::new(ir::Exp::Const(WORD_SIZE as i64)),
     );
 
     // Calculate memory address offset from array pointer
@@ -106,7 +106,7 @@ pub fn translate_var(frames: &[Frame], name: &Symbol) -> ir::Tree {
     translate_simple_var(frames, name)
 }
 
-pub fn translate_int(n: i32) -> ir::Tree {
+pub fn translate_int(n: i64) -> ir::Tree {
     ir::Exp::Const(n).into()
 }
 
@@ -123,7 +123,26 @@ pub fn translate_str(data: &mut Vec<ir::Data>, string: Symbol) -> ir::Tree {
     ir::Exp::Name(label).into()
 }
 
-pub fn translate_call(binding: &Binding, arg_exps: Vec<ir::Tree>) -> ir::Tree {
+pub fn translate_static_link(frames: &[Frame], parent: Label) -> ir::Exp {
+
+    // Start off at current frame's base pointer
+    let rbp = ir::Exp::Temp(Temp::Reg(Reg::RBP));
+    let link = store("STATIC_LINK");
+
+    // Follow static links up to the frame enclosing the callee
+    frames.iter()
+        .rev()
+        .fold_while(rbp, |acc, frame| {
+            if frame.label() == parent {
+                Done(acc)
+            } else {
+                Continue(frame.get(link, acc))
+            }
+        })
+        .into_inner()
+}
+
+pub fn translate_call(frames: &[Frame], parent: Option<Label>, binding: &Binding, arg_exps: Vec<ir::Tree>) -> ir::Tree {
 
     let mut arg_exps = arg_exps.into_iter()
         .map(|arg_exp| arg_exp.into())
@@ -132,7 +151,8 @@ pub fn translate_call(binding: &Binding, arg_exps: Vec<ir::Tree>) -> ir::Tree {
     let label = match binding {
     | Binding::Ext(_, _, label) => label,
     | Binding::Fun(_, _, label) => {
-        arg_exps.insert(0, ir::Exp::Temp(Temp::from_reg(Reg::RBP)).into());
+        let parent = parent.expect("Internal error: missing parent frame");
+        arg_exps.insert(0, translate_static_link(frames, parent));
         label
     },
     | _ => panic!("Internal error: call of non-function"),
@@ -227,10 +247,40 @@ pub fn translate_bin(lhs_exp: ir::Tree, op: Binop, rhs_exp: ir::Tree) -> ir::Tre
 
 }
 
+pub fn translate_str_bin(lhs_exp: ir::Tree, op: Binop, rhs_exp: ir::Tree) -> ir::Tree {
+
+    let string_compare = Label::from_fixed("string_compare");
+    let relop = translate_relop(&op)
+        .expect("Internal error: non-comparison string binop");
+
+    // Compare contents in the runtime instead of pointers
+    let compare_exp = ir::Exp::Call(
+        Box::new(ir::Exp::Name(string_compare)),
+        vec![
+            lhs_exp.into(),
+            rhs_exp.into(),
+        ],
+    );
+
+    // Conditional operation on sign of result
+    ir::Tree::Cx(
+        Box::new(move |t, f| {
+            ir::Stm::CJump(
+                compare_exp.clone(),
+                relop,
+                ir::Exp::Const(0),
+                t,
+                f
+            )
+        })
+    )
+
+}
+
 pub fn translate_rec(fields_exp: Vec<ir::Tree>) -> ir::Tree {
 
     // Calculate record size for malloc
-    let size = ir::Exp::Const(WORD_SIZE * fields_exp.len() as i32);
+    let size = ir::Exp::Const(WORD_SIZE as i64 * fields_exp.len() as i64);
 
     // Retrieve malloc label
     // TODO: is it okay to hard-code this?
@@ -260,7 +310,7 @@ pub fn translate_rec(fields_exp: Vec<ir::Tree>) -> ir::Tree {
                         ir::Exp::Binop(
                             Box::new(ir::Exp::Temp(pointer)),
                             ir::Binop::Add,
-                            Box::new(ir::Exp::Const(WORD_SIZE * i as i32)),
+                            Box::new(ir::Exp::Const(WORD_SIZE as i64 * i as i64)),
                         )
                     )
                 ),
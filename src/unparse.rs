@@ -0,0 +1,240 @@
+use ast::*;
+use util::escape;
+
+/// Grammar position an expression is printed in, from loosest to tightest.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+
+    /// Any statement, including an [if] without an [else].
+    Open,
+
+    /// Statement that can be followed by an [else].
+    Closed,
+
+    /// Expression that isn't a statement.
+    Exp,
+
+    /// Operand of a binary operator, binding at least as tightly as the given precedence.
+    /// Literal ints are at the level above multiplicative operators.
+    Binary(usize),
+
+    /// Operand of a negation, which can't be a non-negative literal int.
+    Negated,
+}
+
+/// Level of a literal int, which binds tighter than any operator but can't be negated.
+const ATOM: Level = Level::Binary(5);
+
+/// Prints [exp] as Tiger source that parses back to the same tree, up to spans.
+///
+/// Parentheses are only added where the tree couldn't have come from the parser,
+/// such as a lower-precedence operator as an operand, and they reparse as a [Seq].
+/// Likewise, adjacent function or type declarations reparse as a single group.
+pub fn unparse(exp: &Exp) -> String {
+    let mut unparser = Unparser { out: String::new(), level: 0 };
+    unparser.exp(exp, Level::Open);
+    unparser.out.push('\n');
+    unparser.out
+}
+
+struct Unparser {
+    out: String,
+    level: usize,
+}
+
+/// Tightest position that [exp] can be printed in without parentheses.
+fn level(exp: &Exp) -> Level {
+    match exp {
+    | Exp::If{or: None, ..}             => Level::Open,
+    | Exp::If{or: Some(or), ..}         => if level(or) == Level::Open { Level::Open } else { Level::Exp },
+    | Exp::While{body, ..}
+    | Exp::For{body, ..}                => if level(body) == Level::Open { Level::Open } else { Level::Closed },
+    | Exp::Ass{..}                      => Level::Closed,
+    | Exp::Let{..}
+    | Exp::Arr{..}                      => Level::Exp,
+    | Exp::Bin{op, ..}                  => Level::Binary(op.precedence()),
+    | Exp::Int(n, _) if *n >= 0         => ATOM,
+    | _                                 => Level::Negated,
+    }
+}
+
+impl Unparser {
+
+    fn push(&mut self, text: &str) {
+        self.out.push_str(text);
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+        self.out.push_str(&"    ".repeat(self.level));
+    }
+
+    /// Prints each of [items] with [print], separated by [sep].
+    fn list<T, F>(&mut self, items: &[T], sep: &str, mut print: F) where F: FnMut(&mut Self, &T) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 { self.push(sep); }
+            print(self, item);
+        }
+    }
+
+    /// Prints [exp] in [position], parenthesizing it if it doesn't fit there.
+    fn exp(&mut self, exp: &Exp, position: Level) {
+        if level(exp) < position {
+            self.push("(");
+            self.exp(exp, Level::Open);
+            self.push(")");
+            return
+        }
+
+        match exp {
+        | Exp::Break(_)    => self.push("break"),
+        | Exp::Nil(_)      => self.push("nil"),
+        | Exp::Var(var, _) => self.var(var),
+        | Exp::Int(n, _)   => self.push(&n.to_string()),
+        | Exp::Str(s, _)   => self.push(&format!("\"{}\"", escape(s))),
+        | Exp::Call{name, args, ..} => {
+            self.push(&format!("{}(", name));
+            self.list(args, ", ", |unparser, arg| unparser.exp(arg, Level::Exp));
+            self.push(")");
+        },
+        | Exp::Neg(exp, _) => {
+            self.push("-");
+            self.exp(exp, Level::Negated);
+        },
+        | Exp::Bin{lhs, op, rhs, ..} => {
+            self.exp(lhs, Level::Binary(op.precedence()));
+            self.push(&format!(" {} ", op.symbol()));
+            self.exp(rhs, Level::Binary(op.precedence() + 1));
+        },
+        | Exp::Rec{name, fields, ..} => {
+            self.push(&format!("{} {{", name));
+            self.list(fields, ", ", |unparser, field| {
+                unparser.push(&format!("{} = ", field.name));
+                unparser.exp(&field.exp, Level::Exp);
+            });
+            self.push("}");
+        },
+        | Exp::Seq(exps, _) => {
+            self.push("(");
+            self.list(exps, "; ", |unparser, exp| unparser.exp(exp, Level::Open));
+            self.push(")");
+        },
+        | Exp::Ass{name, exp, ..} => {
+            self.var(name);
+            self.push(" := ");
+            self.exp(exp, Level::Exp);
+        },
+        | Exp::If{guard, then, or, ..} => {
+            self.push("if ");
+            self.exp(guard, Level::Exp);
+            self.push(" then ");
+            match or {
+            | None => self.exp(then, Level::Open),
+            | Some(or) => {
+                self.exp(then, Level::Closed);
+                self.push(" else ");
+                self.exp(or, Level::Open);
+            },
+            }
+        },
+        | Exp::While{guard, body, ..} => {
+            self.push("while ");
+            self.exp(guard, Level::Exp);
+            self.push(" do ");
+            self.exp(body, Level::Open);
+        },
+        | Exp::For{name, lo, hi, body, ..} => {
+            self.push(&format!("for {} := ", name));
+            self.exp(lo, Level::Exp);
+            self.push(" to ");
+            self.exp(hi, Level::Exp);
+            self.push(" do ");
+            self.exp(body, Level::Open);
+        },
+        | Exp::Let{decs, body, ..} => {
+            self.push("let");
+            self.level += 1;
+            for dec in decs { self.dec(dec); }
+            self.level -= 1;
+            self.newline();
+            self.push("in");
+            self.level += 1;
+            match &**body {
+            | Exp::Seq(exps, _) => for (i, exp) in exps.iter().enumerate() {
+                if i > 0 { self.push(";"); }
+                self.newline();
+                self.exp(exp, Level::Open);
+            },
+            | body => {
+                self.newline();
+                self.exp(body, Level::Open);
+            },
+            }
+            self.level -= 1;
+            self.newline();
+            self.push("end");
+        },
+        | Exp::Arr{name, size, init, ..} => {
+            self.push(&format!("{}[", name));
+            self.exp(size, Level::Exp);
+            self.push("] of ");
+            self.exp(init, Level::Binary(0));
+        },
+        }
+    }
+
+    fn var(&mut self, var: &Var) {
+        match var {
+        | Var::Simple(name, _) => self.push(&name.to_string()),
+        | Var::Field(var, field, _, _) => {
+            self.var(var);
+            self.push(&format!(".{}", field));
+        },
+        | Var::Index(var, index, _) => {
+            self.var(var);
+            self.push("[");
+            self.exp(index, Level::Exp);
+            self.push("]");
+        },
+        }
+    }
+
+    /// Prints [dec] on its own line, or one line per function or type in a group.
+    fn dec(&mut self, dec: &Dec) {
+        match dec {
+        | Dec::Fun(funs, _) => for fun in funs {
+            self.newline();
+            self.push(&format!("function {}(", fun.name));
+            self.list(&fun.args, ", ", Self::field_dec);
+            self.push(")");
+            if let Some(rets) = fun.rets { self.push(&format!(": {}", rets)); }
+            self.push(" = ");
+            self.exp(&fun.body, Level::Open);
+        },
+        | Dec::Var{name, ty, init, ..} => {
+            self.newline();
+            self.push(&format!("var {}", name));
+            if let Some(ty) = ty { self.push(&format!(": {}", ty)); }
+            self.push(" := ");
+            self.exp(init, Level::Exp);
+        },
+        | Dec::Type(types, _) => for dec in types {
+            self.newline();
+            self.push(&format!("type {} = ", dec.name));
+            match &dec.ty {
+            | Type::Name(name, _)    => self.push(&name.to_string()),
+            | Type::Arr(name, _, _) => self.push(&format!("array of {}", name)),
+            | Type::Rec(fields, _)   => {
+                self.push("{");
+                self.list(fields, ", ", Self::field_dec);
+                self.push("}");
+            },
+            }
+        },
+        }
+    }
+
+    fn field_dec(&mut self, field: &FieldDec) {
+        self.push(&format!("{}: {}", field.name, field.ty));
+    }
+}
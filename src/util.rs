@@ -1,4 +1,5 @@
 use std::fmt;
+use std::fmt::Write;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Void {}
@@ -9,6 +10,22 @@ impl fmt::Display for Void {
     }
 }
 
+/// Escapes a string whose chars each stand for a single byte back into Tiger syntax.
+pub fn escape(string: &str) -> String {
+    let mut escaped = String::new();
+    for c in string.chars() {
+        match c {
+        | '\n'        => escaped.push_str("\\n"),
+        | '\t'        => escaped.push_str("\\t"),
+        | '"'         => escaped.push_str("\\\""),
+        | '\\'        => escaped.push_str("\\\\"),
+        | ' ' ..= '~' => escaped.push(c),
+        | _           => write!(escaped, "\\{:03}", c as u32).expect("Internal error: IO"),
+        }
+    }
+    escaped
+}
+
 #[macro_use]
 macro_rules! hashmap {
     ( $( $key:expr => $value:expr ),* ) => {
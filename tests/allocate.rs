@@ -0,0 +1,39 @@
+extern crate codespan;
+extern crate tigerc;
+
+use codespan::{CodeMap, FileName};
+
+use tigerc::asm::{Asm, Binary};
+use tigerc::assemble::{allocate, tile, Color};
+use tigerc::ir::Form;
+use tigerc::operand::Reg;
+use tigerc::translate::read_verified;
+
+/// Five values that live across calls fit in the five callee-saved registers
+/// the prologue saves, so none of them has to be spilled
+#[test]
+fn test_color_callee_saved() {
+    let source = "
+        g (ESCAPES 0)
+            (MOVE (CALL (NAME f) (CONST 0)) (TEMP TEMP_A_0))
+            (MOVE (CALL (NAME f) (TEMP TEMP_A_0)) (TEMP TEMP_B_0))
+            (MOVE (CALL (NAME f) (TEMP TEMP_B_0)) (TEMP TEMP_C_0))
+            (MOVE (CALL (NAME f) (TEMP TEMP_C_0)) (TEMP TEMP_D_0))
+            (MOVE (CALL (NAME f) (TEMP TEMP_D_0)) (TEMP TEMP_E_0))
+            (MOVE (CALL (NAME f) (TEMP TEMP_E_0)) (TEMP TEMP_F_0))
+            (MOVE (BINOP (TEMP TEMP_F_0) ADD (BINOP (TEMP TEMP_A_0) ADD (BINOP (TEMP TEMP_B_0) ADD (BINOP (TEMP TEMP_C_0) ADD (BINOP (TEMP TEMP_D_0) ADD (TEMP TEMP_E_0)))))) (TEMP TEMP_RAX))
+    ";
+
+    let mut code = CodeMap::new();
+    let file = code.add_filemap(FileName::virtual_("callee_saved"), source.to_string());
+    let unit = read_verified(file, Form::Reordered).unwrap();
+    let unit = allocate::<Color>(tile(unit));
+    let body = &unit.functions[0].body;
+
+    for reg in &Reg::CALLEE_SAVED {
+        assert!(
+            body.contains(&Asm::Mov(Binary::RR(Reg::RAX, *reg))),
+            "no call result is kept in {}", reg,
+        );
+    }
+}
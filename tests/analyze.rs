@@ -0,0 +1,146 @@
+extern crate codespan;
+extern crate simple_symbol;
+extern crate tigerc;
+
+use codespan::{CodeMap, FileName};
+use simple_symbol::store;
+
+use tigerc::analyze::available::available_expressions;
+use tigerc::analyze::constant::{constant_propagation, Constant};
+use tigerc::analyze::flow::Flow;
+use tigerc::analyze::reaching::{reaching_definitions, Definition};
+use tigerc::ir::{Binop, Exp};
+use tigerc::operand::{Label, Temp};
+use tigerc::translate;
+
+/// Branches on Y, assigning X and A only on the false branch, then joins.
+const DIAMOND: &str = "main
+    (MOVE (CONST 1) (TEMP TEMP_X_0))
+    (MOVE (CONST 3) (TEMP TEMP_Z_2))
+    (MOVE (BINOP (TEMP TEMP_A_3) ADD (TEMP TEMP_B_4)) (TEMP TEMP_C_5))
+    (CJUMP (TEMP TEMP_Y_1) EQ (CONST 0) T_0 F_1)
+    (LABEL F_1)
+    (MOVE (CONST 2) (TEMP TEMP_X_0))
+    (MOVE (CONST 0) (TEMP TEMP_A_3))
+    (JUMP (NAME J_2))
+    (LABEL T_0)
+    (JUMP (NAME J_2))
+    (LABEL J_2)
+    (MOVE (TEMP TEMP_X_0) (TEMP TEMP_RAX))";
+
+/// Flow graph of the only function in [source]
+fn flow(source: &str) -> Flow {
+    let mut code = CodeMap::new();
+    let file = code.add_filemap(FileName::virtual_("<case>"), source.to_string());
+    let mut unit = translate::read(file).unwrap();
+    let function = unit.functions.remove(0);
+    Flow::new(function.label, function.body)
+}
+
+fn label(name: &'static str, id: usize) -> Label {
+    Label::Unfixed { id, name: store(name) }
+}
+
+fn temp(name: &'static str, id: usize) -> Temp {
+    Temp::Temp { id, name: store(name) }
+}
+
+#[test]
+fn test_reaching() {
+    let flow = flow(DIAMOND);
+    let solution = reaching_definitions(&flow);
+    let start = flow.start();
+    let (t, f, j) = (label("T", 0), label("F", 1), label("J", 2));
+
+    let x_start = Definition { block: start, index: 0 };
+    let x_false = Definition { block: f, index: 1 };
+
+    assert!(solution.input(t).contains(&x_start));
+    assert!(!solution.output(f).contains(&x_start));
+    assert!(solution.output(f).contains(&x_false));
+    assert!(solution.input(j).contains(&x_start));
+    assert!(solution.input(j).contains(&x_false));
+    assert!(solution.input(start).is_empty());
+}
+
+#[test]
+fn test_available() {
+    let flow = flow(DIAMOND);
+    let solution = available_expressions(&flow);
+    let (t, f, j) = (label("T", 0), label("F", 1), label("J", 2));
+
+    let sum = Exp::Binop(
+        Box::new(Exp::Temp(temp("A", 3))),
+        Binop::Add,
+        Box::new(Exp::Temp(temp("B", 4))),
+    );
+
+    assert!(solution.input(t).contains(&sum));
+    assert!(solution.input(f).contains(&sum));
+    assert!(!solution.output(f).contains(&sum));
+    assert!(!solution.input(j).contains(&sum));
+}
+
+#[test]
+fn test_constant() {
+    let flow = flow(DIAMOND);
+    let solution = constant_propagation(&flow);
+    let (t, f, j) = (label("T", 0), label("F", 1), label("J", 2));
+
+    let (x, z) = (temp("X", 0), temp("Z", 2));
+
+    assert_eq!(solution.output(t).get(&x), Some(&Constant::Const(1)));
+    assert_eq!(solution.output(f).get(&x), Some(&Constant::Const(2)));
+    assert_eq!(solution.input(j).get(&x), Some(&Constant::Varying));
+    assert_eq!(solution.input(j).get(&z), Some(&Constant::Const(3)));
+    assert_eq!(solution.input(j).get(&temp("C", 5)), Some(&Constant::Varying));
+}
+
+/// Loops reach a fixed point that accounts for the back edge
+#[test]
+fn test_loop() {
+    let flow = flow("main
+    (MOVE (CONST 0) (TEMP TEMP_I_0))
+    (MOVE (CONST 5) (TEMP TEMP_N_1))
+    (JUMP (NAME L_0))
+    (LABEL L_0)
+    (MOVE (BINOP (TEMP TEMP_I_0) ADD (CONST 1)) (TEMP TEMP_I_0))
+    (CJUMP (TEMP TEMP_I_0) LT (TEMP TEMP_N_1) L_0 D_1)
+    (LABEL D_1)
+    (MOVE (TEMP TEMP_I_0) (TEMP TEMP_RAX))");
+
+    let (l, d) = (label("L", 0), label("D", 1));
+    let (i, n) = (temp("I", 0), temp("N", 1));
+
+    let constants = constant_propagation(&flow);
+    assert_eq!(constants.input(l).get(&i), Some(&Constant::Varying));
+    assert_eq!(constants.input(l).get(&n), Some(&Constant::Const(5)));
+
+    let reaching = reaching_definitions(&flow);
+    assert!(reaching.input(l).contains(&Definition { block: l, index: 1 }));
+    assert!(reaching.input(d).contains(&Definition { block: l, index: 1 }));
+    assert!(!reaching.input(d).contains(&Definition { block: flow.start(), index: 0 }));
+}
+
+/// Labels reached by falling through still start their own blocks
+#[test]
+fn test_fall_through() {
+    let flow = flow("main
+    (MOVE (CONST 1) (TEMP TEMP_X_0))
+    (LABEL L_0)
+    (MOVE (BINOP (TEMP TEMP_X_0) ADD (CONST 1)) (TEMP TEMP_X_0))
+    (CJUMP (TEMP TEMP_X_0) LT (CONST 5) L_0 D_1)
+    (LABEL D_1)
+    (MOVE (TEMP TEMP_X_0) (TEMP TEMP_RAX))");
+
+    let (l, d) = (label("L", 0), label("D", 1));
+    let x = temp("X", 0);
+
+    assert!(flow.graph().contains_edge(flow.start(), l));
+    assert!(flow.graph().contains_edge(l, l));
+    assert!(flow.graph().contains_edge(l, d));
+
+    let constants = constant_propagation(&flow);
+    assert_eq!(constants.output(flow.start()).get(&x), Some(&Constant::Const(1)));
+    assert_eq!(constants.input(l).get(&x), Some(&Constant::Varying));
+}
@@ -0,0 +1,70 @@
+extern crate tigerc;
+
+#[macro_use]
+mod util;
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use tigerc::json::Json;
+use util::*;
+
+/// Test-generating macro.
+/// [$name] is the name of the generated test function
+/// [$file] is the name of the test file, without extensions
+/// [$flag] is the flag for the phase whose output is checked
+/// [$ext] is the extension of the phase's text output
+macro_rules! emit {
+    ($name:ident, $file:expr, $flag:expr, $ext:expr) => {
+        #[test]
+        pub fn $name() {
+            let Unit { file, solution, actual } = get_unit(
+                $file,
+                "emit",
+                concat!($ext, ".jsonsol"),
+                concat!($ext, ".json"),
+            );
+            run(concat!("--emit-format=json ", $flag), &file);
+            assert_eq!(read_json(&solution), read_json(&actual), "JSON for {} differs", $file);
+        }
+    }
+}
+
+fn read_json(path: &PathBuf) -> Json {
+    Json::from_str(&read_to_string(path)).unwrap()
+}
+
+fn kinds<'a>(values: &'a Json, key: &str) -> Vec<&'a str> {
+    values.get(key).and_then(Json::as_arr).unwrap().iter()
+        .map(|value| value.get("kind").and_then(Json::as_str).unwrap())
+        .collect()
+}
+
+emit!(test_lexed, "emit_03", "-l", "lexed");
+emit!(test_parsed, "emit_01", "-p", "parsed");
+emit!(test_errors, "emit_02", "-t", "typed");
+
+/// Later phases are checked by shape, since labels and temporaries are numbered globally
+#[test]
+fn test_backend() {
+    let Unit { file, .. } = get_unit("emit_01", "emit", "", "");
+    run("--emit-format=json --canonize --tile", &file);
+
+    let ir = read_json(&file.with_extension("canonized.json"));
+    let functions = ir.get("ir").and_then(|ir| ir.get("functions")).and_then(Json::as_arr).unwrap();
+    assert_eq!(functions.len(), 2);
+    for function in functions {
+        assert!(kinds(function, "body").iter().all(|kind| kind != &"Seq"), "canonized IR is not flat");
+    }
+
+    let asm = read_json(&file.with_extension("tiled.json"));
+    let functions = asm.get("asm").and_then(|asm| asm.get("functions")).and_then(Json::as_arr).unwrap();
+    assert_eq!(functions.len(), 2);
+    for function in functions {
+        assert!(kinds(function, "body").contains(&"Instruction"));
+    }
+
+    // The assembly itself is still written as text
+    assert!(file.with_extension("s").exists());
+    assert!(read_json(&file.with_extension("s.json")).get("asm").is_some());
+}
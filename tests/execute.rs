@@ -0,0 +1,64 @@
+#[macro_use]
+mod util;
+
+use util::*;
+
+/// Flags for programs fast enough to interpret
+const GOOD: [&str; 10] = [
+    "--regalloc=trivial",
+    "--regalloc=color",
+    "--regalloc=linear",
+    "--interpret-ir=translate",
+    "--interpret-ir=canonize",
+    "--interpret-ir=fold",
+    "--interpret-ir=reorder",
+    "--interpret-asm=abstract",
+    "--interpret-asm=assembly --regalloc=color",
+    "--interpret-asm=assembly --regalloc=linear",
+];
+
+/// Flags for programs that only run compiled
+const NATIVE: [&str; 3] = [
+    "--regalloc=trivial",
+    "--regalloc=color",
+    "--regalloc=linear",
+];
+
+generate_execute!(good, "execute", GOOD, "outsol", "out");
+generate_execute!(example, "../examples", GOOD, "outsol", "out");
+generate_execute!(native_example, "../examples", NATIVE, "outsol", "out");
+
+generate_execute!(good64, "execute", ["--int64", "--int64 --regalloc=color"], "outsol", "out");
+
+example!(test_escape, "escape");
+example!(test_factorial, "factorial");
+native_example!(test_fibonacci, "fibonacci");
+example!(test_hailstones, "hailstones");
+
+good!(test_captured, "captured");
+
+good!(test_records, "records");
+good!(test_stores, "stores");
+good!(test_links, "links");
+good!(test_nested, "nested");
+good!(test_locals, "locals");
+good!(test_exit, "exit");
+good!(test_divide, "divide");
+good!(test_queens, "queens");
+good!(test_recursion, "recursion");
+
+good!(test_builtins, "builtins");
+good!(test_substring, "substring");
+good!(test_chr, "chr");
+
+good!(test_strings, "strings");
+good!(test_lengths, "lengths");
+good!(test_escapes, "escapes");
+good!(test_merge, "merge");
+good!(test_conditions, "conditions");
+good!(test_effects, "effects");
+good!(test_constants, "constants");
+
+good!(test_integers, "integers");
+good64!(test_integers64, "integers64");
+good64!(test_stores64, "stores64");
@@ -0,0 +1,85 @@
+#[macro_use]
+mod util;
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use util::*;
+
+/// Test-generating macro.
+/// [$name] is the name of the generated test function
+/// [$file] is the name of the test file, without extensions
+/// [$flags] are the space-separated command line flags to pass to the formatter
+macro_rules! formats {
+    ($name:ident, $file:expr) => {
+        formats!($name, $file, "");
+    };
+    ($name:ident, $file:expr, $flags:expr) => {
+        #[test]
+        pub fn $name() {
+            check_format($file, $flags);
+        }
+    };
+}
+
+/// Run the formatter with the given space-separated flags
+fn tigerfmt(args: &str, file: &PathBuf) -> Output {
+    Command::new("target/debug/tigerfmt")
+        .args(args.split_whitespace())
+        .arg(file)
+        .output()
+        .unwrap()
+}
+
+/// Format the test program, compare it against the solution,
+/// and check that the solution is already formatted
+fn check_format(name: &str, flags: &str) {
+    let Unit { file, solution, actual } = get_unit(name, "format", "fmtsol", "fmt");
+    let output = tigerfmt(flags, &file);
+    assert!(output.status.success(), "failed to format {}", name);
+
+    File::create(&actual).unwrap().write_all(&output.stdout).unwrap();
+    assert_eq!(read_to_string(&solution), read_to_string(&actual), "formatting of {} differs", name);
+
+    let check = tigerfmt(&format!("--check {}", flags), &solution);
+    assert!(check.status.success(), "solution for {} is not formatted", name);
+}
+
+formats!(test_comments, "format_01");
+formats!(test_width, "format_02");
+formats!(test_indent, "format_03", "--indent 2 --width 40");
+formats!(test_empty, "format_04");
+
+/// Every program the compiler accepts should format without panicking,
+/// and formatting it again should change nothing
+#[test]
+fn test_corpus() {
+    for directory in &["parse", "type", "execute", "lsp"] {
+        let mut files = fs::read_dir(PathBuf::from("tests").join(directory)).unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "tig"))
+            .collect::<Vec<_>>();
+        files.sort();
+
+        for file in files {
+            let output = tigerfmt("--int64", &file);
+            assert_ne!(output.status.code(), Some(101), "formatter panicked on {}", file.display());
+            if !output.status.success() { continue }
+
+            let actual = file.with_extension("fmt");
+            File::create(&actual).unwrap().write_all(&output.stdout).unwrap();
+            let check = tigerfmt("--int64 --check", &actual);
+            assert!(check.status.success(), "formatting {} is not idempotent", file.display());
+        }
+    }
+}
+
+#[test]
+fn test_check() {
+    let Unit { file, .. } = get_unit("format_02", "format", "fmtsol", "fmt");
+    let output = tigerfmt("--check", &file);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stdout).unwrap().contains("is not formatted"));
+}
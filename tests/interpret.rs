@@ -0,0 +1,40 @@
+extern crate simple_symbol;
+extern crate tigerc;
+
+use simple_symbol::store;
+
+use tigerc::asm::{Asm, Binary, Function, Unit};
+use tigerc::interpret::interpret_asm;
+use tigerc::operand::{Imm, Label, Reg};
+
+/// Exit code of a program that keeps 5 in [reg] across a call to an empty function
+fn exit_after_call(reg: Reg) -> i32 {
+    let (main, empty, exit) = (Label::from_fixed("main"), Label::from_fixed("empty"), Label::from_fixed("__exit__"));
+    let function = |body| Function { body, stack_info: (0, store("sub"), store("add")) };
+    let unit = Unit {
+        data: Vec::new(),
+        functions: vec![
+            function(vec![Asm::Label(empty), Asm::Ret]),
+            function(vec![
+                Asm::Label(main),
+                Asm::Mov(Binary::IR(Imm::Int(5), reg)),
+                Asm::Call(empty, 0),
+                Asm::Mov(Binary::RR(reg, Reg::RDI)),
+                Asm::Call(exit, 1),
+                Asm::Ret,
+            ]),
+        ],
+    };
+    interpret_asm(&unit)
+}
+
+/// Calls to Tiger functions overwrite caller-saved registers, like calls into the runtime
+#[test]
+fn test_clobber_caller_saved() {
+    for reg in Reg::CALLER_SAVED.iter().filter(|reg| **reg != Reg::get_return()) {
+        assert_ne!(exit_after_call(*reg), 5, "{} survived a call", reg);
+    }
+    for reg in &Reg::CALLEE_SAVED {
+        assert_eq!(exit_after_call(*reg), 5, "{} was overwritten by a call", reg);
+    }
+}
@@ -0,0 +1,153 @@
+extern crate codespan;
+extern crate tigerc;
+
+#[macro_use]
+mod util;
+
+use std::env::current_dir;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use codespan::{CodeMap, FileName};
+
+use tigerc::ir;
+use tigerc::translate;
+use util::*;
+
+/// Test-generating macro.
+/// [$name] is the name of the generated test function
+/// [$file] is the name of the test file, without extensions
+/// [$flag] are the space-separated command line flags to compile the IR with
+/// [$ext] are the extensions of phase outputs to check against `[ext]sol` files
+macro_rules! ir {
+    ($name:ident, $file:expr, $flag:expr $(, $ext:expr)*) => {
+        #[test]
+        pub fn $name() {
+            let file = current_dir().unwrap().join("tests").join("ir").join($file).with_extension("ir");
+            run($flag, &file);
+            $(
+                assert_eq!(
+                    read_to_string(&file.with_extension(concat!($ext, "sol"))),
+                    read_to_string(&file.with_extension($ext)),
+                    "{} of {} differs", $ext, $file,
+                );
+            )*
+            let actual = file.with_extension("out");
+            execute(&file, &actual);
+            assert_eq!(read_to_string(&file.with_extension("outsol")), read_to_string(&actual), "output of {} differs", $file);
+        }
+    }
+}
+
+ir!(test_translated, "ir_01", "");
+ir!(test_fold, "ir_02", "--ir-stage=canonize --fold", "folded");
+ir!(test_reorder, "ir_03", "--ir-stage=fold --reorder", "reordered");
+ir!(test_canonize, "ir_04", "--canonize", "canonized");
+ir!(test_reordered, "ir_05", "--ir-stage=reorder");
+
+/// Malformed IR is reported instead of reaching tiling, which assumes it is well-formed
+#[test]
+fn test_malformed() {
+    let cases = vec![
+        ("bad_01", "jump to a label outside of the function"),
+        ("bad_02", "move to something other than a temporary or memory"),
+    ];
+
+    for (name, reason) in cases {
+        let file = current_dir().unwrap().join("tests").join("ir").join(name).with_extension("ir");
+        let _ = fs::remove_file(file.with_extension("s"));
+        let output = Command::new("target/debug/tigerc")
+            .arg("--ir-stage=reorder")
+            .arg(&file)
+            .output()
+            .unwrap();
+
+        assert_ne!(output.status.code(), Some(101), "compiler panicked on {}", name);
+        assert!(String::from_utf8(output.stdout).unwrap().contains(reason), "{} is not reported", name);
+        assert!(!file.with_extension("s").exists(), "{} was compiled", name);
+    }
+}
+
+fn read(name: &str, source: &str) -> Result<String, String> {
+    let mut code = CodeMap::new();
+    let file = code.add_filemap(FileName::virtual_(name.to_string()), source.to_string());
+    translate::read(file)
+        .map(|unit| unit.to_string())
+        .map_err(|err| err.to_debug(&code))
+}
+
+/// Canonized IR dumped for every execution test reads back to the same IR
+#[test]
+fn test_round_trip() {
+    let mut files = fs::read_dir(PathBuf::from("tests").join("execute")).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "tig"))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    // Some programs only type check with 64-bit integers
+    for file in &files {
+        run("--int64 --canonize", file);
+        let dump = read_to_string(&file.with_extension("canonized"));
+        let name = file.display().to_string();
+        let unit = read(&name, &dump).unwrap_or_else(|err| panic!("canonized {} does not read: {}", name, err));
+        assert_eq!(dump.trim_end(), unit.trim_end(), "canonized {} reads differently", name);
+    }
+
+    assert!(!files.is_empty());
+}
+
+/// Malformed IR is reported instead of read
+#[test]
+fn test_errors() {
+    let cases = vec![
+        "(DATA STRING_0 \"unterminated)",
+        "(DATA STRING_0 \"\\q\")",
+        "main\n    (MOVE (CONST 1))",
+        "main\n    (MOVE (CONST 1) (TEMP RAX))",
+        "main\n    (MOVE (BINOP (CONST 1) PLUS (CONST 2)) (TEMP TEMP_RAX))",
+        "main\n    (CJUMP (CONST 1) EQ (CONST 2) T_0)",
+        "main\n    (GOTO (NAME L_0))",
+        "main (ESCAPES -1)",
+    ];
+
+    for case in cases {
+        assert!(read("<case>", case).is_err(), "read malformed IR: {}", case);
+    }
+
+    assert!(read("<case>", "main (ESCAPES 0)\n    (MOVE (CONST -1) (TEMP TEMP_RAX))").is_ok());
+}
+
+/// Reasons that [source] isn't in [form]
+fn violations(source: &str, form: ir::Form) -> Vec<&'static str> {
+    let mut code = CodeMap::new();
+    let file = code.add_filemap(FileName::virtual_("<case>"), source.to_string());
+    let unit = translate::read(file).unwrap();
+    match ir::verify(&unit, form) {
+    | Ok(())          => Vec::new(),
+    | Err(violations) => violations.into_iter().map(|violation| violation.reason).collect(),
+    }
+}
+
+/// Each form of IR is checked for its own invariants
+#[test]
+fn test_verify() {
+    let nested = "main\n    (SEQ (MOVE (ESEQ (LABEL L_0) (CONST 1)) (TEMP TEMP_X_0)) (JUMP (NAME L_0)))";
+    assert!(violations(nested, ir::Form::Translated).is_empty());
+    assert_eq!(violations(nested, ir::Form::Canonical), vec!["sequence in canonical IR", "ESEQ in canonical IR"]);
+
+    let calls = "main\n    (MOVE (CALL (NAME f)) (TEMP TEMP_X_0))\n    (EXP (CALL (NAME f)))\n    (MOVE (CALL (NAME f)) (MEM (TEMP TEMP_X_0)))\n    (EXP (CALL (NAME f) (CALL (NAME g))))";
+    assert!(violations(calls, ir::Form::Translated).is_empty());
+    assert_eq!(violations(calls, ir::Form::Canonical).len(), 2);
+
+    let constant = "main\n    (MOVE (CONST 1) (CONST 2))";
+    assert_eq!(violations(constant, ir::Form::Translated), vec!["move to something other than a temporary or memory"]);
+
+    let missing = "main\n    (CJUMP (CONST 1) EQ (CONST 1) T_0 F_1)\n    (LABEL F_1)";
+    assert_eq!(violations(missing, ir::Form::Translated), vec!["jump to a label outside of the function"]);
+
+    let reordered = "main\n    (CJUMP (CONST 1) EQ (CONST 1) T_0 F_1)\n    (LABEL T_0)\n    (LABEL F_1)";
+    assert_eq!(violations(reordered, ir::Form::Canonical), vec!["label reached by falling through"]);
+    assert_eq!(violations(reordered, ir::Form::Reordered), vec!["false label does not follow conditional jump"]);
+}
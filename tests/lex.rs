@@ -60,3 +60,31 @@ good!(test_symbol_06, "symbol_06");
 good!(test_symbol_07, "symbol_07");
 good!(test_symbol_08, "symbol_08");
 good!(test_symbol_09, "symbol_09");
+
+good!(test_string_01, "string_01");
+good!(test_string_02, "string_02");
+good!(test_string_03, "string_03");
+good!(test_string_04, "string_04");
+good!(test_string_05, "string_05");
+
+bad!(test_bad_string_01, "bad_string_01");
+bad!(test_bad_string_02, "bad_string_02");
+bad!(test_bad_string_03, "bad_string_03");
+bad!(test_bad_string_04, "bad_string_04");
+bad!(test_bad_string_05, "bad_string_05");
+bad!(test_bad_string_06, "bad_string_06");
+
+good!(test_comment_01, "comment_01");
+good!(test_comment_02, "comment_02");
+good!(test_comment_03, "comment_03");
+good!(test_comment_04, "comment_04");
+
+bad!(test_bad_comment_01, "bad_comment_01");
+bad!(test_bad_comment_02, "bad_comment_02");
+bad!(test_bad_comment_03, "bad_comment_03");
+
+good!(test_int_01, "int_01");
+good!(test_int_02, "int_02");
+
+bad!(test_bad_int_01, "bad_int_01");
+bad!(test_bad_int_02, "bad_int_02");
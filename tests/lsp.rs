@@ -0,0 +1,119 @@
+extern crate tigerc;
+
+#[macro_use]
+mod util;
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+use tigerc::json::Json;
+use util::*;
+
+/// Test-generating macro.
+/// [$name] is the name of the generated test function
+/// [$file] is the name of the test file, without extensions
+macro_rules! session {
+    ($name:ident, $file:expr) => {
+        #[test]
+        pub fn $name() {
+            check_session($file);
+        }
+    }
+}
+
+/// Build a JSON-RPC message, with an [id] if it is a request
+fn message(id: Option<usize>, method: &str, params: Json) -> Json {
+    let mut members = vec![("jsonrpc", Json::from("2.0"))];
+    if let Some(id) = id { members.push(("id", id.into())); }
+    members.push(("method", method.into()));
+    members.push(("params", params));
+    Json::object(members)
+}
+
+/// Build a request from a line of the form `method [line character]`
+fn request(id: usize, uri: &str, line: &str) -> Json {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    let document = Json::object(vec![("uri", uri.into())]);
+    let mut params = vec![("textDocument", document)];
+
+    if words.len() == 3 {
+        params.push(("position", Json::object(vec![
+            ("line", Json::from_str(words[1]).unwrap()),
+            ("character", Json::from_str(words[2]).unwrap()),
+        ])));
+    }
+
+    if words[0] == "references" {
+        params.push(("context", Json::object(vec![("includeDeclaration", true.into())])));
+    }
+
+    let method = match words[0] {
+    | "symbols" => "textDocument/documentSymbol".to_string(),
+    | method    => format!("textDocument/{}", method),
+    };
+
+    message(Some(id), &method, Json::object(params))
+}
+
+/// Open the test program in the language server, send each request in the
+/// matching `.lspin` file, and check every message sent back
+fn check_session(name: &str) {
+    let Unit { file, solution, actual } = get_unit(name, "lsp", "lspsol", "lsp");
+    let uri = format!("file:///{}.tig", name);
+
+    let mut messages = vec![
+        message(Some(0), "initialize", Json::object(vec![])),
+        message(None, "initialized", Json::object(vec![])),
+        message(None, "textDocument/didOpen", Json::object(vec![
+            ("textDocument", Json::object(vec![
+                ("uri", uri.as_str().into()),
+                ("languageId", "tiger".into()),
+                ("version", Json::Int(1)),
+                ("text", read_to_string(&file).into()),
+            ])),
+        ])),
+    ];
+
+    let requests = read_to_string(&file.with_extension("lspin"));
+    for (i, line) in requests.lines().enumerate() {
+        messages.push(request(i + 1, &uri, line));
+    }
+
+    messages.push(message(Some(requests.lines().count() + 1), "shutdown", Json::Null));
+    messages.push(message(None, "exit", Json::Null));
+
+    let mut input = String::new();
+    for message in messages {
+        let body = message.to_string();
+        input.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+    }
+
+    let mut server = Command::new("target/debug/tiger-lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    server.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = server.wait_with_output().unwrap();
+    assert!(output.status.success(), "language server exited with {}", output.status);
+
+    // Print one message per line
+    let mut stdout = String::from_utf8(output.stdout).unwrap();
+    let mut replies = String::new();
+    while let Some(header) = stdout.find("\r\n\r\n") {
+        let length = usize::from_str(stdout[..header].trim_start_matches("Content-Length: ")).unwrap();
+        let start = header + 4;
+        replies.push_str(&stdout[start..start + length]);
+        replies.push('\n');
+        stdout = stdout[start + length..].to_string();
+    }
+
+    File::create(&actual).unwrap().write_all(replies.as_bytes()).unwrap();
+    assert_eq!(read_to_string(&solution), replies, "replies to {} differ", name);
+}
+
+session!(test_lsp_01, "lsp_01");
+session!(test_lsp_02, "lsp_02");
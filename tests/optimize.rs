@@ -0,0 +1,42 @@
+extern crate simple_symbol;
+extern crate tigerc;
+
+use simple_symbol::store;
+
+use tigerc::asm::{Asm, Binary, Function, Unit};
+use tigerc::operand::Reg;
+use tigerc::optimize::coalesce;
+
+/// Coalesce the moves in a single function with [body]
+fn coalesced(body: Vec<Asm<Reg>>) -> Vec<Asm<Reg>> {
+    let function = Function { body, stack_info: (0, store("main"), store("main")) };
+    let mut unit = coalesce(Unit { data: Vec::new(), functions: vec![function] });
+    unit.functions.remove(0).body
+}
+
+#[test]
+fn test_coalesce_dead() {
+    let body = vec![
+        Asm::Mov(Binary::RR(Reg::RDI, Reg::RCX)),
+        Asm::Mov(Binary::RR(Reg::RCX, Reg::RAX)),
+        Asm::Ret,
+    ];
+    assert_eq!(coalesced(body), vec![
+        Asm::Mov(Binary::RR(Reg::RDI, Reg::RAX)),
+        Asm::Ret,
+    ]);
+}
+
+/// The stack and base pointers are never tracked by liveness, so moves
+/// through them must be kept
+#[test]
+fn test_coalesce_untracked() {
+    for reg in &[Reg::RSP, Reg::RBP] {
+        let body = vec![
+            Asm::Mov(Binary::RR(Reg::RDI, *reg)),
+            Asm::Mov(Binary::RR(*reg, Reg::RAX)),
+            Asm::Ret,
+        ];
+        assert_eq!(coalesced(body.clone()), body);
+    }
+}
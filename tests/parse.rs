@@ -68,4 +68,17 @@ good!(test_comment_03, "comment_03");
 good!(test_comment_04, "comment_04");
 good!(test_comment_05, "comment_05");
 
+good!(test_int_01, "int_01");
+good!(test_int_02, "int_02");
+
+bad!(test_bad_int_01, "bad_int_01");
+bad!(test_bad_int_02, "bad_int_02");
+bad!(test_bad_int_03, "bad_int_03");
+
+bad!(test_bad_eof_01, "bad_eof_01");
+
+bad!(test_bad_recover_01, "bad_recover_01");
+bad!(test_bad_recover_02, "bad_recover_02");
+bad!(test_bad_recover_03, "bad_recover_03");
+
 bad!(test_bad_var_type_01, "bad_var_type_01");
@@ -9,8 +9,15 @@ generate!(good, "type", "-t", "typedsol", "typed", compare_content);
 
 generate!(bad, "type", "-t", "typedsol", "typed", compare_location);
 
+generate!(multiple, "type", "-t", "typedsol", "typed", compare_content);
+
+generate!(allow, "type", "-t --warnings=allow", "typedsol", "typed", compare_content);
+
+generate!(deny, "type", "-t --warnings=deny", "typedsol", "typed", compare_location);
+
 good!(test_queens, "queens");
 good!(test_merge, "merge");
+good!(test_cycle_01, "cycle_01");
 
 good!(test_appel_01, "appel_01");
 good!(test_appel_02, "appel_02");
@@ -62,3 +69,19 @@ bad!(test_appel_39, "appel_39");
 bad!(test_appel_40, "appel_40");
 bad!(test_appel_43, "appel_43");
 bad!(test_appel_45, "appel_45");
+
+multiple!(test_bad_multiple_01, "bad_multiple_01");
+multiple!(test_bad_multiple_02, "bad_multiple_02");
+multiple!(test_bad_multiple_03, "bad_multiple_03");
+bad!(test_bad_rich_01, "bad_rich_01");
+bad!(test_bad_rich_02, "bad_rich_02");
+bad!(test_bad_cycle_01, "bad_cycle_01");
+bad!(test_bad_cycle_02, "bad_cycle_02");
+bad!(test_bad_cycle_03, "bad_cycle_03");
+
+good!(test_lint_01, "lint_01");
+deny!(test_lint_02, "lint_02");
+allow!(test_lint_03, "lint_03");
+bad!(test_lint_04, "lint_04");
+good!(test_lint_05, "lint_05");
+good!(test_lint_06, "lint_06");
@@ -0,0 +1,94 @@
+extern crate codespan;
+extern crate tigerc;
+
+#[macro_use]
+mod util;
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+use codespan::{ByteIndex, CodeMap, FileName};
+
+use tigerc::ast::{Binop, Exp};
+use tigerc::config::Config;
+use tigerc::lex;
+use tigerc::parse;
+use tigerc::span::Span;
+use tigerc::unparse::unparse;
+use util::*;
+
+/// Lex and parse [source], or [None] if it isn't a valid program
+fn parse(name: String, source: String) -> Option<Exp> {
+    let mut code = CodeMap::new();
+    let file = code.add_filemap(FileName::virtual_(name), source);
+    let tokens = lex::lex(file, Config::default()).ok()?;
+    parse::parse(tokens, Config::default()).ok()
+}
+
+/// Every program under [tests/parse] should print to source that parses
+/// back to the same tree, which is compared by its debug dump since it omits spans
+#[test]
+fn test_round_trip() {
+    let mut files = fs::read_dir(PathBuf::from("tests").join("parse")).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "tig"))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut count = 0;
+    for file in files {
+        let name = file.display().to_string();
+        let ast = match parse(name.clone(), read_to_string(&file)) {
+        | Some(ast) => ast,
+        | None      => continue,
+        };
+
+        let source = unparse(&ast);
+        let actual = file.with_extension("unparsed");
+        File::create(&actual).unwrap().write_all(source.as_bytes()).unwrap();
+
+        let reparsed = parse(actual.display().to_string(), source)
+            .unwrap_or_else(|| panic!("unparsed {} does not parse", name));
+        assert_eq!(ast.to_string(), reparsed.to_string(), "unparsed {} parses differently", name);
+        count += 1;
+    }
+
+    assert!(count > 0);
+}
+
+fn span() -> Span {
+    Span::new(ByteIndex(0), ByteIndex(0))
+}
+
+fn int(n: i64) -> Box<Exp> {
+    Box::new(Exp::Int(n, span()))
+}
+
+fn bin(lhs: Box<Exp>, op: Binop, rhs: Box<Exp>) -> Box<Exp> {
+    Box::new(Exp::Bin { lhs, op, op_span: span(), rhs, span: span() })
+}
+
+fn cond(guard: Box<Exp>, then: Box<Exp>, or: Option<Box<Exp>>) -> Box<Exp> {
+    Box::new(Exp::If { guard, then, or, span: span() })
+}
+
+/// Trees that couldn't have come from the parser are parenthesized where needed
+#[test]
+fn test_parenthesize() {
+    let cases = vec![
+        (bin(bin(int(1), Binop::Add, int(2)), Binop::Mul, int(3)), "(1 + 2) * 3"),
+        (bin(int(1), Binop::Sub, bin(int(2), Binop::Sub, int(3))), "1 - (2 - 3)"),
+        (bin(bin(int(1), Binop::Sub, int(2)), Binop::Sub, int(3)), "1 - 2 - 3"),
+        (bin(int(1), Binop::LOr, bin(int(2), Binop::LAnd, int(3))), "1 | 2 & 3"),
+        (Box::new(Exp::Neg(int(5), span())), "-(5)"),
+        (Box::new(Exp::Neg(int(-5), span())), "--5"),
+        (bin(cond(int(1), int(2), Some(int(3))), Binop::Add, int(4)), "(if 1 then 2 else 3) + 4"),
+        (cond(int(1), cond(int(2), int(3), None), Some(int(4))), "if 1 then (if 2 then 3) else 4"),
+        (cond(int(1), int(2), Some(cond(int(3), int(4), None))), "if 1 then 2 else if 3 then 4"),
+    ];
+
+    for (exp, expected) in cases {
+        assert_eq!(unparse(&exp), format!("{}\n", expected));
+    }
+}
@@ -3,9 +3,11 @@ extern crate regex;
 use std::env::current_dir;
 use std::fs::File;
 use std::io::prelude::*;
-use std::path::PathBuf;
-use std::process::Command;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::str::FromStr;
+use std::sync::Once;
 
 use self::regex::Regex;
 
@@ -13,7 +15,7 @@ use self::regex::Regex;
 ///
 /// [$macro]   is the name of the test generator
 /// [$dir]     is the subdirectory of [tests] to look for files in
-/// [$flag]    is the command line flag to pass to the compiler
+/// [$flag]    are the space-separated command line flags to pass to the compiler
 /// [$sol_ext] is the file extension of the solution file
 /// [$act_ext] is the file extension of the generated file
 /// [$compare] is the function used to compare the solution and generated files as Strings
@@ -28,13 +30,44 @@ macro_rules! generate {
                 pub fn $name() {
                     let Unit { file, solution, actual } = get_unit($file, $dir, $sol_ext, $act_ext);
                     run($flag, &file);
-                    $compare(solution, actual);
+                    assert!($compare(&solution, &actual), "{} differs from {}", actual.display(), solution.display());
                 }
             }
         }
     }
 }
 
+/// Generates an execution-test-generating macro.
+///
+/// [$macro]   is the name of the test generator
+/// [$dir]     is the subdirectory of [tests] to look for files in
+/// [$flags]   are the command line flags to compile the program with, one run each
+/// [$sol_ext] is the file extension of the expected output file
+/// [$act_ext] is the file extension of the actual output file
+macro_rules! generate_execute {
+    ($macro:ident, $dir:expr, $flags:expr, $sol_ext:expr, $act_ext:expr) => {
+        /// Test-generating macro.
+        /// [$name] is the name of the generated test function
+        /// [$file] is the name of the test file, without extensions
+        /// [$attr] is an optional attribute for the test, e.g. `ignore`
+        macro_rules! $macro {
+            ($name:ident, $file:expr) => {
+                #[test]
+                pub fn $name() {
+                    check_execution($file, $dir, &$flags, $sol_ext, $act_ext);
+                }
+            };
+            (#[$attr:meta] $name:ident, $file:expr) => {
+                #[test]
+                #[$attr]
+                pub fn $name() {
+                    check_execution($file, $dir, &$flags, $sol_ext, $act_ext);
+                }
+            };
+        }
+    }
+}
+
 /// Single test unit
 pub struct Unit {
 
@@ -65,28 +98,113 @@ pub fn get_unit(name: &str, directory: &str, sol_ext: &str, act_ext: &str) -> Un
     Unit { file, solution, actual }
 }
 
-/// Run the compiler with the given flag
-pub fn run(arg: &str, file: &PathBuf) {
+/// Run the compiler with the given space-separated flags
+pub fn run(args: &str, file: &PathBuf) {
     Command::new("target/debug/tigerc")
-        .arg(arg)
+        .args(args.split_whitespace())
         .arg(file)
         .output()
         .unwrap();
 }
 
-/// Return the error locations in a String
-pub fn get_location(text: String) -> (usize, usize) {
-    let re = Regex::new(r"^(\d+):(\d+)").unwrap();
-    let caps = re.captures(&text).unwrap();
-    (usize::from_str(&caps[1]).unwrap(), usize::from_str(&caps[2]).unwrap())
+/// Return the error locations in a String, one per reported error
+pub fn get_locations(text: String) -> Vec<(usize, usize)> {
+    let re = Regex::new(r"(?m)^(\d+):(\d+)").unwrap();
+    re.captures_iter(&text)
+        .map(|caps| (usize::from_str(&caps[1]).unwrap(), usize::from_str(&caps[2]).unwrap()))
+        .collect()
 }
 
-pub fn compare_content(exp: PathBuf, act: PathBuf) -> bool {
-    read_to_string(&exp).chars().filter(|c| !c.is_whitespace()).eq(
-        read_to_string(&act).chars().filter(|c| !c.is_whitespace())
+pub fn compare_content(exp: &PathBuf, act: &PathBuf) -> bool {
+    read_to_string(exp).chars().filter(|c| !c.is_whitespace()).eq(
+        read_to_string(act).chars().filter(|c| !c.is_whitespace())
     )
 }
 
-pub fn compare_location(exp: PathBuf, act: PathBuf) -> bool {
-    get_location(read_to_string(&exp)) == get_location(read_to_string(&act))
+pub fn compare_location(exp: &PathBuf, act: &PathBuf) -> bool {
+    let expected = get_locations(read_to_string(exp));
+    !expected.is_empty() && expected == get_locations(read_to_string(act))
+}
+
+static RUNTIME: Once = Once::new();
+
+/// Build the runtime library, once per test binary
+pub fn build_runtime() {
+    RUNTIME.call_once(|| {
+        let status = Command::new("make")
+            .arg("-C")
+            .arg("runtime")
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "failed to build runtime");
+    });
+}
+
+/// Link the compiled assembly for [file], run it, and write its stdout and
+/// exit status to [actual]
+pub fn execute(file: &Path, actual: &Path) {
+    build_runtime();
+
+    let executable = file.with_extension("exe");
+    let status = Command::new("cc")
+        .arg("-no-pie")
+        .arg(file.with_extension("s"))
+        .arg("runtime/libtiger.a")
+        .arg("-o")
+        .arg(&executable)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "failed to link {}", file.display());
+
+    record(Command::new(&executable), file, actual);
+}
+
+/// Run the program in [file] with an interpreter selected by [flags] instead
+/// of compiling it, and write its stdout and exit status to [actual]
+pub fn interpret(flags: &str, file: &Path, actual: &Path) {
+    let mut command = Command::new("target/debug/tigerc");
+    command.args(flags.split_whitespace()).arg(file);
+    record(command, file, actual);
+}
+
+/// Run [command] with the `.in` file matching [file] as stdin if present,
+/// and write its stdout and exit status to [actual]
+fn record(mut command: Command, file: &Path, actual: &Path) {
+    let input = file.with_extension("in");
+    let stdin = if input.exists() { Stdio::from(File::open(input).unwrap()) } else { Stdio::null() };
+    let output = command
+        .stdin(stdin)
+        .output()
+        .unwrap();
+
+    // Report signals the way the shell does
+    let code = output.status.code()
+        .or_else(|| output.status.signal().map(|signal| 128 + signal))
+        .unwrap();
+
+    let mut stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if !stdout.is_empty() && !stdout.ends_with('\n') { stdout.push('\n'); }
+    stdout.push_str(&format!("exit {}\n", code));
+
+    File::create(actual).unwrap().write_all(stdout.as_bytes()).unwrap();
+}
+
+/// Compile and run, or interpret, and check the output of the test program once per flag
+pub fn check_execution(name: &str, directory: &str, flags: &[&str], sol_ext: &str, act_ext: &str) {
+    let Unit { file, solution, actual } = get_unit(name, directory, sol_ext, act_ext);
+    for flag in flags {
+        if flag.contains("--interpret") {
+            interpret(flag, &file, &actual);
+        } else {
+            run(flag, &file);
+            execute(&file, &actual);
+        }
+        assert_eq!(
+            read_to_string(&solution),
+            read_to_string(&actual),
+            "output of {} differs with {}", name, flag,
+        );
+    }
 }